@@ -1,33 +1,47 @@
-mod utils;
-mod simple_example;
-
 use std::env;
-use utils::plot_bias::plot_bias_vs_alpha_y;
-use simple_example::run_regressions::{run_yxv_regression, run_other_regressions};
+use std::f64::consts::PI;
+use ndarray::array;
+use marketing_iv_methods::utils::plot_bias::plot_bias_vs_alpha_y;
+use marketing_iv_methods::utils::bootstrap::{BootstrapScheme, WildWeights};
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::elasticity::DemandSpecification;
+use marketing_iv_methods::utils::error_distribution::ErrorDistribution;
+use marketing_iv_methods::utils::random_vector_gen::{fresh_seed, seeded_rng, Copula};
+use marketing_iv_methods::utils::summary::Summarize;
+use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_other_regressions, run_iv_regression, run_k_class_regressions, run_jive_regressions, run_plausibly_exogenous_regressions, run_weak_iv_confidence_sets, run_control_function_regression, run_bayesian_iv_regression, run_bayesian_iv_dp_regression, run_bootstrap_iv_regression, run_first_stage_report, run_hausman_tests, run_overid_tests, run_elasticity_regressions};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: Vec<String> = env::args().collect();
 
     if args.len() < 2 {
-        eprintln!("Usage: cargo run -- [bias | bias-size | main-regression | biased-regression]");
+        eprintln!("Usage: cargo run -- [bias | bias-size | main-regression | biased-regression | iv-regression | weak-iv-bias | weak-iv-sets | plausible-iv | control-function | bayes-iv | bayes-iv-dp | bootstrap-iv | first-stage | hausman | overid | elasticity | simultaneity | error-distributions | copula] [classical | hc0 | hc1 | hc2 | hc3 | hac-bartlett | hac-parzen | hac-qs] [seed]");
         return Ok(());
     }
 
     let command = &args[1];
-    let beta = -0.5;
-    let sigma_a = 1.0;
-    let sigma_ex = 1.0;
-    let sigma_ey = 1.0;
-    let intercept = false;
-    let alpha_y = 4.5;
-    let alpha_x = 2.5;
-    let n = 10000;
-    let params = (n, beta, alpha_y, alpha_x, sigma_a, sigma_ex, sigma_ey, intercept);
+    let params = SimulationParams::default();
+    // the IV commands add one instrument to the instrument-free baseline design
+    let iv_params = params.to_builder().pi(vec![1.0]).build()?;
+    let beta = params.beta();
+    let intercept = params.intercept();
+    // standard errors of the printed regressions, classical unless asked otherwise
+    let covariance: CovarianceType = match args.get(2) {
+        Some(arg) => arg.parse()?,
+        None => CovarianceType::Classical,
+    };
+    // every random draw comes from one seeded generator, so a run can be repeated from its seed
+    let seed: u64 = match args.get(3) {
+        Some(arg) => arg.parse()?,
+        None => fresh_seed(),
+    };
+    println!("Seed: {}", seed);
+    let mut rng = seeded_rng(seed);
 
     match command.as_str() {
         "bias" => {
-            let (_, generated_data) = run_yxv_regression(params)?;
-            let (_, _, bias) = run_other_regressions(&generated_data, intercept)?;
+            let (_, generated_data) = run_yxv_regression(&params, &covariance, &mut rng)?;
+            let (_, _, bias) = run_other_regressions(&generated_data, &covariance, intercept)?;
             println!("Bias in x coefficient: {}", bias);
         }
 
@@ -36,11 +50,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             let mut bias_values = vec![];
 
             for alpha_y in (0..=20).map(|i| i as f64 * 0.1 + 1.0) {
-                let params = (n, beta, alpha_y, alpha_x, sigma_a, sigma_ex, sigma_ey, intercept);
+                let params = params.to_builder().alpha_y(alpha_y).build()?;
 
-                match run_yxv_regression(params) {
+                match run_yxv_regression(&params, &covariance, &mut rng) {
                     Ok((_, generated_data)) => {
-                        match run_other_regressions(&generated_data, intercept) {
+                        match run_other_regressions(&generated_data, &covariance, intercept) {
                             Ok((_, _, bias)) => {
                                 alpha_y_values.push(alpha_y);
                                 bias_values.push(bias);
@@ -56,23 +70,331 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         "main-regression" => {
-            let (model, _) = run_yxv_regression(params)?;
-            println!("Main regression (y ~ x + v) coefficients:");
-            println!("{:?}", model.params());
+            let (model, _) = run_yxv_regression(&params, &covariance, &mut rng)?;
+            println!("Main regression (y ~ x + v):");
+            print!("{}", model.summary("y", &["x", "v"])?);
+            println!("True coefficients: x = {}, v = {}", beta, params.alpha_y());
         }
 
         "biased-regression" => {
-            let (_, generated_data) = run_yxv_regression(params)?;
-            let (biased_model, _, _) = run_other_regressions(&generated_data, intercept)?;
-            println!("Biased regression (y ~ x) coefficients:");
-            println!("{:?}", biased_model.params());
+            let (_, generated_data) = run_yxv_regression(&params, &covariance, &mut rng)?;
+            let (biased_model, _, _) = run_other_regressions(&generated_data, &covariance, intercept)?;
+            println!("Biased regression (y ~ x):");
+            print!("{}", biased_model.summary("y", &["x"])?);
+            println!("True coefficient: x = {}", beta);
+        }
+
+        "iv-regression" => {
+            let (_, generated_data) = run_yxv_regression(&iv_params, &covariance, &mut rng)?;
+            let iv_model = run_iv_regression(&generated_data, &covariance, intercept)?;
+            println!("IV regression (y ~ x, instrumented by z):");
+            print!("{}", iv_model.summary("y", &["x"])?);
+            println!("True coefficient: x = {}", beta);
+        }
+
+        "weak-iv-sets" => {
+            // coverage of 95% sets for beta when the first stage is weak
+            let replications = 500;
+            let weak_params = params.to_builder().n(200).pi(vec![0.1; 4]).build()?;
+            let mut covered = [0; 3];
+            let mut unbounded = [0; 3];
+
+            for _ in 0..replications {
+                let (_, generated_data) = run_yxv_regression(&weak_params, &covariance, &mut rng)?;
+                let (wald, ar, clr) = run_weak_iv_confidence_sets(&generated_data, &covariance, 0.95, intercept)?;
+                for (i, set) in [wald, ar, clr].iter().enumerate() {
+                    covered[i] += set.contains(beta) as usize;
+                    unbounded[i] += !set.is_bounded() as usize;
+                }
+            }
+
+            println!("95% confidence sets for the x coefficient over {} replications:", replications);
+            println!("{:>6} {:>10} {:>10}", "", "coverage", "unbounded");
+            for (i, name) in ["Wald", "AR", "CLR"].iter().enumerate() {
+                println!("{:>6} {:>10.3} {:>10.3}", name, covered[i] as f64 / replications as f64, unbounded[i] as f64 / replications as f64);
+            }
+        }
+
+        "plausible-iv" => {
+            // widen the 95% interval for beta as the instruments are allowed to enter y directly
+            let (_, generated_data) = run_yxv_regression(&iv_params, &covariance, &mut rng)?;
+            println!("95% bounds for the x coefficient when |gamma| <= delta:");
+            println!("{:>6} {:>22} {:>22}", "delta", "UCI", "LTZ");
+            for delta in [0.0, 0.1, 0.25, 0.5] {
+                let (uci, ltz) = run_plausibly_exogenous_regressions(&generated_data, delta, 0.95, intercept)?;
+                println!(
+                    "{:>6.2} {:>22} {:>22}",
+                    delta,
+                    format!("[{:.4}, {:.4}]", uci.lower()[0], uci.upper()[0]),
+                    format!("[{:.4}, {:.4}]", ltz.lower()[0], ltz.upper()[0])
+                );
+            }
+        }
+
+        "control-function" => {
+            let (_, generated_data) = run_yxv_regression(&iv_params, &covariance, &mut rng)?;
+            let cf_model = run_control_function_regression(&generated_data, &covariance, intercept)?;
+            println!("Control-function regression (y ~ x + v_hat):");
+            print!("{}", cf_model.summary("y", &["x"])?);
+        }
+
+        "bayes-iv" => {
+            let (_, generated_data) = run_yxv_regression(&iv_params, &covariance, &mut rng)?;
+            let draws = run_bayesian_iv_regression(&generated_data, 2000, 500, intercept, &mut rng)?;
+            println!("Bayesian IV (rivGibbs) posterior:");
+            print!("{}", draws.summary("y", &["x"])?);
+        }
+
+        "bayes-iv-dp" => {
+            let (_, generated_data) = run_yxv_regression(&iv_params, &covariance, &mut rng)?;
+            let draws = run_bayesian_iv_dp_regression(&generated_data, 2000, 500, &mut rng)?;
+            println!("Bayesian IV (rivDP) posterior:");
+            print!("{}", draws.summary("y", &["x"])?);
+        }
+
+        "bootstrap-iv" => {
+            // small-sample 2SLS, where the Wald interval can be far off
+            let small_params = iv_params.to_builder().n(100).build()?;
+            let (_, generated_data) = run_yxv_regression(&small_params, &covariance, &mut rng)?;
+            println!("95% bootstrap intervals for the x coefficient (2SLS, n = 100, truth {}):", beta);
+            println!("{:>10} {:>22} {:>22} {:>22}", "scheme", "percentile", "BCa", "bootstrap-t");
+            let schemes = [
+                ("pairs", BootstrapScheme::Pairs),
+                ("residual", BootstrapScheme::Residual),
+                ("wild", BootstrapScheme::Wild(WildWeights::Rademacher)),
+            ];
+            for (name, scheme) in schemes.iter() {
+                let result = run_bootstrap_iv_regression(&generated_data, &covariance, scheme, 999, intercept, &mut rng)?;
+                let intervals = [result.percentile_interval(0.95)?, result.bca_interval(0.95)?, result.bootstrap_t_interval(0.95)?];
+                let formatted: Vec<String> = intervals.iter().map(|ci| format!("[{:.4}, {:.4}]", ci[[0, 0]], ci[[0, 1]])).collect();
+                println!("{:>10} {:>22} {:>22} {:>22}", name, formatted[0], formatted[1], formatted[2]);
+            }
+            let wre = BootstrapScheme::WildRestrictedEfficient { weights: WildWeights::Rademacher, null_value: beta };
+            let result = run_bootstrap_iv_regression(&generated_data, &covariance, &wre, 999, intercept, &mut rng)?;
+            println!("WRE bootstrap p-value for x coefficient = {}: {:.4}", beta, result.bootstrap_t_p_value(0, beta)?);
+        }
+
+        "first-stage" => {
+            // the default design against a small sample with many weak instruments
+            let (_, generated_data) = run_yxv_regression(&iv_params, &covariance, &mut rng)?;
+            println!("Default design (n = {}, pi = {:?}):", iv_params.n(), iv_params.pi());
+            print!("{}", run_first_stage_report(&generated_data, intercept)?);
+            let weak_params = params.to_builder().n(200).pi(vec![0.1; 4]).build()?;
+            let (_, generated_data) = run_yxv_regression(&weak_params, &covariance, &mut rng)?;
+            println!();
+            println!("Weak design (n = {}, pi = {:?}):", weak_params.n(), weak_params.pi());
+            print!("{}", run_first_stage_report(&generated_data, intercept)?);
+        }
+
+        "hausman" => {
+            // the default design, where x is endogenous, against one where x does not load on v
+            let exogenous_params = iv_params.to_builder().alpha_x(0.0).build()?;
+            println!("Durbin-Wu-Hausman tests of x ({} covariance):", covariance);
+            println!("{:>12} {:>12} {:>12} {:>10} {:>12}", "design", "method", "statistic", "p-value", "2SLS - OLS");
+            for (name, design_params) in [("endogenous", &iv_params), ("exogenous", &exogenous_params)] {
+                let (_, generated_data) = run_yxv_regression(design_params, &covariance, &mut rng)?;
+                let (regression, contrast) = run_hausman_tests(&generated_data, &covariance, intercept)?;
+                for (method, test) in [("regression", &regression), ("contrast", &contrast)] {
+                    println!("{:>12} {:>12} {:>12.4} {:>10.4} {:>12.4}", name, method, test.statistic(), test.p_value(), test.difference()[0]);
+                }
+            }
+        }
+
+        "overid" => {
+            // two instruments that affect y directly, in the ways the tests can and cannot detect
+            let replications = 500;
+            let overid_params = params.to_builder().n(1000).pi(vec![1.0, 1.0]).build()?;
+            let scenarios = [
+                ("both valid", [0.0, 0.0]),
+                ("z2 invalid", [0.0, 0.5]),
+                ("both invalid", [0.5, 0.5]),
+            ];
+            println!("Rejection rates at 5% over {} replications (n = {}, pi = {:?}):", replications, overid_params.n(), overid_params.pi());
+            println!("{:>14} {:>12} {:>10} {:>10} {:>12}", "scenario", "gamma", "Sargan", "Hansen J", "C (z2)");
+            for (name, gamma) in scenarios.iter() {
+                let mut rejections = [0; 3];
+                for _ in 0..replications {
+                    let (_, generated_data) = run_yxv_regression(&overid_params, &covariance, &mut rng)?;
+                    let (sargan, hansen, difference) = run_overid_tests(&generated_data, gamma, &[1], intercept)?;
+                    for (count, test) in rejections.iter_mut().zip([sargan, hansen, difference].iter()) {
+                        *count += (test.p_value() < 0.05) as usize;
+                    }
+                }
+                let rates: Vec<f64> = rejections.iter().map(|r| *r as f64 / replications as f64).collect();
+                println!("{:>14} {:>12} {:>10.3} {:>10.3} {:>12.3}", name, format!("{:?}", gamma), rates[0], rates[1], rates[2]);
+            }
+        }
+
+        "elasticity" => {
+            // read x and y as price and quantity deviations around a price of 10 and a quantity of 20
+            let (_, generated_data) = run_yxv_regression(&iv_params, &covariance, &mut rng)?;
+            println!("Own-price elasticity at the means ({} covariance):", covariance);
+            println!("{:>8} {:>20} {:>20} {:>8} {:>14} {:>14}", "model", "OLS", "2SLS", "truth", "OLS bias", "implied bias");
+            for (name, specification, price_level, quantity_level) in [
+                ("linear", DemandSpecification::Linear, 10.0, 20.0),
+                ("log-log", DemandSpecification::LogLog, 10f64.ln(), 20f64.ln()),
+            ] {
+                let (ols, iv, truth, implied_bias) = run_elasticity_regressions(&generated_data, beta, specification, price_level, quantity_level, &covariance)?;
+                println!(
+                    "{:>8} {:>20} {:>20} {:>8.4} {:>14.4} {:>14.4}",
+                    name,
+                    format!("{:.4} ({:.4})", ols.estimates()[0], ols.std_errors()[0]),
+                    format!("{:.4} ({:.4})", iv.estimates()[0], iv.std_errors()[0]),
+                    truth,
+                    ols.estimates()[0] - truth,
+                    implied_bias
+                );
+            }
+        }
+
+        "simultaneity" => {
+            // no omitted variable in y, so the endogeneity comes only from correlated errors
+            println!("Simultaneity through correlated errors (alpha_y = 0, {} covariance):", covariance);
+            println!("{:>6} {:>10} {:>10} {:>14} {:>10}", "rho", "OLS", "OLS bias", "formula bias", "2SLS");
+            for rho in [-0.6, -0.3, 0.0, 0.3, 0.6] {
+                let rho_params = iv_params.to_builder().alpha_y(0.0).rho(rho).build()?;
+                let (_, generated_data) = run_yxv_regression(&rho_params, &covariance, &mut rng)?;
+                let (ols_model, _, bias) = run_other_regressions(&generated_data, &covariance, intercept)?;
+                let iv_model = run_iv_regression(&generated_data, &covariance, intercept)?;
+                println!(
+                    "{:>6.2} {:>10.4} {:>10.4} {:>14.4} {:>10.4}",
+                    rho,
+                    ols_model.params()[0],
+                    ols_model.params()[0] - beta,
+                    bias,
+                    iv_model.params()[0]
+                );
+            }
+        }
+
+        "error-distributions" => {
+            // the same shape for v, e_x and e_y, with a smaller sample so that the samplers stay quick
+            println!("Estimates of beta = {} under non-normal errors (n = 1000, {} covariance):", beta, covariance);
+            println!("{:>20} {:>10} {:>10} {:>10} {:>10}", "distribution", "OLS", "2SLS", "rivGibbs", "rivDP");
+            let distributions = [
+                ErrorDistribution::Normal,
+                ErrorDistribution::StudentT { df: 3.0 },
+                ErrorDistribution::SkewNormal { shape: 5.0 },
+                ErrorDistribution::LogNormal { log_sd: 1.0 },
+                ErrorDistribution::Laplace,
+                ErrorDistribution::NormalMixture { weights: vec![0.9, 0.1], means: vec![0.0, 0.0], std_devs: vec![1.0, 5.0] },
+            ];
+            for distribution in distributions {
+                let label = distribution.to_string();
+                let shape_params = iv_params.to_builder().n(1000).distribution(distribution).build()?;
+                let (_, generated_data) = run_yxv_regression(&shape_params, &covariance, &mut rng)?;
+                let (ols_model, _, _) = run_other_regressions(&generated_data, &covariance, intercept)?;
+                let iv_model = run_iv_regression(&generated_data, &covariance, intercept)?;
+                let gibbs = run_bayesian_iv_regression(&generated_data, 1000, 200, intercept, &mut rng)?;
+                let dp = run_bayesian_iv_dp_regression(&generated_data, 1000, 200, &mut rng)?;
+                println!(
+                    "{:>20} {:>10.4} {:>10.4} {:>10.4} {:>10.4}",
+                    label,
+                    ols_model.params()[0],
+                    iv_model.params()[0],
+                    gibbs.beta.mean().unwrap_or(f64::NAN),
+                    dp.beta.mean().unwrap_or(f64::NAN)
+                );
+            }
+        }
+
+        "copula" => {
+            // every copula has Kendall's tau 0.5 between each pair of v, e_x and e_y
+            let r = (PI / 4.0).sin();
+            let correlation = array![[1.0, r, r], [r, 1.0, r], [r, r, 1.0]];
+            let copulas = [
+                ("independent", None),
+                ("gaussian", Some(Copula::Gaussian { correlation: correlation.clone() })),
+                ("t(3)", Some(Copula::StudentT { correlation, df: 3.0 })),
+                ("clayton", Some(Copula::Clayton { theta: 2.0 })),
+                ("gumbel", Some(Copula::Gumbel { theta: 2.0 })),
+            ];
+            println!("Estimates of beta = {} with dependent t(5) unobservables (n = 2000, {} covariance):", beta, covariance);
+            println!("{:>12} {:>10} {:>10} {:>10}", "copula", "OLS", "2SLS", "rivGibbs");
+            for (name, copula) in copulas {
+                let mut builder = iv_params.to_builder().n(2000).distribution(ErrorDistribution::StudentT { df: 5.0 });
+                if let Some(copula) = copula {
+                    builder = builder.copula(copula);
+                }
+                let (_, generated_data) = run_yxv_regression(&builder.build()?, &covariance, &mut rng)?;
+                let (ols_model, _, _) = run_other_regressions(&generated_data, &covariance, intercept)?;
+                let iv_model = run_iv_regression(&generated_data, &covariance, intercept)?;
+                let gibbs = run_bayesian_iv_regression(&generated_data, 1000, 200, intercept, &mut rng)?;
+                println!(
+                    "{:>12} {:>10.4} {:>10.4} {:>10.4}",
+                    name,
+                    ols_model.params()[0],
+                    iv_model.params()[0],
+                    gibbs.beta.mean().unwrap_or(f64::NAN)
+                );
+            }
+        }
+
+        "weak-iv-bias" => {
+            // many weak instruments in small samples, where 2SLS drifts towards OLS
+            let replications = 500;
+            let weak_params = params.to_builder().n(200).pi(vec![0.1; 10]).build()?;
+            let mut estimates = vec![vec![]; 7];
+            let mut failures = 0;
+
+            for _ in 0..replications {
+                // a failed replication, e.g. a singular design in a small sample, is skipped rather
+                // than aborting the whole simulation
+                let replication = (|| -> Result<[f64; 7], String> {
+                    let (_, generated_data) = run_yxv_regression(&weak_params, &covariance, &mut rng)?;
+                    let (ols_model, _, _) = run_other_regressions(&generated_data, &covariance, intercept)?;
+                    let (tsls_model, liml_model, fuller_model) = run_k_class_regressions(&generated_data, &covariance, intercept)?;
+                    let (jive1_model, jive2_model, ujive_model) = run_jive_regressions(&generated_data, intercept)?;
+                    Ok([
+                        ols_model.params()[0],
+                        tsls_model.params()[0],
+                        liml_model.params()[0],
+                        fuller_model.params()[0],
+                        jive1_model.params()[0],
+                        jive2_model.params()[0],
+                        ujive_model.params()[0],
+                    ])
+                })();
+                match replication {
+                    Ok(replication) => {
+                        for (values, estimate) in estimates.iter_mut().zip(replication) {
+                            values.push(estimate);
+                        }
+                    }
+                    Err(err_msg) => {
+                        // report the first failure; later ones are only counted
+                        if failures == 0 {
+                            eprintln!("A weak-iv-bias replication failed and was skipped: {}", err_msg);
+                        }
+                        failures += 1;
+                    }
+                }
+            }
+
+            if failures > 0 {
+                println!("Skipped {} of {} replications that failed", failures, replications);
+            }
+            println!("Median bias in x coefficient over {} replications:", replications - failures);
+            for (name, values) in ["OLS", "2SLS", "LIML", "Fuller(1)", "JIVE1", "JIVE2", "UJIVE"].iter().zip(estimates.iter_mut()) {
+                println!("{:>10}: {:.4}", name, median(values) - beta);
+            }
         }
 
         _ => {
             eprintln!("Unrecognized command: {}", command);
-            eprintln!("Usage: cargo run -- [bias | bias-size | main-regression | biased-regression]");
+            eprintln!("Usage: cargo run -- [bias | bias-size | main-regression | biased-regression | iv-regression | weak-iv-bias | weak-iv-sets | plausible-iv | control-function | bayes-iv | bayes-iv-dp | bootstrap-iv | first-stage | hausman | overid | elasticity | simultaneity | error-distributions | copula] [classical | hc0 | hc1 | hc2 | hc3 | hac-bartlett | hac-parzen | hac-qs] [seed]");
         }
     }
 
     Ok(())
 }
+
+/// Returns the median of `values`, sorting them in place, or NaN when `values` is empty.
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    values.sort_by(f64::total_cmp);
+    let len = values.len();
+    (values[(len - 1) / 2] + values[len / 2]) / 2.0
+}
@@ -1,40 +1,56 @@
-use crate::utils::random_vector_gen::random_vector_generate; 
-use ndarray::Array2;
+use crate::utils::error_distribution::{error_vector_generate, ErrorDistribution};
+use crate::utils::random_vector_gen::{copula_generate, multivariate_normal_generate, random_vector_generate, Copula};
+use super::simulation_params::SimulationParams;
+use ndarray::{array, Array1, Array2, Axis};
+use ndarray_rand::rand::Rng;
 use ndarray_rand::rand_distr::Normal;
 
 /// A structure to store the independent variables generated by `ind_var_generate`.
 ///
 /// This structure holds the generated data for the independent variables, including:
 /// - `v`: The generated random variable `v`.
+/// - `z`: The generated instruments, one column per entry of `pi`.
 /// - `e_x`: The generated random error term `e_x`.
-/// - `x`: The calculated independent variable `x` (a linear combination of `v` and `e_x`).
+/// - `x`: The calculated independent variable `x` (a linear combination of `v`, `z` and `e_x`).
 /// - `alpha_x`: The coefficient of `v`.
+/// - `pi`: The coefficients of the instruments `z`.
 /// - `sigma_a`: The standard deviation of `v`.
 /// - `sigma_ex`: The standard deviation of the error term `e_x`.
+/// - `v_distribution`: The shape of the distribution of `v`.
+/// - `ex_distribution`: The shape of the distribution of `e_x`.
 ///
 /// # Example
 /// 
 /// ```rust
 /// use ndarray::Array2;
 /// use marketing_iv_methods::simple_example::generate_vector_data::IndVars;
+/// use marketing_iv_methods::utils::error_distribution::ErrorDistribution;
 ///
 /// let ind_vars = IndVars {
 ///     v: Array2::zeros((5, 1)),
+///     z: Array2::zeros((5, 1)),
 ///     e_x: Array2::zeros((5, 1)),
 ///     x: Array2::zeros((5, 1)),
 ///     alpha_x: 2.5,
+///     pi: vec![1.0],
 ///     sigma_a: 1.0,
 ///     sigma_ex: 1.0,
+///     v_distribution: ErrorDistribution::Normal,
+///     ex_distribution: ErrorDistribution::Normal,
 /// };
 /// ```
 #[derive(Debug)]  // for testing purposes later
 pub struct IndVars {
     pub v: Array2<f64>,
+    pub z: Array2<f64>,
     pub e_x: Array2<f64>,
     pub x: Array2<f64>,
     pub alpha_x: f64,
+    pub pi: Vec<f64>,
     pub sigma_a: f64,
     pub sigma_ex: f64,
+    pub v_distribution: ErrorDistribution,
+    pub ex_distribution: ErrorDistribution,
 }
 
 /// A structure to store the dependent variables generated by `dep_var_generate`.
@@ -45,6 +61,9 @@ pub struct IndVars {
 /// - `alpha_y`: The coefficient of `v` in the regression equation.
 /// - `beta`: The coefficient of `x` in the regression equation.
 /// - `sigma_ey`: The standard deviation of the error term `e_y`.
+/// - `rho`: The correlation between `e_y` and the error term `e_x` of `ind_vars`.
+/// - `ey_distribution`: The shape of the distribution of `e_y`.
+/// - `copula`: The copula that `v`, `e_x` and `e_y` were drawn from, if any.
 /// - `ind_vars`: The independent variables that were used to generate `y`.
 ///
 /// # Example
@@ -53,6 +72,7 @@ pub struct IndVars {
 /// use ndarray::Array2;
 /// use marketing_iv_methods::simple_example::generate_vector_data::IndVars;
 /// use marketing_iv_methods::simple_example::generate_vector_data::DepVars;
+/// use marketing_iv_methods::utils::error_distribution::ErrorDistribution;
 ///
 /// let dep_vars = DepVars {
 ///     y: Array2::zeros((5, 1)),
@@ -60,13 +80,20 @@ pub struct IndVars {
 ///     alpha_y: 1.5,
 ///     beta: -0.5,
 ///     sigma_ey: 1.0,
+///     rho: 0.0,
+///     ey_distribution: ErrorDistribution::Normal,
+///     copula: None,
 ///     ind_vars: IndVars {
 ///         v: Array2::zeros((5, 1)),
+///         z: Array2::zeros((5, 1)),
 ///         e_x: Array2::zeros((5, 1)),
 ///         x: Array2::zeros((5, 1)),
 ///         alpha_x: 2.5,
+///         pi: vec![1.0],
 ///         sigma_a: 1.0,
 ///         sigma_ex: 1.0,
+///         v_distribution: ErrorDistribution::Normal,
+///         ex_distribution: ErrorDistribution::Normal,
 ///     },
 /// };
 /// ```
@@ -77,15 +104,28 @@ pub struct DepVars {
     pub alpha_y: f64,
     pub beta: f64,
     pub sigma_ey: f64,
+    pub rho: f64,
+    pub ey_distribution: ErrorDistribution,
+    pub copula: Option<Copula>,
     pub ind_vars: IndVars,
 }
 
 /// Generates independent variables for a regression model.
 ///
 /// This function generates a set of independent variables, including:
-/// - A random error term `v` with standard deviation `sigma_a`.
-/// - A random error term `e_x` with standard deviation `sigma_ex`.
-/// - The independent variable `x`, which is calculated as `x = alpha_x * v + e_x`.
+/// - A random error term `v` with standard deviation `sigma_a` and the shape `v_distribution`.
+/// - One standard normal instrument per entry of `pi`, stacked as the columns of `z`.
+/// - A random error term `e_x` with standard deviation `sigma_ex` and the shape `ex_distribution`.
+/// - The independent variable `x`, which is calculated as `x = alpha_x * v + pi * z + e_x`.
+///
+/// The instruments are independent of `v` and of every error term, so they shift `x` without
+/// entering `y`. Passing an empty `pi` gives the original design in which `x` is driven only by
+/// `v` and noise. Every draw comes from `rng`, in the order `v`, `e_x`, then the instruments.
+///
+/// The shapes are standardised, so `sigma_a` and `sigma_ex` remain the standard deviations
+/// whatever the distribution, and `ErrorDistribution::Normal` for both gives the normal design.
+/// Heavy-tailed or skewed shapes such as `ErrorDistribution::StudentT` or
+/// `ErrorDistribution::LogNormal` mimic the outliers and skewness of sales data.
 ///
 /// The function returns a `Result` that contains an `IndVars` struct with the generated data.
 ///
@@ -93,13 +133,18 @@ pub struct DepVars {
 /// 
 /// - `n`: The number of observations (i.e., the number of rows in the generated arrays).
 /// - `alpha_x`: The coefficient for the variable `v`.
+/// - `pi`: The coefficients for the instruments `z`. Its length sets the number of instruments.
 /// - `sigma_a`: The standard deviation for the error term `v`.
 /// - `sigma_ex`: The standard deviation for the error term `e_x`.
+/// - `v_distribution`: The shape of the distribution of `v`.
+/// - `ex_distribution`: The shape of the distribution of `e_x`.
+/// - `rng`: The random number generator the data are drawn from.
 ///
 /// # Returns
 /// 
 /// Returns a `Result<IndVars, String>`, where `Ok` contains the generated independent variables, 
-/// and `Err` contains an error message if the inputs are invalid (e.g., non-positive standard deviations).
+/// and `Err` contains an error message if the inputs are invalid (e.g., non-positive standard
+/// deviations or an invalid distribution).
 ///
 /// # Example
 /// 
@@ -107,8 +152,11 @@ pub struct DepVars {
 /// use ndarray::Array2;
 /// use marketing_iv_methods::simple_example::generate_vector_data::IndVars;
 /// use marketing_iv_methods::simple_example::generate_vector_data::ind_var_generate;
+/// use marketing_iv_methods::utils::error_distribution::ErrorDistribution;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
 ///
-/// let result = ind_var_generate(100, 2.5, 1.0, 1.0);
+/// let heavy = ErrorDistribution::StudentT { df: 3.0 };
+/// let result = ind_var_generate(100, 2.5, &[1.0], 1.0, 1.0, &ErrorDistribution::Normal, &heavy, &mut seeded_rng(42));
 /// match result {
 ///     Ok(ind_vars) => {
 ///         println!("{:?}", ind_vars);
@@ -118,7 +166,17 @@ pub struct DepVars {
 ///     }
 /// }
 /// ```
-pub fn ind_var_generate(n: usize, alpha_x: f64, sigma_a: f64, sigma_ex: f64) -> Result<IndVars, String> {
+#[allow(clippy::too_many_arguments)]
+pub fn ind_var_generate<R: Rng + ?Sized>(
+    n: usize,
+    alpha_x: f64,
+    pi: &[f64],
+    sigma_a: f64,
+    sigma_ex: f64,
+    v_distribution: &ErrorDistribution,
+    ex_distribution: &ErrorDistribution,
+    rng: &mut R,
+) -> Result<IndVars, String> {
     // check that sigma values are positive
     if sigma_a <= 0.0 {
         return Err("sigma_a must be positive".into());
@@ -127,25 +185,52 @@ pub fn ind_var_generate(n: usize, alpha_x: f64, sigma_a: f64, sigma_ex: f64) ->
         return Err("sigma_ex must be positive".into());
     }
 
-    // generate the error term
-    let dist_v = Normal::new(0.0, sigma_a).unwrap(); 
-    let dist_e = Normal::new(0.0, sigma_ex).unwrap();
+    // generate the error terms
+    let v = error_vector_generate(n, v_distribution, sigma_a, rng).map_err(|e| format!("Invalid distribution of v: {}", e))?;
+    let e_x = error_vector_generate(n, ex_distribution, sigma_ex, rng).map_err(|e| format!("Invalid distribution of e_x: {}", e))?;
+
+    Ok(ind_vars_from_errors(v, e_x, alpha_x, pi, sigma_a, sigma_ex, (v_distribution, ex_distribution), rng))
+}
 
-    let v = random_vector_generate(n, dist_v);
-    let e_x = random_vector_generate(n, dist_e);
+/// Draws the instruments and builds `x` from the confounder `v` and the error term `e_x`, whose
+/// distributions are recorded in `distributions`.
+#[allow(clippy::too_many_arguments)]
+fn ind_vars_from_errors<R: Rng + ?Sized>(
+    v: Array2<f64>,
+    e_x: Array2<f64>,
+    alpha_x: f64,
+    pi: &[f64],
+    sigma_a: f64,
+    sigma_ex: f64,
+    distributions: (&ErrorDistribution, &ErrorDistribution),
+    rng: &mut R,
+) -> IndVars {
+    let n = v.nrows();
+
+    // generate the instruments, one standard normal column per coefficient in pi
+    let mut z = Array2::zeros((n, pi.len()));
+    for mut column in z.columns_mut() {
+        column.assign(&random_vector_generate(n, Normal::new(0.0, 1.0).unwrap(), rng).column(0));
+    }
+    let pi_vec = Array1::from(pi.to_vec());
+    let z_effect = z.dot(&pi_vec).insert_axis(Axis(1));
 
     // generate the other independent variable, x
-    let x = alpha_x * &v + &e_x; //& helps me borrow v and e_x immutably so I can use them later
+    let x = alpha_x * &v + &z_effect + &e_x; //& helps me borrow v and e_x immutably so I can use them later
 
     // return a data struct holding the values generated
-     Ok(IndVars {
+    IndVars {
         v,
+        z,
         e_x,
         x,
         alpha_x,
+        pi: pi.to_vec(),
         sigma_a,
         sigma_ex,
-    }) 
+        v_distribution: distributions.0.clone(),
+        ex_distribution: distributions.1.clone(),
+    }
 }
 
 /// Generates dependent variables for a regression model using the independent variables.
@@ -156,7 +241,8 @@ pub fn ind_var_generate(n: usize, alpha_x: f64, sigma_a: f64, sigma_ex: f64) ->
 /// - `x` and `v` are the independent variables from the `IndVars` structure.
 /// - `beta` is the coefficient for the independent variable `x`.
 /// - `alpha_y` is the coefficient for the independent variable `v`.
-/// - `e_y` is the error term generated with standard deviation `sigma_ey`.
+/// - `e_y` is the error term generated with standard deviation `sigma_ey` and the shape
+///   `ey_distribution`.
 ///
 /// The function returns a `Result` containing a `DepVars` struct with the generated data.
 ///
@@ -165,12 +251,15 @@ pub fn ind_var_generate(n: usize, alpha_x: f64, sigma_a: f64, sigma_ex: f64) ->
 /// - `beta`: The coefficient for the independent variable `x`.
 /// - `alpha_y`: The coefficient for the independent variable `v`.
 /// - `sigma_ey`: The standard deviation for the error term `e_y`.
+/// - `ey_distribution`: The shape of the distribution of `e_y`.
 /// - `ind_vars`: The independent variables, returned by `ind_var_generate`.
+/// - `rng`: The random number generator the error term is drawn from.
 ///
 /// # Returns
 /// 
 /// Returns a `Result<DepVars, String>`, where `Ok` contains the generated dependent variables, 
-/// and `Err` contains an error message if the inputs are invalid (e.g., non-positive standard deviation).
+/// and `Err` contains an error message if the inputs are invalid (e.g., non-positive standard
+/// deviation or an invalid distribution).
 ///
 /// # Example
 /// 
@@ -178,16 +267,23 @@ pub fn ind_var_generate(n: usize, alpha_x: f64, sigma_a: f64, sigma_ex: f64) ->
 /// use ndarray::Array2;
 /// use marketing_iv_methods::simple_example::generate_vector_data::IndVars;
 /// use marketing_iv_methods::simple_example::generate_vector_data::dep_var_generate;
+/// use marketing_iv_methods::utils::error_distribution::ErrorDistribution;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
 ///
 /// let ind_vars = IndVars {
 ///     v: Array2::zeros((5, 1)),
+///     z: Array2::zeros((5, 1)),
 ///     e_x: Array2::zeros((5, 1)),
 ///     x: Array2::zeros((5, 1)),
 ///     alpha_x: 2.5,
+///     pi: vec![1.0],
 ///     sigma_a: 1.0,
 ///     sigma_ex: 1.0,
+///     v_distribution: ErrorDistribution::Normal,
+///     ex_distribution: ErrorDistribution::Normal,
 /// };
-/// let result = dep_var_generate(-0.5, 1.5, 1.0, ind_vars);
+/// let skewed = ErrorDistribution::SkewNormal { shape: 4.0 };
+/// let result = dep_var_generate(-0.5, 1.5, 1.0, &skewed, ind_vars, &mut seeded_rng(42));
 /// match result {
 ///     Ok(dep_vars) => {
 ///         println!("{:?}", dep_vars);
@@ -197,7 +293,7 @@ pub fn ind_var_generate(n: usize, alpha_x: f64, sigma_a: f64, sigma_ex: f64) ->
 ///     }
 /// }
 /// ```
-pub fn dep_var_generate(beta: f64, alpha_y: f64, sigma_ey: f64, ind_vars: IndVars) -> Result<DepVars, String> {
+pub fn dep_var_generate<R: Rng + ?Sized>(beta: f64, alpha_y: f64, sigma_ey: f64, ey_distribution: &ErrorDistribution, ind_vars: IndVars, rng: &mut R) -> Result<DepVars, String> {
     // check that sigma_ey is positive
     if sigma_ey <= 0.0 {
         return Err("sigma_ey must be positive".into());
@@ -206,21 +302,130 @@ pub fn dep_var_generate(beta: f64, alpha_y: f64, sigma_ey: f64, ind_vars: IndVar
     // get the number of rows from x (or v, since both should be the same size)
     let n = ind_vars.x.nrows();
 
-    // generate the distribution of the error term
-    let dist_e = Normal::new(0.0, sigma_ey).unwrap();
-    
     // generate the error term
-    let e_y = random_vector_generate(n, dist_e);
+    let e_y = error_vector_generate(n, ey_distribution, sigma_ey, rng).map_err(|e| format!("Invalid distribution of e_y: {}", e))?;
+
+    Ok(dep_vars_from_error(beta, alpha_y, sigma_ey, 0.0, ey_distribution, e_y, ind_vars))
+}
 
+/// Builds `y` from the independent variables and the error term `e_y`.
+fn dep_vars_from_error(beta: f64, alpha_y: f64, sigma_ey: f64, rho: f64, ey_distribution: &ErrorDistribution, e_y: Array2<f64>, ind_vars: IndVars) -> DepVars {
     // generate y, the dependent variable
     let y = beta * &ind_vars.x + alpha_y * &ind_vars.v + &e_y;
     // return a data struct holding the values generated
-     Ok(DepVars {
+    DepVars {
         y,
         e_y,
         alpha_y,
         beta,
         sigma_ey,
+        rho,
+        ey_distribution: ey_distribution.clone(),
+        copula: None,
         ind_vars,
-    }) 
+    }
+}
+
+/// Generates the independent and dependent variables together, with correlated error terms.
+///
+/// This function generates the same design as `ind_var_generate` followed by `dep_var_generate`,
+///
+/// `x = alpha_x * v + pi * z + e_x` and `y = beta * x + alpha_y * v + e_y`,
+///
+/// except that the error terms `(e_x, e_y)` are drawn jointly from a bivariate normal
+/// distribution with standard deviations `sigma_ex` and `sigma_ey` and correlation `rho`, using
+/// `multivariate_normal_generate`. A nonzero `rho` is the textbook simultaneity setup of the
+/// Bayesian IV literature: `x` is endogenous even when the omitted `v` does not enter `y`.
+/// With `rho = 0` the design is the one of the two separate generators. The draws come from
+/// `rng`, in the order `v`, the error pairs, then the instruments.
+///
+/// `v` is drawn with the shape of `params.v_distribution()`. When `e_x` or `e_y` has a
+/// non-normal shape the two errors are drawn independently, one after the other, which requires
+/// `rho = 0`.
+///
+/// When `params.copula()` is set, `(v, e_x, e_y)` are instead drawn together from that copula by
+/// `copula_generate`, with the marginals set by the distributions and standard deviations of
+/// `params`. The copula can make the confounder depend on the errors, and give the
+/// unobservables tail dependence that no correlation captures; the OLS bias formula of
+/// `run_other_regressions` then no longer applies.
+///
+/// # Parameters
+///
+/// - `params`: The `SimulationParams` of the design. Only `intercept` is not used.
+/// - `rng`: The random number generator the data are drawn from.
+///
+/// # Returns
+///
+/// Returns a `Result<DepVars, String>`, where `Ok` contains the generated variables, with the
+/// independent variables in `ind_vars`, and `Err` contains an error message if the error
+/// covariance cannot be factorised, `rho` is nonzero with non-normal errors, or the copula is
+/// invalid.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::generate_vector_data::joint_var_generate;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(100).alpha_y(0.0).rho(0.6).build().unwrap();
+/// let dep_vars = joint_var_generate(&params, &mut seeded_rng(42)).unwrap();
+/// assert_eq!(dep_vars.rho, 0.6);
+/// assert_eq!(dep_vars.ind_vars.e_x.shape(), dep_vars.e_y.shape());
+/// ```
+pub fn joint_var_generate<R: Rng + ?Sized>(params: &SimulationParams, rng: &mut R) -> Result<DepVars, String> {
+    let (v, e_x, e_y) = match params.copula() {
+        Some(copula) => copula_unobservables(params, copula, rng)?,
+        None => independent_unobservables(params, rng)?,
+    };
+
+    let distributions = (params.v_distribution(), params.ex_distribution());
+    let ind_vars = ind_vars_from_errors(v, e_x, params.alpha_x(), params.pi(), params.sigma_a(), params.sigma_ex(), distributions, rng);
+    let mut dep_vars = dep_vars_from_error(params.beta(), params.alpha_y(), params.sigma_ey(), params.rho(), params.ey_distribution(), e_y, ind_vars);
+    dep_vars.copula = params.copula().cloned();
+    Ok(dep_vars)
+}
+
+/// The draws of the confounder `v` and the error terms `e_x` and `e_y`.
+type Unobservables = (Array2<f64>, Array2<f64>, Array2<f64>);
+
+/// Draws `v` on its own, then the error terms of both equations, jointly normal when both are
+/// normal.
+fn independent_unobservables<R: Rng + ?Sized>(params: &SimulationParams, rng: &mut R) -> Result<Unobservables, String> {
+    let n = params.n();
+    let (sigma_ex, sigma_ey, rho) = (params.sigma_ex(), params.sigma_ey(), params.rho());
+    let (ex_distribution, ey_distribution) = (params.ex_distribution(), params.ey_distribution());
+
+    // generate the confounder, then the error terms of both equations
+    let v = error_vector_generate(n, params.v_distribution(), params.sigma_a(), rng).map_err(|e| format!("Invalid distribution of v: {}", e))?;
+    let (e_x, e_y) = if *ex_distribution == ErrorDistribution::Normal && *ey_distribution == ErrorDistribution::Normal {
+        // one bivariate normal draw, so that the errors can be correlated
+        let covariance = array![
+            [sigma_ex * sigma_ex, rho * sigma_ex * sigma_ey],
+            [rho * sigma_ex * sigma_ey, sigma_ey * sigma_ey]
+        ];
+        let errors = multivariate_normal_generate(n, &Array1::zeros(2), &covariance, rng)?;
+        (errors.column(0).insert_axis(Axis(1)).to_owned(), errors.column(1).insert_axis(Axis(1)).to_owned())
+    } else if rho == 0.0 {
+        let e_x = error_vector_generate(n, ex_distribution, sigma_ex, rng).map_err(|e| format!("Invalid distribution of e_x: {}", e))?;
+        let e_y = error_vector_generate(n, ey_distribution, sigma_ey, rng).map_err(|e| format!("Invalid distribution of e_y: {}", e))?;
+        (e_x, e_y)
+    } else {
+        return Err("Correlated errors require normal distributions for e_x and e_y".into());
+    };
+    Ok((v, e_x, e_y))
+}
+
+/// Draws `(v, e_x, e_y)` together from `copula`, with the marginals set by `params`.
+fn copula_unobservables<R: Rng + ?Sized>(params: &SimulationParams, copula: &Copula, rng: &mut R) -> Result<Unobservables, String> {
+    let shape_v = params.v_distribution().standardized().map_err(|e| format!("Invalid distribution of v: {}", e))?;
+    let shape_ex = params.ex_distribution().standardized().map_err(|e| format!("Invalid distribution of e_x: {}", e))?;
+    let shape_ey = params.ey_distribution().standardized().map_err(|e| format!("Invalid distribution of e_y: {}", e))?;
+    let quantile_v = |u: f64| params.sigma_a() * shape_v.quantile(u);
+    let quantile_ex = |u: f64| params.sigma_ex() * shape_ex.quantile(u);
+    let quantile_ey = |u: f64| params.sigma_ey() * shape_ey.quantile(u);
+
+    let draws = copula_generate(params.n(), copula, &[&quantile_v, &quantile_ex, &quantile_ey], rng)?;
+    let column = |j: usize| draws.column(j).insert_axis(Axis(1)).to_owned();
+    Ok((column(0), column(1), column(2)))
 }
\ No newline at end of file
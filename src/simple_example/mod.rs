@@ -1,2 +1,3 @@
 pub mod generate_vector_data;
-pub mod run_regressions;
\ No newline at end of file
+pub mod run_regressions;
+pub mod simulation_params;
\ No newline at end of file
@@ -1,7 +1,20 @@
-use crate::utils::linear_regression::run_regression;
-use super::generate_vector_data::{ind_var_generate, dep_var_generate};
-use ndarray::{Array2, Axis, concatenate};
-use linfa_linear::FittedLinearRegression;
+use crate::utils::bootstrap::{run_bootstrap, BootstrapFit, BootstrapResult, BootstrapScheme};
+use crate::utils::covariance::CovarianceType;
+use crate::utils::elasticity::{elasticity_at_means, DemandFit, DemandSpecification, Elasticity};
+use crate::utils::first_stage::{run_first_stage_diagnostics, FirstStageDiagnostics};
+use crate::utils::hausman::{run_hausman, HausmanMethod, HausmanTest};
+use crate::utils::overid::{run_difference_in_sargan, run_overid_test, OveridStatistic, OveridTest};
+use crate::utils::linear_regression::{run_regression, run_2sls, run_liml, run_fuller, FittedOls, FittedIvRegression};
+use crate::utils::jive::{run_jive, JiveVariant, FittedJive};
+use crate::utils::plausibly_exogenous::{gamma_box_grid, run_uci, run_ltz, PlausibleBounds};
+use crate::utils::weak_iv::{anderson_rubin_confidence_set, clr_confidence_set, ConfidenceSet};
+use crate::utils::control_function::{run_control_function, FittedControlFunction};
+use crate::utils::bayesian_iv::{run_riv_gibbs, run_riv_dp, RivGibbsDraws, RivDpDraws};
+use super::generate_vector_data::joint_var_generate;
+use super::simulation_params::SimulationParams;
+use ndarray::{Array1, Array2, Axis, concatenate};
+use ndarray_rand::rand::Rng;
+use statrs::distribution::{ContinuousCDF, Normal};
 
 /// A structure to hold the generated data used in the regression models.
 ///
@@ -9,11 +22,15 @@ use linfa_linear::FittedLinearRegression;
 /// - `y`: The dependent variable `y`.
 /// - `x`: The independent variable `x`.
 /// - `v`: The independent variable `v`.
+/// - `z`: The instruments that shift `x` but do not enter `y`.
 /// - `e_y`: The error term `e_y` for the dependent variable.
 /// - `sigma_ex`: The standard deviation of the error term `e_x`.
+/// - `sigma_ey`: The standard deviation of the error term `e_y`.
+/// - `rho`: The correlation between the error terms `e_x` and `e_y`.
 /// - `sigma_a`: The standard deviation of the independent variable `v`.
 /// - `alpha_x`: The coefficient of the independent variable `v`.
 /// - `alpha_y`: The coefficient of the independent variable `x`.
+/// - `pi`: The coefficients of the instruments `z` in the equation for `x`.
 ///
 /// # Example
 ///
@@ -25,11 +42,15 @@ use linfa_linear::FittedLinearRegression;
 ///     y: Array2::zeros((5, 1)),
 ///     x: Array2::zeros((5, 1)),
 ///     v: Array2::zeros((5, 1)),
+///     z: Array2::zeros((5, 1)),
 ///     e_y: Array2::zeros((5, 1)),
 ///     sigma_ex: 1.0,
+///     sigma_ey: 1.0,
+///     rho: 0.0,
 ///     sigma_a: 1.0,
 ///     alpha_x: 2.0,
 ///     alpha_y: 1.5,
+///     pi: vec![1.0],
 /// };
 /// ```
 #[derive(Debug)]
@@ -37,42 +58,49 @@ pub struct GeneratedData {
     pub y: Array2<f64>,
     pub x: Array2<f64>,
     pub v: Array2<f64>,
+    pub z: Array2<f64>,
     pub e_y: Array2<f64>,
     pub sigma_ex: f64,
+    pub sigma_ey: f64,
+    pub rho: f64,
     pub sigma_a: f64,
     pub alpha_x: f64,
     pub alpha_y: f64,
+    pub pi: Vec<f64>,
 }
 
 /// Runs a regression of `y` on `x` and `v`, and returns the fitted regression model along with the generated data.
 ///
 /// This function generates independent and dependent variables using the given parameters, then runs a regression model of `y` on `x` and `v`. It returns both the fitted regression model and the generated data as a tuple.
 ///
+/// The errors of the `x` and `y` equations are drawn jointly by `joint_var_generate`, with the
+/// correlation `rho` of `params`. All of the data are drawn from `rng`, so a generator from
+/// `seeded_rng` makes the run reproducible: the same seed and parameters give bit-identical
+/// data.
+///
 /// # Parameters
 /// 
-/// - `params`: A tuple containing:
-///   - `n`: The number of observations (number of rows in the generated arrays).
-///   - `beta`: The coefficient for `x` in the dependent variable equation.
-///   - `alpha_y`: The coefficient for `v` in the dependent variable equation.
-///   - `alpha_x`: The coefficient for `v` in the independent variable equation.
-///   - `sigma_a`: The standard deviation of the error term `v`.
-///   - `sigma_ex`: The standard deviation of the error term `e_x`.
-///   - `sigma_ey`: The standard deviation of the error term `e_y`.
-///   - `intercept`: A boolean indicating whether to include an intercept in the regression.
+/// - `params`: The `SimulationParams` of the design, including the instrument coefficients `pi`.
+///   An empty `pi` generates data without instruments.
+/// - `covariance`: The `CovarianceType` used for the standard errors of the regression.
+/// - `rng`: The random number generator the data are drawn from.
 ///
 /// # Returns
 /// 
 /// Returns a `Result` containing:
-/// - `Ok`: A tuple where the first element is the fitted `FittedLinearRegression<f64>` model and the second is the `GeneratedData` struct.
+/// - `Ok`: A tuple where the first element is the fitted `FittedOls` model and the second is the `GeneratedData` struct.
 /// - `Err`: An error message if any of the data generation or regression steps fail.
 ///
 /// # Example
 ///
 /// ```rust
 /// use marketing_iv_methods::simple_example::run_regressions::run_yxv_regression;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
 ///
-/// let params = (100, 0.5, 1.0, 2.0, 1.0, 0.5, 1.0, true);
-/// let result = run_yxv_regression(params);
+/// let params = SimulationParams::builder().n(100).beta(0.5).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).intercept(true).build().unwrap();
+/// let result = run_yxv_regression(&params, &CovarianceType::HC1, &mut seeded_rng(42));
 /// match result {
 ///     Ok((model, data)) => {
 ///         println!("{:?}", model);
@@ -83,30 +111,21 @@ pub struct GeneratedData {
 ///     }
 /// }
 /// ```
-pub fn run_yxv_regression(params: (usize, f64, f64, f64, f64, f64, f64, bool)) -> Result<(FittedLinearRegression<f64>, GeneratedData), String> {
-    let (n, beta, alpha_y, alpha_x, sigma_a, sigma_ex, sigma_ey, intercept) = params;
-
-    let ind_vars = match ind_var_generate(n, alpha_x, sigma_a, sigma_ex) {
-        Ok(vars) => { vars }
-        Err(err_msg) => {
-            eprintln!("Error generating independent variables: {}", err_msg);
-            return Err("Error generating independent variables".into());
-        }
-    };
-    
-    let dep_vars = match dep_var_generate(beta, alpha_y, sigma_ey, ind_vars) {
+pub fn run_yxv_regression<R: Rng + ?Sized>(params: &SimulationParams, covariance: &CovarianceType, rng: &mut R) -> Result<(FittedOls, GeneratedData), String> {
+    // draw the errors of both equations jointly, so that they can be correlated
+    let dep_vars = match joint_var_generate(params, rng) {
         Ok(vars) => { vars }
         Err(err_msg) => {
-            eprintln!("Error generating dependent variables: {}", err_msg);
-            return Err("Error generating dependent variables".into());
+            eprintln!("Error generating the simulated variables: {}", err_msg);
+            return Err("Error generating the simulated variables".into());
         }
     };
-    
+
     // create input data array
     let x = concatenate(Axis(1), &[dep_vars.ind_vars.x.view(), dep_vars.ind_vars.v.view()]).unwrap();
 
     // run the regression
-    let yxv_regression = match run_regression(&x, &dep_vars.y, intercept) {
+    let yxv_regression = match run_regression(&x, &dep_vars.y, covariance, params.intercept()) {
         Ok(vars) => { vars }
         Err(err_msg) => {
             eprintln!("Error in the regression step: {}", err_msg);
@@ -119,11 +138,15 @@ pub fn run_yxv_regression(params: (usize, f64, f64, f64, f64, f64, f64, bool)) -
         y: dep_vars.y,
         x: dep_vars.ind_vars.x,
         v: dep_vars.ind_vars.v,
+        z: dep_vars.ind_vars.z,
         e_y: dep_vars.e_y,
         sigma_ex: dep_vars.ind_vars.sigma_ex,
+        sigma_ey: dep_vars.sigma_ey,
+        rho: dep_vars.rho,
         sigma_a: dep_vars.ind_vars.sigma_a,
         alpha_x: dep_vars.ind_vars.alpha_x,
         alpha_y: dep_vars.alpha_y,
+        pi: dep_vars.ind_vars.pi,
     };
 
     // return the tuple of the regression result and the tuple of generated data
@@ -133,16 +156,23 @@ pub fn run_yxv_regression(params: (usize, f64, f64, f64, f64, f64, f64, bool)) -
 /// Runs additional regression models, including regression of `y` on `x`, and regression of the composite error term (`alpha_y * v + e_y`) on `x`.
 /// It also calculates the bias term using a formula from the Rossi paper.
 ///
+/// The bias is the covariance of `x` with the composite error over the variance of `x`,
+/// `(alpha_y * alpha_x * sigma_a^2 + rho * sigma_ex * sigma_ey) / (alpha_x^2 * sigma_a^2 + sum(pi^2) + sigma_ex^2)`,
+/// so the omitted `v` and correlated errors each add to it, and can offset each other.
+/// The formula assumes that `v` is independent of the errors, which a copula in the
+/// `SimulationParams` can break.
+///
 /// # Parameters
 ///
 /// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `covariance`: The `CovarianceType` used for the standard errors of both regressions.
 /// - `intercept`: A boolean indicating whether to include an intercept in the regression.
 ///
 /// # Returns
 /// 
 /// Returns a `Result` containing:
 /// - `Ok`: A tuple where the first element is the regression of `y` on `x`, the second element is the regression of the composite error term on `x`,
-///         and the third element is the bias term calculated using a formula from the Rossi paper.
+///   and the third element is the bias term calculated using a formula from the Rossi paper.
 /// - `Err`: An error message if any of the regression steps fail.
 ///
 /// # Example
@@ -152,11 +182,14 @@ pub fn run_yxv_regression(params: (usize, f64, f64, f64, f64, f64, f64, bool)) -
 /// use marketing_iv_methods::simple_example::run_regressions::GeneratedData;
 /// use marketing_iv_methods::simple_example::run_regressions::run_yxv_regression;
 /// use marketing_iv_methods::simple_example::run_regressions::run_other_regressions;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
 ///
-/// let params = (100, 0.5, 1.0, 2.0, 1.0, 0.5, 1.0, false);
-/// let (_, generated_data) = run_yxv_regression(params).unwrap();
+/// let params = SimulationParams::builder().n(100).beta(0.5).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
 /// let intercept = false;
-/// let result = run_other_regressions(&generated_data, intercept);
+/// let result = run_other_regressions(&generated_data, &CovarianceType::HC3, intercept);
 /// match result {
 ///     Ok((yx_regression, vex_regression, bias)) => {
 ///         println!("{:?}", yx_regression);
@@ -168,10 +201,10 @@ pub fn run_yxv_regression(params: (usize, f64, f64, f64, f64, f64, f64, bool)) -
 ///     }
 /// }
 /// ```
-pub fn run_other_regressions(generated_data: &GeneratedData, intercept: bool) -> Result<(FittedLinearRegression<f64>, FittedLinearRegression<f64>, f64), String> {
+pub fn run_other_regressions(generated_data: &GeneratedData, covariance: &CovarianceType, intercept: bool) -> Result<(FittedOls, FittedOls, f64), String> {
 
     // run the regression of y on x alone
-    let yx_regression = match run_regression(&generated_data.x, &generated_data.y, intercept) {
+    let yx_regression = match run_regression(&generated_data.x, &generated_data.y, covariance, intercept) {
         Ok(model) => { model }
         Err(err_msg) => {
             eprintln!("Error in the regression of y on x: {}", err_msg);
@@ -183,7 +216,7 @@ pub fn run_other_regressions(generated_data: &GeneratedData, intercept: bool) ->
     let ve = generated_data.alpha_y * &generated_data.v + &generated_data.e_y;
 
     // run the regression of alpha_y*v + e_y on x alone
-    let vex_regression = match run_regression(&generated_data.x, &ve, intercept) {
+    let vex_regression = match run_regression(&generated_data.x, &ve, covariance, intercept) {
         Ok(model) => { model }
         Err(err_msg) => {
             eprintln!("Error in the regression of composite error term on x: {}", err_msg);
@@ -191,9 +224,788 @@ pub fn run_other_regressions(generated_data: &GeneratedData, intercept: bool) ->
         }
     };
 
-    // calculate the bias term from the formula provided in the Rossi paper
-    let bias = generated_data.alpha_y * generated_data.alpha_x * generated_data.sigma_a.powf(2.0)/(generated_data.alpha_x.powf(2.0) * generated_data.sigma_a.powf(2.0) + generated_data.sigma_ex.powf(2.0));
+    // calculate the bias term from the formula provided in the Rossi paper,
+    // where the standard normal instruments add sum(pi^2) to the variance of x
+    // and correlated errors add rho * sigma_ex * sigma_ey to its covariance with the error of y
+    let pi_variance: f64 = generated_data.pi.iter().map(|p| p.powf(2.0)).sum();
+    let error_covariance = generated_data.rho * generated_data.sigma_ex * generated_data.sigma_ey;
+    let bias = (generated_data.alpha_y * generated_data.alpha_x * generated_data.sigma_a.powf(2.0) + error_covariance)/(generated_data.alpha_x.powf(2.0) * generated_data.sigma_a.powf(2.0) + pi_variance + generated_data.sigma_ex.powf(2.0));
 
     // return results
     Ok((yx_regression, vex_regression, bias))
-}
\ No newline at end of file
+}
+
+/// Turns the biased OLS and the 2SLS fits of `y` on `x` into own-price elasticities at the means.
+///
+/// The simulated `x` and `y` are centred on zero, so they are read as deviations of price and
+/// quantity from `price_level` and `quantity_level`: the demand curve is
+/// `q = quantity_level + beta * (p - price_level)` for `Linear` and the same in logs for
+/// `LogLog`. Both regressions include an intercept and use `covariance` for the delta-method
+/// standard errors.
+///
+/// Alongside the two estimates the function returns the true elasticity at the mean price and
+/// the elasticity bias implied by the OLS bias of `run_other_regressions`. The OLS line passes
+/// through the sample means, so its elasticity at the means converges to
+/// `(beta + bias) * p_bar / q_bar`. In a log-log model the elasticity bias is the coefficient
+/// bias itself; in a linear model it is scaled by `p_bar / q_bar`.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `beta`: The true coefficient on `x` used to generate the data.
+/// - `specification`: The `DemandSpecification` the data are read as.
+/// - `price_level`: The price, or log price, around which `x` varies.
+/// - `quantity_level`: The quantity, or log quantity, demanded at `price_level`.
+/// - `covariance`: The `CovarianceType` used for the standard errors.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: A tuple with the OLS elasticity, the 2SLS elasticity, the true elasticity at the mean
+///   price and the elasticity bias implied by the OLS bias.
+/// - `Err`: An error message if the data contain no instruments or a step fails.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_elasticity_regressions};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::elasticity::DemandSpecification;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(200).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_elasticity_regressions(&generated_data, -0.5, DemandSpecification::Linear, 10.0, 20.0, &CovarianceType::HC1) {
+///     Ok((ols, iv, truth, bias)) => println!("{} {} {} {}", ols.estimates()[0], iv.estimates()[0], truth, bias),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_elasticity_regressions(generated_data: &GeneratedData, beta: f64, specification: DemandSpecification, price_level: f64, quantity_level: f64, covariance: &CovarianceType) -> Result<(Elasticity, Elasticity, f64, f64), String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let price = generated_data.x.mapv(|x| x + price_level);
+    let quantity = generated_data.y.mapv(|y| y + quantity_level);
+    let no_controls = Array2::zeros((price.nrows(), 0));
+    let fits = run_regression(&price, &quantity, covariance, true)
+        .map(|model| DemandFit::from(&model))
+        .and_then(|ols| run_2sls(&quantity, &price, &no_controls, &generated_data.z, covariance, true).map(|model| (ols, DemandFit::from(&model))));
+    let elasticities = fits.and_then(|(ols, iv)| {
+        Ok((elasticity_at_means(&ols, &price, 0, specification)?, elasticity_at_means(&iv, &price, 0, specification)?))
+    });
+    let (ols_elasticity, iv_elasticity) = match elasticities {
+        Ok(elasticities) => elasticities,
+        Err(err_msg) => {
+            eprintln!("Error in the elasticities of the demand for x: {}", err_msg);
+            return Err("Error in the elasticities of the demand for x".into());
+        }
+    };
+
+    let (_, _, bias) = run_other_regressions(generated_data, covariance, true)?;
+    let (truth, implied_bias) = match specification {
+        DemandSpecification::LogLog => (beta, bias),
+        DemandSpecification::Linear => {
+            let mean_price = price.mean().unwrap_or(price_level);
+            let mean_quantity = quantity.mean().unwrap_or(quantity_level);
+            let truth = beta * mean_price / (quantity_level + beta * (mean_price - price_level));
+            (truth, (beta + bias) * mean_price / mean_quantity - truth)
+        }
+    };
+
+    Ok((ols_elasticity, iv_elasticity, truth, implied_bias))
+}
+
+/// Runs a two-stage least squares regression of `y` on `x`, using the simulated instruments `z`.
+///
+/// The confounder `v` is left out of the model, exactly as in the biased regression of `y` on `x`
+/// from `run_other_regressions`. Because `z` is independent of `v`, the 2SLS estimate of the
+/// coefficient on `x` is consistent for `beta`, which makes it the natural fit to put next to the
+/// biased one.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `covariance`: The `CovarianceType` used for the standard errors.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regression.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: The fitted `FittedIvRegression` model.
+/// - `Err`: An error message if the data contain no instruments or the regression step fails.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_iv_regression};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(100).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_iv_regression(&generated_data, &CovarianceType::HC1, false) {
+///     Ok(iv_regression) => println!("{:?}", iv_regression.params()),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_iv_regression(generated_data: &GeneratedData, covariance: &CovarianceType, intercept: bool) -> Result<FittedIvRegression, String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    match run_2sls(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, covariance, intercept) {
+        Ok(model) => Ok(model),
+        Err(err_msg) => {
+            eprintln!("Error in the 2SLS regression of y on x: {}", err_msg);
+            Err("Error in the 2SLS regression of y on x".into())
+        }
+    }
+}
+
+/// Runs the 2SLS, LIML and Fuller(1) regressions of `y` on `x`, using the simulated instruments `z`.
+///
+/// All three are members of the k-class family and differ only in how far they move away from
+/// OLS. With weak or many instruments 2SLS is biased towards the OLS estimate, while LIML and
+/// Fuller stay close to `beta` in median, which is the comparison drawn in the Rossi paper.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `covariance`: The `CovarianceType` used for the standard errors of all three fits.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regression.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: A tuple of the fitted 2SLS, LIML and Fuller(1) models, in that order.
+/// - `Err`: An error message if the data contain no instruments or any of the regression steps fail.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_k_class_regressions};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(200).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![0.3, 0.3, 0.3]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_k_class_regressions(&generated_data, &CovarianceType::Classical, false) {
+///     Ok((tsls, liml, fuller)) => {
+///         println!("2SLS: {}, LIML: {}, Fuller: {}", tsls.params()[0], liml.params()[0], fuller.params()[0]);
+///     }
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_k_class_regressions(generated_data: &GeneratedData, covariance: &CovarianceType, intercept: bool) -> Result<(FittedIvRegression, FittedIvRegression, FittedIvRegression), String> {
+    let tsls_regression = run_iv_regression(generated_data, covariance, intercept)?;
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    let liml_regression = match run_liml(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, covariance, intercept) {
+        Ok(model) => { model }
+        Err(err_msg) => {
+            eprintln!("Error in the LIML regression of y on x: {}", err_msg);
+            return Err("Error in the LIML regression of y on x".into());
+        }
+    };
+
+    let fuller_regression = match run_fuller(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, 1.0, covariance, intercept) {
+        Ok(model) => { model }
+        Err(err_msg) => {
+            eprintln!("Error in the Fuller regression of y on x: {}", err_msg);
+            return Err("Error in the Fuller regression of y on x".into());
+        }
+    };
+
+    Ok((tsls_regression, liml_regression, fuller_regression))
+}
+
+/// Runs the JIVE1, JIVE2 and UJIVE regressions of `y` on `x`, using the simulated instruments `z`.
+///
+/// The jackknife estimators build the first-stage fit of each observation without using that
+/// observation, so unlike 2SLS they are not pulled towards OLS when there are many instruments.
+/// They are the many-instrument-robust counterpart to the k-class fits of
+/// `run_k_class_regressions`.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regression.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: A tuple of the fitted JIVE1, JIVE2 and UJIVE models, in that order.
+/// - `Err`: An error message if the data contain no instruments or any of the regression steps fail.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_jive_regressions};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(200).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![0.3, 0.3, 0.3]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_jive_regressions(&generated_data, false) {
+///     Ok((jive1, jive2, ujive)) => {
+///         println!("JIVE1: {}, JIVE2: {}, UJIVE: {}", jive1.params()[0], jive2.params()[0], ujive.params()[0]);
+///     }
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_jive_regressions(generated_data: &GeneratedData, intercept: bool) -> Result<(FittedJive, FittedJive, FittedJive), String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    let mut fits = Vec::with_capacity(3);
+    for variant in [JiveVariant::Jive1, JiveVariant::Jive2, JiveVariant::Ujive] {
+        match run_jive(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, variant, intercept) {
+            Ok(model) => fits.push(model),
+            Err(err_msg) => {
+                eprintln!("Error in the {:?} regression of y on x: {}", variant, err_msg);
+                return Err(format!("Error in the {:?} regression of y on x", variant));
+            }
+        }
+    }
+
+    let ujive_regression = fits.pop().unwrap();
+    let jive2_regression = fits.pop().unwrap();
+    let jive1_regression = fits.pop().unwrap();
+    Ok((jive1_regression, jive2_regression, ujive_regression))
+}
+
+/// Computes the Conley-Hansen-Rossi bounds for the coefficient on `x` when each instrument may
+/// have a direct effect on `y` of up to `delta` in absolute value.
+///
+/// The union-of-confidence-intervals bounds take the support of `gamma` to be the box
+/// `[-delta, delta]` for every instrument, covered by a grid of 11 points per instrument. The
+/// local-to-zero bounds use a prior with the same mean and variance as a uniform distribution on
+/// that box, i.e. mean zero and covariance `delta^2 / 3` times the identity. With `delta = 0`
+/// both reduce to the usual 2SLS confidence interval.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `delta`: The largest direct effect of an instrument on `y` that is considered plausible.
+/// - `confidence`: The nominal coverage of the bounds, between 0 and 1.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regression.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: A tuple of the union-of-confidence-intervals and local-to-zero bounds, in that order.
+/// - `Err`: An error message if the data contain no instruments, `delta` is negative, or any of
+///   the regression steps fail.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_plausibly_exogenous_regressions};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(200).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_plausibly_exogenous_regressions(&generated_data, 0.1, 0.95, false) {
+///     Ok((uci, ltz)) => {
+///         println!("UCI: [{}, {}]", uci.lower()[0], uci.upper()[0]);
+///         println!("LTZ: [{}, {}]", ltz.lower()[0], ltz.upper()[0]);
+///     }
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_plausibly_exogenous_regressions(generated_data: &GeneratedData, delta: f64, confidence: f64, intercept: bool) -> Result<(PlausibleBounds, PlausibleBounds), String> {
+    let n_instruments = generated_data.z.ncols();
+    if n_instruments == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+    if delta < 0.0 {
+        return Err("delta must be non-negative".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    let support = gamma_box_grid(&vec![-delta; n_instruments], &vec![delta; n_instruments], 11)?;
+    let uci_bounds = match run_uci(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, &support, confidence, intercept) {
+        Ok(bounds) => { bounds }
+        Err(err_msg) => {
+            eprintln!("Error in the union-of-confidence-intervals bounds: {}", err_msg);
+            return Err("Error in the union-of-confidence-intervals bounds".into());
+        }
+    };
+
+    let gamma_mean = Array1::zeros(n_instruments);
+    let gamma_cov = Array2::eye(n_instruments) * delta.powf(2.0) / 3.0;
+    let ltz_bounds = match run_ltz(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, &gamma_mean, &gamma_cov, confidence, intercept) {
+        Ok(bounds) => { bounds }
+        Err(err_msg) => {
+            eprintln!("Error in the local-to-zero bounds: {}", err_msg);
+            return Err("Error in the local-to-zero bounds".into());
+        }
+    };
+
+    Ok((uci_bounds, ltz_bounds))
+}
+
+/// Computes the 2SLS Wald, Anderson-Rubin and CLR confidence sets for the coefficient on `x`,
+/// using the simulated instruments `z`.
+///
+/// The Wald set is the usual interval `beta_hat +/- z * se` around the 2SLS estimate, with the
+/// standard error of the chosen `covariance` and a normal critical value. It is only valid when
+/// the instruments are strong. The Anderson-Rubin and CLR sets are valid however weak the instruments
+/// are, and become unbounded when the data cannot rule out arbitrarily large effects. The CLR set
+/// is inverted over a grid of 201 points centred on the 2SLS estimate and spanning ten times the
+/// half-width of the Wald interval on each side (at least one unit).
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `covariance`: The `CovarianceType` used for the standard error of the Wald set.
+/// - `confidence`: The coverage of the sets, between 0 and 1.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regression.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: A tuple of the Wald, Anderson-Rubin and CLR confidence sets, in that order.
+/// - `Err`: An error message if the data contain no instruments or any of the steps fail.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_weak_iv_confidence_sets};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(200).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![0.1, 0.1]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_weak_iv_confidence_sets(&generated_data, &CovarianceType::Classical, 0.95, false) {
+///     Ok((wald, ar, clr)) => println!("Wald: {}, AR: {}, CLR: {}", wald, ar, clr),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_weak_iv_confidence_sets(generated_data: &GeneratedData, covariance: &CovarianceType, confidence: f64, intercept: bool) -> Result<(ConfidenceSet, ConfidenceSet, ConfidenceSet), String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err("confidence must be between 0 and 1".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    let tsls_model = match run_2sls(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, covariance, intercept) {
+        Ok(model) => { model }
+        Err(err_msg) => {
+            eprintln!("Error in the 2SLS Wald interval: {}", err_msg);
+            return Err("Error in the 2SLS Wald interval".into());
+        }
+    };
+    let critical = Normal::new(0.0, 1.0).map_err(|e| format!("Invalid normal distribution: {}", e))?.inverse_cdf(0.5 + confidence / 2.0);
+    let (estimate, std_error) = (tsls_model.params()[0], tsls_model.std_errors()[0]);
+    let (lower, upper) = (estimate - critical * std_error, estimate + critical * std_error);
+    let wald_set = ConfidenceSet::new(vec![(lower, upper)]);
+
+    let ar_set = match anderson_rubin_confidence_set(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, confidence, intercept) {
+        Ok(set) => { set }
+        Err(err_msg) => {
+            eprintln!("Error in the Anderson-Rubin confidence set: {}", err_msg);
+            return Err("Error in the Anderson-Rubin confidence set".into());
+        }
+    };
+
+    let centre = (lower + upper) / 2.0;
+    let span = (10.0 * (upper - lower) / 2.0).max(1.0);
+    let grid = Array1::linspace(centre - span, centre + span, 201);
+    let clr_set = match clr_confidence_set(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, &grid, confidence, intercept) {
+        Ok(set) => { set }
+        Err(err_msg) => {
+            eprintln!("Error in the CLR confidence set: {}", err_msg);
+            return Err("Error in the CLR confidence set".into());
+        }
+    };
+
+    Ok((wald_set, ar_set, clr_set))
+}
+
+/// Bootstraps the 2SLS regression of `y` on `x`, using the simulated instruments `z`.
+///
+/// The model is the one fit by `run_iv_regression`, and the bootstrap-t statistics use the
+/// standard errors of the chosen `covariance`. A heteroskedasticity-robust choice such as `HC1`
+/// keeps them valid under heteroskedasticity. In small samples the bootstrap
+/// distribution of 2SLS is often skewed, which the percentile, BCa and bootstrap-t intervals of
+/// the returned `BootstrapResult` pick up and the usual Wald interval does not.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `covariance`: The `CovarianceType` used for the standard errors of each bootstrap fit.
+/// - `scheme`: The `BootstrapScheme` used to build the bootstrap samples.
+/// - `n_bootstrap`: The number of bootstrap replications.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regression.
+/// - `rng`: The random number generator the bootstrap samples are drawn from.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: The `BootstrapResult` for the coefficient on `x`.
+/// - `Err`: An error message if the data contain no instruments or the bootstrap fails.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_bootstrap_iv_regression};
+/// use marketing_iv_methods::utils::bootstrap::{BootstrapScheme, WildWeights};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(100).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let mut rng = seeded_rng(42);
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+/// match run_bootstrap_iv_regression(&generated_data, &CovarianceType::HC1, &BootstrapScheme::Wild(WildWeights::Rademacher), 199, false, &mut rng) {
+///     Ok(result) => println!("{}", result.percentile_interval(0.95).unwrap()),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_bootstrap_iv_regression<R: Rng + ?Sized>(generated_data: &GeneratedData, covariance: &CovarianceType, scheme: &BootstrapScheme, n_bootstrap: usize, intercept: bool, rng: &mut R) -> Result<BootstrapResult, String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    let tsls = |y: &Array2<f64>, x: &Array2<f64>, w: &Array2<f64>, z: &Array2<f64>| {
+        run_2sls(y, x, w, z, covariance, intercept).map(|model| BootstrapFit::from(&model))
+    };
+    match run_bootstrap(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, tsls, scheme, n_bootstrap, intercept, rng) {
+        Ok(result) => Ok(result),
+        Err(err_msg) => {
+            eprintln!("Error in the bootstrap of the 2SLS regression of y on x: {}", err_msg);
+            Err("Error in the bootstrap of the 2SLS regression of y on x".into())
+        }
+    }
+}
+
+/// Reports the strength of the first stage of `x` on the simulated instruments `z`.
+///
+/// Weak instruments bias 2SLS towards OLS and distort its Wald tests, so the report pairs the
+/// first-stage F, partial R-squared, Cragg-Donald, Kleibergen-Paap and effective F statistics
+/// with the Stock-Yogo critical values for the number of instruments in the design.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `intercept`: A boolean indicating whether to include an intercept in the first stage.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: The `FirstStageDiagnostics` report.
+/// - `Err`: An error message if the data contain no instruments or the diagnostics fail.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_first_stage_report};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(100).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_first_stage_report(&generated_data, false) {
+///     Ok(report) => println!("{}", report),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_first_stage_report(generated_data: &GeneratedData, intercept: bool) -> Result<FirstStageDiagnostics, String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    match run_first_stage_diagnostics(&generated_data.x, &no_controls, &generated_data.z, intercept) {
+        Ok(report) => Ok(report),
+        Err(err_msg) => {
+            eprintln!("Error in the first-stage diagnostics of x on z: {}", err_msg);
+            Err("Error in the first-stage diagnostics of x on z".into())
+        }
+    }
+}
+
+/// Tests whether `x` is endogenous, using the simulated instruments `z`.
+///
+/// In the simulation `x` is endogenous whenever `alpha_x` and `alpha_y` are both nonzero, but in
+/// practice `v` is unobserved and only `y`, `x` and `z` are available. Both forms of the
+/// Durbin-Wu-Hausman test answer the question from those alone: the regression form adds the
+/// first-stage residual of `x` to the regression of `y` on `x`, and the contrast form compares
+/// the 2SLS and OLS estimates of the coefficient on `x`.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `covariance`: The `CovarianceType` used for the variance in the test statistics.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regressions.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: A tuple with the regression-based and contrast-based `HausmanTest`.
+/// - `Err`: An error message if the data contain no instruments or either test fails.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_hausman_tests};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(100).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_hausman_tests(&generated_data, &CovarianceType::HC1, false) {
+///     Ok((regression, contrast)) => println!("{} {}", regression.p_value(), contrast.p_value()),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_hausman_tests(generated_data: &GeneratedData, covariance: &CovarianceType, intercept: bool) -> Result<(HausmanTest, HausmanTest), String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    let mut tests = Vec::with_capacity(2);
+    for method in [HausmanMethod::Regression, HausmanMethod::Contrast] {
+        match run_hausman(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, method, covariance, intercept) {
+            Ok(test) => tests.push(test),
+            Err(err_msg) => {
+                eprintln!("Error in the {:?} Hausman test of x: {}", method, err_msg);
+                return Err(format!("Error in the {:?} Hausman test of x", method));
+            }
+        }
+    }
+    let contrast = tests.pop().unwrap();
+    let regression = tests.pop().unwrap();
+    Ok((regression, contrast))
+}
+
+/// Tests the overidentifying restrictions of the simulated instruments, after letting them
+/// affect `y` directly.
+///
+/// The simulated instruments are valid, so the violation is added here: `y` is replaced by
+/// `y + z * gamma`, which makes instrument `j` invalid whenever `gamma[j]` is nonzero. The
+/// Sargan and Hansen J tests can only detect violations that the instruments disagree about,
+/// and the difference-in-Sargan test only detects a violation of the `suspect` instruments when
+/// the others are valid. When `gamma` is proportional to `pi` every instrument points to the
+/// same biased estimate and none of the tests has any power, which is the heart of the Rossi
+/// critique of overidentification tests.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `gamma`: The direct effects of the instruments on `y`, one per instrument.
+/// - `suspect`: The instruments whose validity the difference-in-Sargan test checks.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regressions.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: A tuple with the Sargan test, the Hansen J test and the Hansen-based
+///   difference-in-Sargan test of the `suspect` instruments.
+/// - `Err`: An error message if `gamma` does not match the instruments or any test fails.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_overid_tests};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(200).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0, 1.0]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_overid_tests(&generated_data, &[0.0, 0.2], &[1], false) {
+///     Ok((sargan, hansen, difference)) => println!("{} {} {}", sargan.p_value(), hansen.p_value(), difference.p_value()),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_overid_tests(generated_data: &GeneratedData, gamma: &[f64], suspect: &[usize], intercept: bool) -> Result<(OveridTest, OveridTest, OveridTest), String> {
+    if gamma.len() != generated_data.z.ncols() {
+        return Err(format!("gamma has {} entries for {} instruments", gamma.len(), generated_data.z.ncols()));
+    }
+
+    let gamma = Array1::from(gamma.to_vec()).insert_axis(Axis(1));
+    let y = &generated_data.y + &generated_data.z.dot(&gamma);
+    let no_controls = Array2::zeros((y.nrows(), 0));
+    let results = (
+        run_overid_test(&y, &generated_data.x, &no_controls, &generated_data.z, OveridStatistic::Sargan, intercept),
+        run_overid_test(&y, &generated_data.x, &no_controls, &generated_data.z, OveridStatistic::HansenJ, intercept),
+        run_difference_in_sargan(&y, &generated_data.x, &no_controls, &generated_data.z, suspect, OveridStatistic::HansenJ, intercept),
+    );
+    match results {
+        (Ok(sargan), Ok(hansen), Ok(difference)) => Ok((sargan, hansen, difference)),
+        (Err(err_msg), _, _) | (_, Err(err_msg), _) | (_, _, Err(err_msg)) => {
+            eprintln!("Error in the overidentification tests of z: {}", err_msg);
+            Err("Error in the overidentification tests of z".into())
+        }
+    }
+}
+
+/// Runs a control-function regression of `y` on `x`, using the simulated instruments `z`.
+///
+/// `x` is first regressed on `z`, and the first-stage residual is added to the regression of `y`
+/// on `x`. The residual stands in for the omitted confounder `v`, so the coefficient on `x` is
+/// consistent for `beta`, and the t-test on the residual's coefficient tests whether `x` is
+/// endogenous.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `covariance`: The `CovarianceType` used for the corrected standard errors.
+/// - `intercept`: A boolean indicating whether to include an intercept in the regression.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: The fitted `FittedControlFunction` model.
+/// - `Err`: An error message if the data contain no instruments or the regression step fails.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_control_function_regression};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(100).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut seeded_rng(42)).unwrap();
+/// match run_control_function_regression(&generated_data, &CovarianceType::Classical, false) {
+///     Ok(cf_regression) => println!("{:?}", cf_regression.params()),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_control_function_regression(generated_data: &GeneratedData, covariance: &CovarianceType, intercept: bool) -> Result<FittedControlFunction, String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    match run_control_function(&generated_data.y, &generated_data.x, &no_controls, &generated_data.z, covariance, intercept) {
+        Ok(model) => Ok(model),
+        Err(err_msg) => {
+            eprintln!("Error in the control-function regression of y on x: {}", err_msg);
+            Err("Error in the control-function regression of y on x".into())
+        }
+    }
+}
+
+/// Runs the Bayesian instrumental-variables Gibbs sampler on the simulated data, using the
+/// instruments `z` and the default conjugate prior.
+///
+/// As in `run_iv_regression`, the confounder `v` is left out of the model. Its effect is absorbed
+/// by the correlation between the errors of the `x` and `y` equations, which is what the sampler
+/// models explicitly.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `n_draws`: The number of posterior draws to keep.
+/// - `burn_in`: The number of initial draws to discard.
+/// - `intercept`: A boolean indicating whether to include an intercept in both equations.
+/// - `rng`: The random number generator the draws are taken from.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: The `RivGibbsDraws` from the sampler.
+/// - `Err`: An error message if the data contain no instruments or the sampler fails.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_bayesian_iv_regression};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(100).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let mut rng = seeded_rng(42);
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+/// match run_bayesian_iv_regression(&generated_data, 200, 50, false, &mut rng) {
+///     Ok(draws) => println!("posterior mean of beta: {}", draws.beta.mean().unwrap()),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_bayesian_iv_regression<R: Rng + ?Sized>(generated_data: &GeneratedData, n_draws: usize, burn_in: usize, intercept: bool, rng: &mut R) -> Result<RivGibbsDraws, String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    match run_riv_gibbs(&generated_data.y, &generated_data.x, &generated_data.z, &no_controls, None, n_draws, burn_in, intercept, rng) {
+        Ok(draws) => Ok(draws),
+        Err(err_msg) => {
+            eprintln!("Error in the Bayesian IV sampler: {}", err_msg);
+            Err("Error in the Bayesian IV sampler".into())
+        }
+    }
+}
+
+/// Runs the Bayesian instrumental-variables sampler with Dirichlet-process mixture errors on the
+/// simulated data, using the instruments `z` and the default prior.
+///
+/// The mixture absorbs any intercept, so unlike `run_bayesian_iv_regression` there is no
+/// `intercept` flag.
+///
+/// # Parameters
+///
+/// - `generated_data`: A reference to a `GeneratedData` struct containing the generated data for regression.
+/// - `n_draws`: The number of posterior draws to keep.
+/// - `burn_in`: The number of initial draws to discard.
+/// - `rng`: The random number generator the draws are taken from.
+///
+/// # Returns
+///
+/// Returns a `Result` containing:
+/// - `Ok`: The `RivDpDraws` from the sampler.
+/// - `Err`: An error message if the data contain no instruments or the sampler fails.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_bayesian_iv_dp_regression};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let params = SimulationParams::builder().n(100).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+/// let mut rng = seeded_rng(42);
+/// let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+/// match run_bayesian_iv_dp_regression(&generated_data, 100, 20, &mut rng) {
+///     Ok(draws) => println!("posterior mean of beta: {}", draws.beta.mean().unwrap()),
+///     Err(err) => println!("Error: {}", err),
+/// }
+/// ```
+pub fn run_bayesian_iv_dp_regression<R: Rng + ?Sized>(generated_data: &GeneratedData, n_draws: usize, burn_in: usize, rng: &mut R) -> Result<RivDpDraws, String> {
+    if generated_data.z.ncols() == 0 {
+        return Err("The generated data contain no instruments".into());
+    }
+
+    let no_controls = Array2::zeros((generated_data.y.nrows(), 0));
+    match run_riv_dp(&generated_data.y, &generated_data.x, &generated_data.z, &no_controls, None, n_draws, burn_in, rng) {
+        Ok(draws) => Ok(draws),
+        Err(err_msg) => {
+            eprintln!("Error in the Dirichlet-process Bayesian IV sampler: {}", err_msg);
+            Err("Error in the Dirichlet-process Bayesian IV sampler".into())
+        }
+    }
+}
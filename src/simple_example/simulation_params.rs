@@ -0,0 +1,345 @@
+use crate::utils::error_distribution::ErrorDistribution;
+use crate::utils::random_vector_gen::Copula;
+
+/// The parameters of the simulated design used by `run_yxv_regression`.
+///
+/// The data are generated as
+///
+/// `x = alpha_x * v + z * pi + e_x` and `y = beta * x + alpha_y * v + e_y`,
+///
+/// where `v` is the unobserved confounder with standard deviation `sigma_a`, `e_x` and `e_y` are
+/// the errors with standard deviations `sigma_ex` and `sigma_ey` and correlation `rho`, and `z`
+/// holds one standard normal instrument per entry of `pi`. `v`, `e_x` and `e_y` are normal
+/// unless another `ErrorDistribution` is set for them. `x` is endogenous when the omitted `v` enters both
+/// equations, when `rho` is nonzero, or both.
+///
+/// This structure holds:
+/// - `n`: The number of observations.
+/// - `beta`: The coefficient of `x` in the equation for `y`.
+/// - `alpha_y`: The coefficient of `v` in the equation for `y`.
+/// - `alpha_x`: The coefficient of `v` in the equation for `x`.
+/// - `sigma_a`: The standard deviation of `v`.
+/// - `sigma_ex`: The standard deviation of the error term `e_x`.
+/// - `sigma_ey`: The standard deviation of the error term `e_y`.
+/// - `rho`: The correlation between the error terms `e_x` and `e_y`. It must be zero when
+///   either error term is not normal.
+/// - `intercept`: Whether the regressions include an intercept.
+/// - `pi`: The coefficients of the instruments `z` in the equation for `x`. An empty `pi` gives
+///   data without instruments.
+/// - `v_distribution`, `ex_distribution`, `ey_distribution`: The shapes of the distributions of
+///   `v`, `e_x` and `e_y`, standardised so that the `sigma` fields remain their standard
+///   deviations.
+/// - `copula`: The copula of `(v, e_x, e_y)`, in that order, or `None` for a confounder that is
+///   independent of the errors. A copula sets all the dependence, so `rho` must then be zero.
+///
+/// The fields are private, so every `SimulationParams` has passed the validation in
+/// `SimulationParamsBuilder::build`. The defaults are the baseline design run by `main.rs`:
+/// `n = 10000`, `beta = -0.5`, `alpha_y = 4.5`, `alpha_x = 2.5`, unit standard deviations,
+/// uncorrelated normal errors, no copula, no intercept and no instruments. The IV commands add
+/// instruments with `to_builder().pi(...)`.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+///
+/// let params = SimulationParams::builder().n(500).alpha_y(1.0).pi(vec![0.5, 0.5]).build().unwrap();
+/// assert_eq!(params.n(), 500);
+/// assert_eq!(params.alpha_x(), 2.5);
+/// assert_eq!(params.pi(), &[0.5, 0.5]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationParams {
+    n: usize,
+    beta: f64,
+    alpha_y: f64,
+    alpha_x: f64,
+    sigma_a: f64,
+    sigma_ex: f64,
+    sigma_ey: f64,
+    rho: f64,
+    intercept: bool,
+    pi: Vec<f64>,
+    v_distribution: ErrorDistribution,
+    ex_distribution: ErrorDistribution,
+    ey_distribution: ErrorDistribution,
+    copula: Option<Copula>,
+}
+
+impl SimulationParams {
+    /// A builder starting from the default parameters.
+    pub fn builder() -> SimulationParamsBuilder {
+        SimulationParamsBuilder::default()
+    }
+
+    /// A builder starting from these parameters, to derive a variant of the design.
+    pub fn to_builder(&self) -> SimulationParamsBuilder {
+        SimulationParamsBuilder { params: self.clone() }
+    }
+
+    /// The number of observations.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The coefficient of `x` in the equation for `y`.
+    pub fn beta(&self) -> f64 {
+        self.beta
+    }
+
+    /// The coefficient of `v` in the equation for `y`.
+    pub fn alpha_y(&self) -> f64 {
+        self.alpha_y
+    }
+
+    /// The coefficient of `v` in the equation for `x`.
+    pub fn alpha_x(&self) -> f64 {
+        self.alpha_x
+    }
+
+    /// The standard deviation of `v`.
+    pub fn sigma_a(&self) -> f64 {
+        self.sigma_a
+    }
+
+    /// The standard deviation of the error term `e_x`.
+    pub fn sigma_ex(&self) -> f64 {
+        self.sigma_ex
+    }
+
+    /// The standard deviation of the error term `e_y`.
+    pub fn sigma_ey(&self) -> f64 {
+        self.sigma_ey
+    }
+
+    /// The correlation between the error terms `e_x` and `e_y`.
+    pub fn rho(&self) -> f64 {
+        self.rho
+    }
+
+    /// Whether the regressions include an intercept.
+    pub fn intercept(&self) -> bool {
+        self.intercept
+    }
+
+    /// The coefficients of the instruments in the equation for `x`.
+    pub fn pi(&self) -> &[f64] {
+        &self.pi
+    }
+
+    /// The shape of the distribution of `v`.
+    pub fn v_distribution(&self) -> &ErrorDistribution {
+        &self.v_distribution
+    }
+
+    /// The shape of the distribution of the error term `e_x`.
+    pub fn ex_distribution(&self) -> &ErrorDistribution {
+        &self.ex_distribution
+    }
+
+    /// The shape of the distribution of the error term `e_y`.
+    pub fn ey_distribution(&self) -> &ErrorDistribution {
+        &self.ey_distribution
+    }
+
+    /// The copula of `v`, `e_x` and `e_y`, if any.
+    pub fn copula(&self) -> Option<&Copula> {
+        self.copula.as_ref()
+    }
+
+    /// Every problem with the parameters, in field order.
+    fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        if self.n == 0 {
+            problems.push("n must be positive".to_string());
+        }
+        for (name, value) in [("beta", self.beta), ("alpha_y", self.alpha_y), ("alpha_x", self.alpha_x)] {
+            if !value.is_finite() {
+                problems.push(format!("{} must be finite, got {}", name, value));
+            }
+        }
+        for (name, value) in [("sigma_a", self.sigma_a), ("sigma_ex", self.sigma_ex), ("sigma_ey", self.sigma_ey)] {
+            if !(value > 0.0 && value.is_finite()) {
+                problems.push(format!("{} must be positive and finite, got {}", name, value));
+            }
+        }
+        if !(self.rho > -1.0 && self.rho < 1.0) {
+            problems.push(format!("rho must be strictly between -1 and 1, got {}", self.rho));
+        }
+        for (j, value) in self.pi.iter().enumerate() {
+            if !value.is_finite() {
+                problems.push(format!("pi[{}] must be finite, got {}", j, value));
+            }
+        }
+        let distributions = [("v", &self.v_distribution), ("e_x", &self.ex_distribution), ("e_y", &self.ey_distribution)];
+        for (name, distribution) in distributions {
+            if let Err(err) = distribution.validate() {
+                problems.push(format!("the distribution of {} is invalid: {}", name, err));
+            }
+        }
+        let normal_errors = self.ex_distribution == ErrorDistribution::Normal && self.ey_distribution == ErrorDistribution::Normal;
+        if let Some(copula) = &self.copula {
+            if let Err(err) = copula.validate(3) {
+                problems.push(format!("the copula is invalid: {}", err));
+            }
+            if self.rho != 0.0 {
+                problems.push("rho must be zero when a copula is set".to_string());
+            }
+        } else if self.rho != 0.0 && !normal_errors {
+            problems.push("rho must be zero unless e_x and e_y are normal".to_string());
+        }
+        problems
+    }
+}
+
+impl Default for SimulationParams {
+    fn default() -> Self {
+        SimulationParams {
+            n: 10000,
+            beta: -0.5,
+            alpha_y: 4.5,
+            alpha_x: 2.5,
+            sigma_a: 1.0,
+            sigma_ex: 1.0,
+            sigma_ey: 1.0,
+            rho: 0.0,
+            intercept: false,
+            pi: vec![],
+            v_distribution: ErrorDistribution::Normal,
+            ex_distribution: ErrorDistribution::Normal,
+            ey_distribution: ErrorDistribution::Normal,
+            copula: None,
+        }
+    }
+}
+
+/// Builds a validated `SimulationParams`, starting from the defaults or from existing
+/// parameters.
+///
+/// Each setter replaces one field and leaves the others alone, so only the fields that differ
+/// from the starting point need to be named. Nothing is checked until `build`, which reports
+/// every invalid field at once.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+///
+/// let result = SimulationParams::builder().n(0).sigma_ey(-1.0).build();
+/// let message = result.unwrap_err();
+/// assert!(message.contains("n must be positive"));
+/// assert!(message.contains("sigma_ey must be positive"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SimulationParamsBuilder {
+    params: SimulationParams,
+}
+
+impl SimulationParamsBuilder {
+    /// Sets the number of observations.
+    pub fn n(mut self, n: usize) -> Self {
+        self.params.n = n;
+        self
+    }
+
+    /// Sets the coefficient of `x` in the equation for `y`.
+    pub fn beta(mut self, beta: f64) -> Self {
+        self.params.beta = beta;
+        self
+    }
+
+    /// Sets the coefficient of `v` in the equation for `y`.
+    pub fn alpha_y(mut self, alpha_y: f64) -> Self {
+        self.params.alpha_y = alpha_y;
+        self
+    }
+
+    /// Sets the coefficient of `v` in the equation for `x`.
+    pub fn alpha_x(mut self, alpha_x: f64) -> Self {
+        self.params.alpha_x = alpha_x;
+        self
+    }
+
+    /// Sets the standard deviation of `v`.
+    pub fn sigma_a(mut self, sigma_a: f64) -> Self {
+        self.params.sigma_a = sigma_a;
+        self
+    }
+
+    /// Sets the standard deviation of the error term `e_x`.
+    pub fn sigma_ex(mut self, sigma_ex: f64) -> Self {
+        self.params.sigma_ex = sigma_ex;
+        self
+    }
+
+    /// Sets the standard deviation of the error term `e_y`.
+    pub fn sigma_ey(mut self, sigma_ey: f64) -> Self {
+        self.params.sigma_ey = sigma_ey;
+        self
+    }
+
+    /// Sets the correlation between the error terms `e_x` and `e_y`.
+    pub fn rho(mut self, rho: f64) -> Self {
+        self.params.rho = rho;
+        self
+    }
+
+    /// Sets whether the regressions include an intercept.
+    pub fn intercept(mut self, intercept: bool) -> Self {
+        self.params.intercept = intercept;
+        self
+    }
+
+    /// Sets the instrument coefficients, one instrument per entry.
+    pub fn pi(mut self, pi: Vec<f64>) -> Self {
+        self.params.pi = pi;
+        self
+    }
+
+    /// Sets the shape of the distribution of `v`.
+    pub fn v_distribution(mut self, distribution: ErrorDistribution) -> Self {
+        self.params.v_distribution = distribution;
+        self
+    }
+
+    /// Sets the shape of the distribution of the error term `e_x`.
+    pub fn ex_distribution(mut self, distribution: ErrorDistribution) -> Self {
+        self.params.ex_distribution = distribution;
+        self
+    }
+
+    /// Sets the shape of the distribution of the error term `e_y`.
+    pub fn ey_distribution(mut self, distribution: ErrorDistribution) -> Self {
+        self.params.ey_distribution = distribution;
+        self
+    }
+
+    /// Sets the copula of `(v, e_x, e_y)`, in that order.
+    pub fn copula(mut self, copula: Copula) -> Self {
+        self.params.copula = Some(copula);
+        self
+    }
+
+    /// Sets the same shape for the distributions of `v`, `e_x` and `e_y`.
+    pub fn distribution(self, distribution: ErrorDistribution) -> Self {
+        self.v_distribution(distribution.clone()).ex_distribution(distribution.clone()).ey_distribution(distribution)
+    }
+
+    /// Validates the parameters.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(SimulationParams)` if every field is valid.
+    /// * `Err(String)` listing every invalid field if `n` is zero, a coefficient is not finite,
+    ///   a standard deviation is not positive and finite, `rho` is not strictly between -1
+    ///   and 1 or is nonzero with non-normal errors or a copula, or a distribution or the copula
+    ///   is invalid.
+    pub fn build(self) -> Result<SimulationParams, String> {
+        let problems = self.params.problems();
+        if problems.is_empty() {
+            Ok(self.params)
+        } else {
+            Err(format!("Invalid simulation parameters: {}", problems.join("; ")))
+        }
+    }
+}
@@ -0,0 +1,692 @@
+use super::matrix_ops::{add_intercept, hstack, solve_spd};
+use super::posterior_draws::{draw_inverse_wishart, draw_normal_from_precision};
+use ndarray::{s, stack, Array1, Array2, Array3, Axis};
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::{Beta, ChiSquared, Distribution, Gamma, StandardNormal};
+
+/// The conjugate prior used by `run_riv_gibbs`.
+///
+/// This structure holds:
+/// - `md`, `ad`: The prior mean and precision of the first-stage coefficients `delta`, so that
+///   `delta ~ N(md, ad^{-1})`.
+/// - `mbg`, `abg`: The prior mean and precision of the structural coefficients `(beta, gamma)`,
+///   so that `(beta, gamma) ~ N(mbg, abg^{-1})`.
+/// - `nu`, `v`: The degrees of freedom and scale of the inverse Wishart prior on the 2x2 error
+///   covariance, `Sigma ~ IW(nu, v)`.
+///
+/// `delta` has one entry per instrument, then one per exogenous control, then the intercept;
+/// `(beta, gamma)` has `beta` first, then one entry per exogenous control, then the intercept.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::utils::bayesian_iv::RivGibbsPrior;
+///
+/// // one instrument, no controls, with an intercept in both equations
+/// let prior = RivGibbsPrior::default_for(2, 2);
+/// assert_eq!(prior.nu, 3.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RivGibbsPrior {
+    pub md: Array1<f64>,
+    pub ad: Array2<f64>,
+    pub mbg: Array1<f64>,
+    pub abg: Array2<f64>,
+    pub nu: f64,
+    pub v: Array2<f64>,
+}
+
+impl RivGibbsPrior {
+    /// The default prior of bayesm's `rivGibbs`: zero means, precisions of `0.01 * I` for both
+    /// coefficient blocks, `nu = 3` and `v = nu * I`.
+    ///
+    /// `n_delta` and `n_beta_gamma` are the lengths of `delta` and `(beta, gamma)`.
+    pub fn default_for(n_delta: usize, n_beta_gamma: usize) -> Self {
+        let nu = 3.0;
+        RivGibbsPrior {
+            md: Array1::zeros(n_delta),
+            ad: 0.01 * Array2::eye(n_delta),
+            mbg: Array1::zeros(n_beta_gamma),
+            abg: 0.01 * Array2::eye(n_beta_gamma),
+            nu,
+            v: nu * Array2::eye(2),
+        }
+    }
+}
+
+/// The posterior draws returned by `run_riv_gibbs`, one row (or entry) per kept draw.
+///
+/// This structure holds:
+/// - `beta`: The draws of the coefficient on the endogenous regressor `x`.
+/// - `gamma`: The draws of the coefficients on the exogenous controls in the `y` equation,
+///   followed by the intercept when one is included.
+/// - `delta`: The draws of the first-stage coefficients: instruments, exogenous controls, then
+///   the intercept when one is included.
+/// - `sigma`: The draws of the 2x2 covariance of `(e_x, e_y)`, stacked along the first axis.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::{Array1, Array2, Array3};
+/// use marketing_iv_methods::utils::bayesian_iv::RivGibbsDraws;
+///
+/// let draws = RivGibbsDraws {
+///     beta: Array1::zeros(10),
+///     gamma: Array2::zeros((10, 1)),
+///     delta: Array2::zeros((10, 2)),
+///     sigma: Array3::zeros((10, 2, 2)),
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct RivGibbsDraws {
+    pub beta: Array1<f64>,
+    pub gamma: Array2<f64>,
+    pub delta: Array2<f64>,
+    pub sigma: Array3<f64>,
+}
+
+/// Runs a Bayesian instrumental-variables Gibbs sampler for a single endogenous regressor.
+///
+/// The model is the two-equation system of Rossi, Allenby and McCulloch (2005), implemented in
+/// bayesm's `rivGibbs`:
+///
+/// `x = z * delta_z + w * delta_w + e_x`
+///
+/// `y = beta * x + w * gamma + e_y`,
+///
+/// with `(e_x, e_y) ~ N(0, Sigma)`. A nonzero off-diagonal in `Sigma` is what makes `x`
+/// endogenous. With conjugate priors, each iteration draws in turn:
+///
+/// 1. `(beta, gamma)` given `delta` and `Sigma`, from the regression of
+///    `y - (s_12 / s_11) * e_x` on `[x, w]` with variance `s_22 - s_12^2 / s_11`;
+/// 2. `delta` given `(beta, gamma)` and `Sigma`, from the reduced form
+///    `(x, y - w * gamma) = (1, beta)' z * delta + (e_x, beta * e_x + e_y)`, whitened with the
+///    Cholesky factor of its error covariance;
+/// 3. `Sigma` given the coefficients, from the inverse Wishart posterior built on the residuals.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x` - A 2D array (`Array2<f64>`) with a single column, representing the endogenous regressor.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `w` - A 2D array (`Array2<f64>`) of exogenous controls that enter both equations. Pass an
+///   array with zero columns if there are none.
+/// * `prior` - The `RivGibbsPrior` to use, or `None` for `RivGibbsPrior::default_for`.
+/// * `n_draws` - The number of draws to keep after the burn-in.
+/// * `burn_in` - The number of initial draws to discard.
+/// * `intercept` - A boolean indicating whether to include an intercept in both equations.
+/// * `rng` - The random number generator the draws are taken from.
+///
+/// # Returns
+///
+/// * `Ok(RivGibbsDraws)` with `n_draws` posterior draws.
+/// * `Err(String)` if the inputs have mismatched shapes, the prior has the wrong dimensions, or
+///   a posterior precision is not positive definite.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::bayesian_iv::run_riv_gibbs;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let z = Array2::from_shape_vec((8, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + 2.0 * &u + &e;
+/// let draws = run_riv_gibbs(&y, &x, &z, &Array2::zeros((8, 0)), None, 100, 10, true, &mut seeded_rng(42)).unwrap();
+/// assert_eq!(draws.beta.len(), 100);
+/// println!("posterior mean of beta = {}", draws.beta.mean().unwrap());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn run_riv_gibbs<R: Rng + ?Sized>(
+    y: &Array2<f64>,
+    x: &Array2<f64>,
+    z: &Array2<f64>,
+    w: &Array2<f64>,
+    prior: Option<&RivGibbsPrior>,
+    n_draws: usize,
+    burn_in: usize,
+    intercept: bool,
+    rng: &mut R,
+) -> Result<RivGibbsDraws, String> {
+    let n = y.nrows();
+    if y.ncols() != 1 || x.ncols() != 1 {
+        return Err("y and x must have exactly one column".into());
+    }
+    if x.nrows() != n || z.nrows() != n || w.nrows() != n {
+        return Err("y, x, z and w must have the same number of rows".into());
+    }
+    if z.ncols() == 0 {
+        return Err("z must have at least one column".into());
+    }
+    if n_draws == 0 {
+        return Err("n_draws must be positive".into());
+    }
+
+    // first-stage regressors [z, w, (1)] and structural regressors [x, w, (1)]
+    let mut z_full = hstack(&[z, w])?;
+    let mut x_full = hstack(&[x, w])?;
+    if intercept {
+        z_full = add_intercept(&z_full);
+        x_full = add_intercept(&x_full);
+    }
+    let n_delta = z_full.ncols();
+    let n_bg = x_full.ncols();
+
+    let default_prior = RivGibbsPrior::default_for(n_delta, n_bg);
+    let prior = prior.unwrap_or(&default_prior);
+    if prior.md.len() != n_delta || prior.ad.shape() != [n_delta, n_delta] {
+        return Err(format!("The prior on delta must have dimension {}", n_delta));
+    }
+    if prior.mbg.len() != n_bg || prior.abg.shape() != [n_bg, n_bg] {
+        return Err(format!("The prior on (beta, gamma) must have dimension {}", n_bg));
+    }
+    if prior.v.shape() != [2, 2] {
+        return Err("The prior scale v must be 2x2".into());
+    }
+
+    let x_col = x.column(0).to_owned();
+    let y_col = y.column(0).to_owned();
+    let w_full = x_full.slice(s![.., 1..]).to_owned();
+    let ztz = z_full.t().dot(&z_full);
+    let xtx = x_full.t().dot(&x_full);
+    let ad_md = prior.ad.dot(&prior.md);
+    let abg_mbg = prior.abg.dot(&prior.mbg);
+
+    // start from the first-stage least squares fit and an identity covariance
+    let mut delta = solve_spd(&ztz, &z_full.t().dot(x))?.index_axis(Axis(1), 0).to_owned();
+    let mut sigma = Array2::<f64>::eye(2);
+
+    let mut draws = RivGibbsDraws {
+        beta: Array1::zeros(n_draws),
+        gamma: Array2::zeros((n_draws, n_bg - 1)),
+        delta: Array2::zeros((n_draws, n_delta)),
+        sigma: Array3::zeros((n_draws, 2, 2)),
+    };
+
+    for iter in 0..burn_in + n_draws {
+        // (beta, gamma) | delta, Sigma
+        let e_x = &x_col - &z_full.dot(&delta);
+        let slope = sigma[[0, 1]] / sigma[[0, 0]];
+        let cond_var = sigma[[1, 1]] - sigma[[0, 1]] * slope;
+        let y_star = &y_col - &(slope * &e_x);
+        let precision = &xtx / cond_var + &prior.abg;
+        let linear = x_full.t().dot(&y_star) / cond_var + &abg_mbg;
+        let beta_gamma = draw_normal_from_precision(&precision, &linear, rng)?;
+        let beta = beta_gamma[0];
+        let gamma = beta_gamma.slice(s![1..]).to_owned();
+
+        // delta | beta, gamma, Sigma, whitening (e_x, beta * e_x + e_y) with L^{-1}
+        let y_tilde = &y_col - &w_full.dot(&gamma);
+        let c00 = sigma[[0, 0]];
+        let c01 = beta * sigma[[0, 0]] + sigma[[0, 1]];
+        let c11 = beta * beta * sigma[[0, 0]] + 2.0 * beta * sigma[[0, 1]] + sigma[[1, 1]];
+        let l00 = c00.sqrt();
+        let l10 = c01 / l00;
+        let l11 = (c11 - l10 * l10).sqrt();
+        let (inv00, inv10, inv11) = (1.0 / l00, -l10 / (l00 * l11), 1.0 / l11);
+        let (c1, c2) = (inv00, inv10 + inv11 * beta);
+        let a1 = inv00 * &x_col;
+        let a2 = inv10 * &x_col + inv11 * &y_tilde;
+        let precision = (c1 * c1 + c2 * c2) * &ztz + &prior.ad;
+        let linear = z_full.t().dot(&(c1 * &a1 + c2 * &a2)) + &ad_md;
+        delta = draw_normal_from_precision(&precision, &linear, rng)?;
+
+        // Sigma | beta, gamma, delta
+        let e_x = &x_col - &z_full.dot(&delta);
+        let e_y = &y_col - &x_full.dot(&beta_gamma);
+        let resid = stack(Axis(1), &[e_x.view(), e_y.view()])
+            .map_err(|e| format!("Failed to stack residuals: {}", e))?;
+        sigma = draw_inverse_wishart(prior.nu + n as f64, &(&prior.v + &resid.t().dot(&resid)), rng)?;
+
+        if iter >= burn_in {
+            let k = iter - burn_in;
+            draws.beta[k] = beta;
+            draws.gamma.row_mut(k).assign(&gamma);
+            draws.delta.row_mut(k).assign(&delta);
+            draws.sigma.index_axis_mut(Axis(0), k).assign(&sigma);
+        }
+    }
+
+    Ok(draws)
+}
+
+/// The prior used by `run_riv_dp`.
+///
+/// This structure holds:
+/// - `md`, `ad`: The prior mean and precision of the first-stage coefficients `delta`.
+/// - `mbg`, `abg`: The prior mean and precision of the structural coefficients `(beta, gamma)`.
+/// - `a`, `nu`, `v`: The Normal-inverse-Wishart base distribution of the Dirichlet process, under
+///   which each mixture component has `Sigma_k ~ IW(nu, v)` and `mu_k | Sigma_k ~ N(0, Sigma_k / a)`.
+/// - `alpha_shape`, `alpha_rate`: The Gamma prior on the Dirichlet-process concentration `alpha`.
+///   Larger values of `alpha` favour more mixture components.
+///
+/// `delta` has one entry per instrument followed by one per exogenous control; `(beta, gamma)`
+/// has `beta` first, then one entry per exogenous control. There are no intercepts, since they
+/// are absorbed by the means of the mixture components.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::utils::bayesian_iv::RivDpPrior;
+///
+/// // one instrument and no controls
+/// let prior = RivDpPrior::default_for(1, 1);
+/// assert_eq!(prior.nu, 5.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RivDpPrior {
+    pub md: Array1<f64>,
+    pub ad: Array2<f64>,
+    pub mbg: Array1<f64>,
+    pub abg: Array2<f64>,
+    pub a: f64,
+    pub nu: f64,
+    pub v: Array2<f64>,
+    pub alpha_shape: f64,
+    pub alpha_rate: f64,
+}
+
+impl RivDpPrior {
+    /// A weakly informative default: the coefficient priors of `RivGibbsPrior::default_for`,
+    /// a base distribution with `a = 0.2`, `nu = 5` and `v = 2 * I` (so that `E[Sigma_k] = I`),
+    /// and a `Gamma(1, 1)` prior on `alpha`.
+    ///
+    /// `n_delta` and `n_beta_gamma` are the lengths of `delta` and `(beta, gamma)`.
+    pub fn default_for(n_delta: usize, n_beta_gamma: usize) -> Self {
+        RivDpPrior {
+            md: Array1::zeros(n_delta),
+            ad: 0.01 * Array2::eye(n_delta),
+            mbg: Array1::zeros(n_beta_gamma),
+            abg: 0.01 * Array2::eye(n_beta_gamma),
+            a: 0.2,
+            nu: 5.0,
+            v: 2.0 * Array2::eye(2),
+            alpha_shape: 1.0,
+            alpha_rate: 1.0,
+        }
+    }
+}
+
+/// The posterior draws returned by `run_riv_dp`, one row (or entry) per kept draw.
+///
+/// This structure holds:
+/// - `beta`: The draws of the coefficient on the endogenous regressor `x`.
+/// - `gamma`: The draws of the coefficients on the exogenous controls in the `y` equation.
+/// - `delta`: The draws of the first-stage coefficients: instruments, then exogenous controls.
+/// - `n_components`: The number of occupied mixture components at each kept draw.
+/// - `alpha`: The draws of the Dirichlet-process concentration parameter.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::{Array1, Array2};
+/// use marketing_iv_methods::utils::bayesian_iv::RivDpDraws;
+///
+/// let draws = RivDpDraws {
+///     beta: Array1::zeros(10),
+///     gamma: Array2::zeros((10, 0)),
+///     delta: Array2::zeros((10, 1)),
+///     n_components: Array1::ones(10),
+///     alpha: Array1::ones(10),
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct RivDpDraws {
+    pub beta: Array1<f64>,
+    pub gamma: Array2<f64>,
+    pub delta: Array2<f64>,
+    pub n_components: Array1<usize>,
+    pub alpha: Array1<f64>,
+}
+
+/// A bivariate normal mixture component for the errors `(e_x, e_y)`.
+#[derive(Debug, Clone)]
+struct Component {
+    mu: [f64; 2],
+    sigma: [[f64; 2]; 2],
+}
+
+impl Component {
+    /// The log density of the bivariate normal at `e`.
+    fn log_density(&self, e: [f64; 2]) -> f64 {
+        let [[s11, s12], [_, s22]] = self.sigma;
+        let det = s11 * s22 - s12 * s12;
+        let (d1, d2) = (e[0] - self.mu[0], e[1] - self.mu[1]);
+        let quad = (s22 * d1 * d1 - 2.0 * s12 * d1 * d2 + s11 * d2 * d2) / det;
+        -(2.0 * std::f64::consts::PI).ln() - 0.5 * det.ln() - 0.5 * quad
+    }
+}
+
+/// Returns the lower Cholesky factor of the inverse of a 2x2 symmetric positive definite `scale`.
+fn inverse_scale_root(scale: &Array2<f64>) -> Result<[[f64; 2]; 2], String> {
+    let det = scale[[0, 0]] * scale[[1, 1]] - scale[[0, 1]] * scale[[1, 0]];
+    if scale[[0, 0]] <= 0.0 || det <= 0.0 {
+        return Err("Inverse Wishart scale is not positive definite".into());
+    }
+    let (p00, p01, p11) = (scale[[1, 1]] / det, -scale[[0, 1]] / det, scale[[0, 0]] / det);
+    let l00 = p00.sqrt();
+    let l10 = p01 / l00;
+    Ok([[l00, 0.0], [l10, (p11 - l10 * l10).sqrt()]])
+}
+
+/// Draws a component from the Normal-inverse-Wishart distribution with mean scale `a`, centre
+/// `centre`, degrees of freedom `nu`, and inverse Wishart scale whose inverse has Cholesky
+/// factor `root`.
+///
+/// This is the 2x2 case of `draw_inverse_wishart` written out in closed form, since it is
+/// called several times per observation and iteration.
+fn draw_component<R: Rng + ?Sized>(
+    centre: [f64; 2],
+    a: f64,
+    nu: f64,
+    root: &[[f64; 2]; 2],
+    rng: &mut R,
+) -> Result<Component, String> {
+    // Bartlett factor of W(nu, scale^{-1}), then invert W to get Sigma
+    let chi_0 = ChiSquared::new(nu).map_err(|e| format!("Invalid degrees of freedom: {}", e))?.sample(rng).sqrt();
+    let chi_1 = ChiSquared::new(nu - 1.0).map_err(|e| format!("Invalid degrees of freedom: {}", e))?.sample(rng).sqrt();
+    let off: f64 = rng.sample(StandardNormal);
+    let f00 = root[0][0] * chi_0;
+    let f10 = root[1][0] * chi_0 + root[1][1] * off;
+    let f11 = root[1][1] * chi_1;
+    let (w00, w01, w11) = (f00 * f00, f00 * f10, f10 * f10 + f11 * f11);
+    let det = w00 * w11 - w01 * w01;
+    let sigma = [[w11 / det, -w01 / det], [-w01 / det, w00 / det]];
+
+    let l00 = (sigma[0][0] / a).sqrt();
+    let l10 = sigma[1][0] / a / l00;
+    let l11 = (sigma[1][1] / a - l10 * l10).sqrt();
+    let (u1, u2): (f64, f64) = (rng.sample(StandardNormal), rng.sample(StandardNormal));
+    Ok(Component {
+        mu: [centre[0] + l00 * u1, centre[1] + l10 * u1 + l11 * u2],
+        sigma,
+    })
+}
+
+/// Runs a Bayesian instrumental-variables sampler with Dirichlet-process mixture errors.
+///
+/// The model is the two-equation system of `run_riv_gibbs`,
+///
+/// `x = z * delta_z + w * delta_w + e_x`
+///
+/// `y = beta * x + w * gamma + e_y`,
+///
+/// but the joint error distribution is a Dirichlet-process mixture of bivariate normals instead
+/// of a single normal, as in bayesm's `rivDP` (Conley, Hansen, McCulloch and Rossi, 2008). Each
+/// observation has its own `(mu_i, Sigma_i)`, drawn from a random distribution
+/// `G ~ DP(alpha, G0)` with the Normal-inverse-Wishart base `G0` given by the prior. This lets the
+/// errors be skewed, heavy-tailed or multimodal, which a normal-error sampler would otherwise
+/// misread as information about `beta`. Each iteration draws in turn:
+///
+/// 1. The component memberships and parameters, given the current errors, with Neal's (2000)
+///    Algorithm 8 and conjugate updates of each occupied component;
+/// 2. `alpha`, given the number of components (Escobar and West, 1995);
+/// 3. `(beta, gamma)` and then `delta`, as in `run_riv_gibbs` but with observation-specific error
+///    means and covariances.
+///
+/// Intercepts are not estimated separately, since they are absorbed by the component means.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x` - A 2D array (`Array2<f64>`) with a single column, representing the endogenous regressor.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `w` - A 2D array (`Array2<f64>`) of exogenous controls that enter both equations. Pass an
+///   array with zero columns if there are none.
+/// * `prior` - The `RivDpPrior` to use, or `None` for `RivDpPrior::default_for`.
+/// * `n_draws` - The number of draws to keep after the burn-in.
+/// * `burn_in` - The number of initial draws to discard.
+/// * `rng` - The random number generator the draws are taken from.
+///
+/// # Returns
+///
+/// * `Ok(RivDpDraws)` with `n_draws` posterior draws.
+/// * `Err(String)` if the inputs have mismatched shapes, the prior is invalid, or a posterior
+///   precision is not positive definite.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::bayesian_iv::run_riv_dp;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let z = Array2::from_shape_vec((8, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + 2.0 * &u + &e;
+/// let draws = run_riv_dp(&y, &x, &z, &Array2::zeros((8, 0)), None, 100, 10, &mut seeded_rng(42)).unwrap();
+/// assert_eq!(draws.n_components.len(), 100);
+/// println!("posterior mean of beta = {}", draws.beta.mean().unwrap());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn run_riv_dp<R: Rng + ?Sized>(
+    y: &Array2<f64>,
+    x: &Array2<f64>,
+    z: &Array2<f64>,
+    w: &Array2<f64>,
+    prior: Option<&RivDpPrior>,
+    n_draws: usize,
+    burn_in: usize,
+    rng: &mut R,
+) -> Result<RivDpDraws, String> {
+    // number of auxiliary components proposed for each observation in Algorithm 8
+    const N_AUXILIARY: usize = 3;
+
+    let n = y.nrows();
+    if y.ncols() != 1 || x.ncols() != 1 {
+        return Err("y and x must have exactly one column".into());
+    }
+    if x.nrows() != n || z.nrows() != n || w.nrows() != n {
+        return Err("y, x, z and w must have the same number of rows".into());
+    }
+    if z.ncols() == 0 {
+        return Err("z must have at least one column".into());
+    }
+    if n_draws == 0 {
+        return Err("n_draws must be positive".into());
+    }
+
+    let z_full = hstack(&[z, w])?;
+    let x_full = hstack(&[x, w])?;
+    let n_delta = z_full.ncols();
+    let n_bg = x_full.ncols();
+
+    let default_prior = RivDpPrior::default_for(n_delta, n_bg);
+    let prior = prior.unwrap_or(&default_prior);
+    if prior.md.len() != n_delta || prior.ad.shape() != [n_delta, n_delta] {
+        return Err(format!("The prior on delta must have dimension {}", n_delta));
+    }
+    if prior.mbg.len() != n_bg || prior.abg.shape() != [n_bg, n_bg] {
+        return Err(format!("The prior on (beta, gamma) must have dimension {}", n_bg));
+    }
+    if prior.v.shape() != [2, 2] {
+        return Err("The prior scale v must be 2x2".into());
+    }
+    if prior.nu <= 1.0 {
+        return Err("nu must exceed 1".into());
+    }
+    if prior.a <= 0.0 || prior.alpha_shape <= 0.0 || prior.alpha_rate <= 0.0 {
+        return Err("a, alpha_shape and alpha_rate must be positive".into());
+    }
+
+    let x_col = x.column(0).to_owned();
+    let y_col = y.column(0).to_owned();
+    let w_full = x_full.slice(s![.., 1..]).to_owned();
+    let ad_md = prior.ad.dot(&prior.md);
+    let abg_mbg = prior.abg.dot(&prior.mbg);
+    let base_root = inverse_scale_root(&prior.v)?;
+
+    // start from least squares fits of both equations, with every observation in one component
+    let mut delta = solve_spd(&z_full.t().dot(&z_full), &z_full.t().dot(x))?.index_axis(Axis(1), 0).to_owned();
+    let mut beta_gamma = solve_spd(&x_full.t().dot(&x_full), &x_full.t().dot(y))?.index_axis(Axis(1), 0).to_owned();
+    let mut components = vec![Component { mu: [0.0, 0.0], sigma: [[1.0, 0.0], [0.0, 1.0]] }];
+    let mut counts = vec![n];
+    let mut membership = vec![0_usize; n];
+    let mut alpha = prior.alpha_shape / prior.alpha_rate;
+
+    let mut draws = RivDpDraws {
+        beta: Array1::zeros(n_draws),
+        gamma: Array2::zeros((n_draws, n_bg - 1)),
+        delta: Array2::zeros((n_draws, n_delta)),
+        n_components: Array1::zeros(n_draws),
+        alpha: Array1::zeros(n_draws),
+    };
+
+    for iter in 0..burn_in + n_draws {
+        let e_x = &x_col - &z_full.dot(&delta);
+        let e_y = &y_col - &x_full.dot(&beta_gamma);
+
+        // memberships | errors, by Neal's Algorithm 8
+        for i in 0..n {
+            let e_i = [e_x[i], e_y[i]];
+            let current = membership[i];
+            counts[current] -= 1;
+            let mut auxiliary = Vec::with_capacity(N_AUXILIARY);
+            if counts[current] == 0 {
+                // a singleton keeps its parameters as one of the auxiliary proposals
+                auxiliary.push(components.swap_remove(current));
+                counts.swap_remove(current);
+                let moved = components.len();
+                for label in membership.iter_mut() {
+                    if *label == moved {
+                        *label = current;
+                    }
+                }
+            }
+            while auxiliary.len() < N_AUXILIARY {
+                auxiliary.push(draw_component([0.0, 0.0], prior.a, prior.nu, &base_root, rng)?);
+            }
+
+            let log_weights: Vec<f64> = components
+                .iter()
+                .zip(counts.iter())
+                .map(|(c, &count)| (count as f64).ln() + c.log_density(e_i))
+                .chain(auxiliary.iter().map(|c| (alpha / N_AUXILIARY as f64).ln() + c.log_density(e_i)))
+                .collect();
+            let max_log = log_weights.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let weights: Vec<f64> = log_weights.iter().map(|lw| (lw - max_log).exp()).collect();
+            let mut u = rng.gen::<f64>() * weights.iter().sum::<f64>();
+            let mut chosen = weights.len() - 1;
+            for (k, weight) in weights.iter().enumerate() {
+                if u < *weight {
+                    chosen = k;
+                    break;
+                }
+                u -= weight;
+            }
+
+            if chosen < components.len() {
+                counts[chosen] += 1;
+                membership[i] = chosen;
+            } else {
+                components.push(auxiliary.swap_remove(chosen - components.len()));
+                counts.push(1);
+                membership[i] = components.len() - 1;
+            }
+        }
+
+        // component parameters | memberships, errors, from the Normal-inverse-Wishart posterior
+        let n_comp = components.len();
+        let mut sums = vec![[0.0_f64; 2]; n_comp];
+        for i in 0..n {
+            sums[membership[i]][0] += e_x[i];
+            sums[membership[i]][1] += e_y[i];
+        }
+        let means: Vec<[f64; 2]> = sums.iter().zip(counts.iter()).map(|(s, &c)| [s[0] / c as f64, s[1] / c as f64]).collect();
+        let mut scatter = vec![Array2::<f64>::zeros((2, 2)); n_comp];
+        for i in 0..n {
+            let k = membership[i];
+            let d = [e_x[i] - means[k][0], e_y[i] - means[k][1]];
+            for r in 0..2 {
+                for c in 0..2 {
+                    scatter[k][[r, c]] += d[r] * d[c];
+                }
+            }
+        }
+        for k in 0..n_comp {
+            let count = counts[k] as f64;
+            let shrink = prior.a * count / (prior.a + count);
+            let mut scale = &prior.v + &scatter[k];
+            for r in 0..2 {
+                for c in 0..2 {
+                    scale[[r, c]] += shrink * means[k][r] * means[k][c];
+                }
+            }
+            let centre = [count * means[k][0] / (prior.a + count), count * means[k][1] / (prior.a + count)];
+            components[k] = draw_component(centre, prior.a + count, prior.nu + count, &inverse_scale_root(&scale)?, rng)?;
+        }
+
+        // alpha | number of components, by the auxiliary-variable scheme of Escobar and West
+        let eta = Beta::new(alpha + 1.0, n as f64)
+            .map_err(|e| format!("Invalid Beta parameters: {}", e))?
+            .sample(rng);
+        let rate = prior.alpha_rate - eta.ln();
+        let odds = (prior.alpha_shape + n_comp as f64 - 1.0) / (n as f64 * rate);
+        let shape = if rng.gen::<f64>() < odds / (1.0 + odds) {
+            prior.alpha_shape + n_comp as f64
+        } else {
+            prior.alpha_shape + n_comp as f64 - 1.0
+        };
+        alpha = Gamma::new(shape, 1.0 / rate)
+            .map_err(|e| format!("Invalid Gamma parameters: {}", e))?
+            .sample(rng);
+
+        // (beta, gamma) | delta, components, with observation-specific error moments
+        let mut weight = Array1::<f64>::zeros(n);
+        let mut y_star = Array1::<f64>::zeros(n);
+        for i in 0..n {
+            let c = &components[membership[i]];
+            let slope = c.sigma[0][1] / c.sigma[0][0];
+            weight[i] = 1.0 / (c.sigma[1][1] - c.sigma[0][1] * slope);
+            y_star[i] = y_col[i] - c.mu[1] - slope * (e_x[i] - c.mu[0]);
+        }
+        let weighted_x = &x_full * &weight.view().insert_axis(Axis(1));
+        let precision = weighted_x.t().dot(&x_full) + &prior.abg;
+        let linear = weighted_x.t().dot(&y_star) + &abg_mbg;
+        beta_gamma = draw_normal_from_precision(&precision, &linear, rng)?;
+        let beta = beta_gamma[0];
+        let gamma = beta_gamma.slice(s![1..]).to_owned();
+
+        // delta | beta, gamma, components, whitening each observation's reduced-form errors
+        let y_tilde = &y_col - &w_full.dot(&gamma);
+        let mut weight = Array1::<f64>::zeros(n);
+        let mut response = Array1::<f64>::zeros(n);
+        for i in 0..n {
+            let c = &components[membership[i]];
+            let [[s11, s12], [_, s22]] = c.sigma;
+            let c00 = s11;
+            let c01 = beta * s11 + s12;
+            let c11 = beta * beta * s11 + 2.0 * beta * s12 + s22;
+            let l00 = c00.sqrt();
+            let l10 = c01 / l00;
+            let l11 = (c11 - l10 * l10).sqrt();
+            let (inv00, inv10, inv11) = (1.0 / l00, -l10 / (l00 * l11), 1.0 / l11);
+            let (c1, c2) = (inv00, inv10 + inv11 * beta);
+            let (m1, m2) = (c.mu[0], beta * c.mu[0] + c.mu[1]);
+            let a1 = inv00 * (x_col[i] - m1);
+            let a2 = inv10 * (x_col[i] - m1) + inv11 * (y_tilde[i] - m2);
+            weight[i] = c1 * c1 + c2 * c2;
+            response[i] = c1 * a1 + c2 * a2;
+        }
+        let weighted_z = &z_full * &weight.view().insert_axis(Axis(1));
+        let precision = weighted_z.t().dot(&z_full) + &prior.ad;
+        let linear = z_full.t().dot(&response) + &ad_md;
+        delta = draw_normal_from_precision(&precision, &linear, rng)?;
+
+        if iter >= burn_in {
+            let k = iter - burn_in;
+            draws.beta[k] = beta;
+            draws.gamma.row_mut(k).assign(&gamma);
+            draws.delta.row_mut(k).assign(&delta);
+            draws.n_components[k] = components.len();
+            draws.alpha[k] = alpha;
+        }
+    }
+
+    Ok(draws)
+}
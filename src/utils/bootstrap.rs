@@ -0,0 +1,477 @@
+use super::jive::FittedJive;
+use super::linear_regression::{iv_design, FittedIvRegression, FittedOls};
+use super::matrix_ops::{hstack, solve_spd};
+use ndarray::{s, Array1, Array2, Axis};
+use ndarray_rand::rand::Rng;
+use statrs::distribution::{ContinuousCDF, Normal};
+
+/// The distribution of the auxiliary weights `eta_i` used by the wild bootstrap schemes.
+///
+/// - `Rademacher`: `eta_i = 1` or `-1` with probability one half each.
+/// - `Mammen`: The two-point distribution of Mammen (1993), `-(sqrt(5) - 1) / 2` with probability
+///   `(sqrt(5) + 1) / (2 sqrt(5))` and `(sqrt(5) + 1) / 2` otherwise, which also matches the third
+///   moment of the residuals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WildWeights {
+    Rademacher,
+    Mammen,
+}
+
+impl WildWeights {
+    /// Draws one auxiliary weight with mean zero and variance one.
+    fn draw<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match self {
+            WildWeights::Rademacher => {
+                if rng.gen::<bool>() { 1.0 } else { -1.0 }
+            }
+            WildWeights::Mammen => {
+                let root5 = 5f64.sqrt();
+                if rng.gen::<f64>() < (root5 + 1.0) / (2.0 * root5) {
+                    -(root5 - 1.0) / 2.0
+                } else {
+                    (root5 + 1.0) / 2.0
+                }
+            }
+        }
+    }
+}
+
+/// The resampling scheme used by `run_bootstrap`.
+///
+/// - `Pairs`: Resamples whole observations `(y_i, x_i, z_i)` with replacement.
+/// - `Residual`: Keeps the exogenous variables fixed and resamples the pairs of structural and
+///   first-stage residuals `(u_i, v_i)` with replacement, after centering them.
+/// - `Wild`: Keeps the exogenous variables fixed and multiplies each pair `(u_i, v_i)` by a
+///   common auxiliary weight `eta_i`, which preserves heteroskedasticity of any form.
+/// - `WildRestrictedEfficient`: The wild restricted efficient (WRE) bootstrap of Davidson and
+///   MacKinnon (2010). The structural equation is estimated with the coefficient on the single
+///   endogenous regressor fixed at `null_value`, and the first stage is estimated efficiently by
+///   adding the restricted structural residuals to it. The bootstrap samples then satisfy the
+///   null, so they are used for bootstrap-t tests and intervals rather than percentile ones.
+///
+/// In the residual-based schemes both `y` and the endogenous regressors are regenerated, through
+/// `x* = Z pi + v*` and `y* = x* beta + x_exog gamma + u*`, so that the bootstrap samples keep the
+/// endogeneity of the original data. With no endogenous regressors only `y` is regenerated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BootstrapScheme {
+    Pairs,
+    Residual,
+    Wild(WildWeights),
+    WildRestrictedEfficient { weights: WildWeights, null_value: f64 },
+}
+
+/// The part of a fitted model that `run_bootstrap` needs from an estimator.
+///
+/// This structure holds:
+/// - `params`: The slope coefficients, ordered as the endogenous regressors followed by the
+///   exogenous controls.
+/// - `intercept`: The fitted intercept, or `0.0` when the model was fit without one.
+/// - `std_errors`: The standard errors of `params`, or `None` for estimators that do not report
+///   them. They are only needed for bootstrap-t inference.
+///
+/// `From` conversions are provided for the fits returned by `run_regression`, the k-class
+/// estimators and `run_jive`.
+#[derive(Debug, Clone)]
+pub struct BootstrapFit {
+    pub params: Array1<f64>,
+    pub intercept: f64,
+    pub std_errors: Option<Array1<f64>>,
+}
+
+impl From<&FittedOls> for BootstrapFit {
+    fn from(fit: &FittedOls) -> Self {
+        BootstrapFit {
+            params: fit.params().clone(),
+            intercept: fit.intercept(),
+            std_errors: Some(fit.std_errors().clone()),
+        }
+    }
+}
+
+impl From<&FittedIvRegression> for BootstrapFit {
+    fn from(fit: &FittedIvRegression) -> Self {
+        BootstrapFit {
+            params: fit.params().clone(),
+            intercept: fit.intercept(),
+            std_errors: Some(fit.std_errors().clone()),
+        }
+    }
+}
+
+impl From<&FittedJive> for BootstrapFit {
+    fn from(fit: &FittedJive) -> Self {
+        BootstrapFit {
+            params: fit.params().clone(),
+            intercept: fit.intercept(),
+            std_errors: None,
+        }
+    }
+}
+
+/// The bootstrap distribution of an estimator, as returned by `run_bootstrap`.
+///
+/// This structure holds:
+/// - `estimate`: The slope coefficients on the original sample.
+/// - `std_errors`: Their standard errors on the original sample, if the estimator reports them.
+/// - `draws`: The bootstrap estimates, one row per replication.
+/// - `draw_std_errors`: The bootstrap standard errors, one row per replication, if available.
+/// - `centre`: The coefficients of the bootstrap data-generating process, around which the
+///   bootstrap t-statistics are centred.
+/// - `jackknife`: The leave-one-out estimates used for the BCa acceleration, one row per
+///   observation. They are not computed for the restricted bootstrap.
+/// - `scheme`: The `BootstrapScheme` used.
+///
+/// Intervals are returned as a `k x 2` array of lower and upper bounds, in the order of the
+/// coefficients.
+#[derive(Debug, Clone)]
+pub struct BootstrapResult {
+    estimate: Array1<f64>,
+    std_errors: Option<Array1<f64>>,
+    draws: Array2<f64>,
+    draw_std_errors: Option<Array2<f64>>,
+    centre: Array1<f64>,
+    jackknife: Option<Array2<f64>>,
+    scheme: BootstrapScheme,
+}
+
+impl BootstrapResult {
+    /// The slope coefficients on the original sample.
+    pub fn estimate(&self) -> &Array1<f64> {
+        &self.estimate
+    }
+
+    /// The bootstrap estimates, one row per replication.
+    pub fn draws(&self) -> &Array2<f64> {
+        &self.draws
+    }
+
+    /// The resampling scheme used.
+    pub fn scheme(&self) -> BootstrapScheme {
+        self.scheme
+    }
+
+    /// The standard deviations of the bootstrap estimates.
+    pub fn bootstrap_std_errors(&self) -> Array1<f64> {
+        self.draws.std_axis(Axis(0), 1.0)
+    }
+
+    /// The percentile interval `[q(alpha / 2), q(1 - alpha / 2)]` of the bootstrap estimates.
+    pub fn percentile_interval(&self, confidence: f64) -> Result<Array2<f64>, String> {
+        let alpha = check_confidence(confidence)?;
+        self.check_unrestricted()?;
+        let mut interval = Array2::zeros((self.estimate.len(), 2));
+        for (j, column) in self.draws.axis_iter(Axis(1)).enumerate() {
+            let sorted = sorted_values(column.iter().copied());
+            interval[[j, 0]] = quantile(&sorted, alpha / 2.0);
+            interval[[j, 1]] = quantile(&sorted, 1.0 - alpha / 2.0);
+        }
+        Ok(interval)
+    }
+
+    /// The bias-corrected and accelerated (BCa) interval of Efron (1987).
+    ///
+    /// The percentile levels are adjusted by the bias correction `z0 = Phi^{-1}(share of draws
+    /// below the estimate)` and the acceleration `a = sum d_i^3 / (6 (sum d_i^2)^{3/2})`, where
+    /// `d_i` are the deviations of the jackknife estimates from their mean.
+    pub fn bca_interval(&self, confidence: f64) -> Result<Array2<f64>, String> {
+        let alpha = check_confidence(confidence)?;
+        self.check_unrestricted()?;
+        let jackknife = self.jackknife.as_ref().ok_or("The jackknife estimates are missing")?;
+        let normal = Normal::new(0.0, 1.0).map_err(|e| format!("Invalid normal distribution: {}", e))?;
+        let n_draws = self.draws.nrows() as f64;
+
+        let mut interval = Array2::zeros((self.estimate.len(), 2));
+        for (j, column) in self.draws.axis_iter(Axis(1)).enumerate() {
+            let below = column.iter().filter(|&&d| d < self.estimate[j]).count() as f64;
+            if below == 0.0 || below == n_draws {
+                return Err("The BCa interval is undefined when every bootstrap draw lies on one side of the estimate".into());
+            }
+            let z0 = normal.inverse_cdf(below / n_draws);
+
+            let loo = jackknife.column(j);
+            let deviations = loo.mean().unwrap() - &loo;
+            let spread = deviations.mapv(|d| d * d).sum();
+            let acceleration = if spread > 0.0 {
+                deviations.mapv(|d| d.powi(3)).sum() / (6.0 * spread.powf(1.5))
+            } else {
+                0.0
+            };
+
+            let sorted = sorted_values(column.iter().copied());
+            for (bound, level) in [alpha / 2.0, 1.0 - alpha / 2.0].iter().enumerate() {
+                let z = z0 + normal.inverse_cdf(*level);
+                interval[[j, bound]] = quantile(&sorted, normal.cdf(z0 + z / (1.0 - acceleration * z)));
+            }
+        }
+        Ok(interval)
+    }
+
+    /// The bootstrap-t (percentile-t) interval `[b - q*(1 - alpha / 2) se, b - q*(alpha / 2) se]`,
+    /// where `q*` are quantiles of the bootstrap t-statistics `(b* - centre) / se*`.
+    pub fn bootstrap_t_interval(&self, confidence: f64) -> Result<Array2<f64>, String> {
+        let alpha = check_confidence(confidence)?;
+        let (std_errors, t_statistics) = self.t_statistics()?;
+        let mut interval = Array2::zeros((self.estimate.len(), 2));
+        for (j, column) in t_statistics.axis_iter(Axis(1)).enumerate() {
+            let sorted = sorted_values(column.iter().copied());
+            interval[[j, 0]] = self.estimate[j] - quantile(&sorted, 1.0 - alpha / 2.0) * std_errors[j];
+            interval[[j, 1]] = self.estimate[j] - quantile(&sorted, alpha / 2.0) * std_errors[j];
+        }
+        Ok(interval)
+    }
+
+    /// The symmetric bootstrap-t p-value of the null that coefficient `index` equals `null_value`,
+    /// the share of bootstrap t-statistics at least as large in absolute value as the observed one.
+    ///
+    /// For the restricted bootstrap, the null must be the one imposed by the scheme.
+    pub fn bootstrap_t_p_value(&self, index: usize, null_value: f64) -> Result<f64, String> {
+        if index >= self.estimate.len() {
+            return Err(format!("Coefficient index {} is out of range", index));
+        }
+        if let BootstrapScheme::WildRestrictedEfficient { null_value: imposed, .. } = self.scheme {
+            if index != 0 || null_value != imposed {
+                return Err("The restricted bootstrap only tests the null it imposes".into());
+            }
+        }
+        let (std_errors, t_statistics) = self.t_statistics()?;
+        let observed = ((self.estimate[index] - null_value) / std_errors[index]).abs();
+        let exceed = t_statistics.column(index).iter().filter(|t| t.abs() >= observed).count();
+        Ok(exceed as f64 / t_statistics.nrows() as f64)
+    }
+
+    /// Returns the original standard errors and the bootstrap t-statistics.
+    ///
+    /// A zero standard error, on the original sample or in a replicate, leaves the t-statistic
+    /// undefined. This happens with the residual-based schemes when the model fits exactly.
+    fn t_statistics(&self) -> Result<(&Array1<f64>, Array2<f64>), String> {
+        let (std_errors, draw_std_errors) = match (&self.std_errors, &self.draw_std_errors) {
+            (Some(std_errors), Some(draw_std_errors)) => (std_errors, draw_std_errors),
+            _ => return Err("The estimator does not report standard errors, so bootstrap-t inference is unavailable".into()),
+        };
+        if std_errors.iter().any(|se| !(*se > 0.0 && se.is_finite())) {
+            return Err("A standard error on the original sample is zero or not finite, so bootstrap-t inference is undefined".into());
+        }
+        let t_statistics = (&self.draws - &self.centre) / draw_std_errors;
+        if t_statistics.iter().any(|t| !t.is_finite()) {
+            return Err("A bootstrap replicate has a zero or non-finite standard error, so its t-statistic is undefined".into());
+        }
+        Ok((std_errors, t_statistics))
+    }
+
+    fn check_unrestricted(&self) -> Result<(), String> {
+        match self.scheme {
+            BootstrapScheme::WildRestrictedEfficient { .. } => Err(
+                "Percentile and BCa intervals are not available for the restricted bootstrap; use bootstrap-t inference".into(),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn check_confidence(confidence: f64) -> Result<f64, String> {
+    if confidence > 0.0 && confidence < 1.0 {
+        Ok(1.0 - confidence)
+    } else {
+        Err("confidence must be between 0 and 1".into())
+    }
+}
+
+fn sorted_values<I: Iterator<Item = f64>>(values: I) -> Vec<f64> {
+    let mut sorted: Vec<f64> = values.collect();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+}
+
+/// The `q` quantile of sorted values, interpolating linearly between order statistics.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (position - lower as f64) * (sorted[upper] - sorted[lower])
+}
+
+/// Runs a bootstrap of any estimator of the linear IV model `y = x_endog * beta + x_exog * gamma + u`.
+///
+/// `estimator` is called with `(y, x_endog, x_exog, z)` on the original sample and on every
+/// bootstrap sample, and returns a `BootstrapFit`. Any estimator in the crate can be wrapped in a
+/// closure, e.g. `|y, x, w, z| run_2sls(y, x, w, z, &CovarianceType::HC1, true).map(|f| BootstrapFit::from(&f))`,
+/// and OLS is covered by passing the regressors as `x_exog` with no endogenous regressors or
+/// instruments. For the residual-based schemes the first stage is an OLS regression of
+/// `x_endog` on `[z, x_exog]`, with a constant when `intercept` is `true`.
+///
+/// Unless the scheme is `WildRestrictedEfficient`, the estimator is also run once on every
+/// leave-one-out sample to obtain the BCa acceleration, which costs `n` additional fits.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors, possibly with zero columns.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments, possibly with zero columns.
+/// * `estimator` - The estimator to bootstrap.
+/// * `scheme` - The `BootstrapScheme` used to build the bootstrap samples.
+/// * `n_bootstrap` - The number of bootstrap replications.
+/// * `intercept` - A boolean indicating whether the model includes an intercept term.
+/// * `rng` - The random number generator the bootstrap samples are drawn from.
+///
+/// # Returns
+///
+/// * `Ok(BootstrapResult)` with the bootstrap distribution of the slope coefficients.
+/// * `Err(String)` if the inputs are invalid, the estimator fails on the original sample or on
+///   a bootstrap or jackknife sample, or the restricted bootstrap is requested with other than
+///   one endogenous regressor.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::bootstrap::{run_bootstrap, BootstrapFit, BootstrapScheme, WildWeights};
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::linear_regression::run_2sls;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let z = Array2::from_shape_fn((30, 1), |(i, _)| (i % 7) as f64);
+/// let u = Array2::from_shape_fn((30, 1), |(i, _)| ((i * 13 % 11) as f64 - 5.0) / 10.0);
+/// let x = &z + &u;
+/// let y = -0.5 * &x + &u;
+/// let tsls = |y: &Array2<f64>, x: &Array2<f64>, w: &Array2<f64>, z: &Array2<f64>| {
+///     run_2sls(y, x, w, z, &CovarianceType::HC1, true).map(|fit| BootstrapFit::from(&fit))
+/// };
+/// let scheme = BootstrapScheme::Wild(WildWeights::Rademacher);
+/// let result = run_bootstrap(&y, &x, &Array2::zeros((30, 0)), &z, tsls, &scheme, 199, true, &mut seeded_rng(42)).unwrap();
+/// println!("{}", result.bootstrap_t_interval(0.95).unwrap());
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn run_bootstrap<F, R>(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    estimator: F,
+    scheme: &BootstrapScheme,
+    n_bootstrap: usize,
+    intercept: bool,
+    rng: &mut R,
+) -> Result<BootstrapResult, String>
+where
+    R: Rng + ?Sized,
+    F: Fn(&Array2<f64>, &Array2<f64>, &Array2<f64>, &Array2<f64>) -> Result<BootstrapFit, String>,
+{
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    if n_bootstrap < 2 {
+        return Err("The bootstrap requires at least two replications".into());
+    }
+    let n = y.nrows();
+    let n_endog = x_endog.ncols();
+    let n_coefs = n_endog + x_exog.ncols();
+    if n_coefs == 0 {
+        return Err("The model has no coefficients to bootstrap".into());
+    }
+
+    let fit = estimator(y, x_endog, x_exog, z).map_err(|e| format!("Estimation on the original sample failed: {}", e))?;
+    if fit.params.len() != n_coefs {
+        return Err(format!("The estimator returned {} coefficients but there are {} regressors", fit.params.len(), n_coefs));
+    }
+
+    // the data-generating process of the residual-based schemes
+    let (centre, exog_part, x_fitted, structural_resid, first_stage_resid) = match scheme {
+        BootstrapScheme::WildRestrictedEfficient { null_value, .. } => {
+            if n_endog != 1 {
+                return Err("The wild restricted efficient bootstrap requires exactly one endogenous regressor".into());
+            }
+            // structural equation with beta fixed at the null, first stage augmented by its residuals
+            let restricted_y = y - &(x_endog * *null_value);
+            let exog_coefs = if design.exog.ncols() > 0 {
+                solve_spd(&design.exog.t().dot(&design.exog), &design.exog.t().dot(&restricted_y))
+                    .map_err(|e| format!("Control cross-product is singular: {}", e))?
+            } else {
+                Array2::zeros((0, 1))
+            };
+            let exog_part = design.exog.dot(&exog_coefs).column(0).to_owned();
+            let structural_resid = restricted_y.column(0).to_owned() - &exog_part;
+            let augmented = hstack(&[&design.z_full, &structural_resid.clone().insert_axis(Axis(1))])?;
+            let pi = solve_spd(&augmented.t().dot(&augmented), &augmented.t().dot(x_endog))
+                .map_err(|e| format!("First-stage cross-product is singular: {}", e))?;
+            let x_fitted = design.z_full.dot(&pi.slice(s![..design.z_full.ncols(), ..]));
+            let first_stage_resid = x_endog - &x_fitted;
+
+            let mut centre = Array1::from_elem(n_coefs, *null_value);
+            centre.slice_mut(s![1..]).assign(&exog_coefs.slice(s![..x_exog.ncols(), 0]));
+            (centre, exog_part, x_fitted, structural_resid, first_stage_resid)
+        }
+        _ => {
+            let x_fitted = if n_endog > 0 {
+                let pi = solve_spd(&design.z_full.t().dot(&design.z_full), &design.z_full.t().dot(x_endog))
+                    .map_err(|e| format!("First-stage cross-product is singular: {}", e))?;
+                design.z_full.dot(&pi)
+            } else {
+                Array2::zeros((n, 0))
+            };
+            let exog_part = x_exog.dot(&fit.params.slice(s![n_endog..])) + fit.intercept;
+            let structural_resid = y.column(0).to_owned() - x_endog.dot(&fit.params.slice(s![..n_endog])) - &exog_part;
+            let first_stage_resid = x_endog - &x_fitted;
+            (fit.params.clone(), exog_part, x_fitted, structural_resid, first_stage_resid)
+        }
+    };
+    let beta = centre.slice(s![..n_endog]).to_owned();
+    let regenerate = |u_star: Array1<f64>, v_star: Array2<f64>| {
+        let x_star = &x_fitted + &v_star;
+        let y_star = (x_star.dot(&beta) + &exog_part + &u_star).insert_axis(Axis(1));
+        (y_star, x_star, x_exog.clone(), z.clone())
+    };
+    let u_centred = &structural_resid - structural_resid.mean().unwrap();
+    let v_centred = &first_stage_resid - &first_stage_resid.mean_axis(Axis(0)).unwrap();
+
+    let mut draws = Array2::zeros((n_bootstrap, n_coefs));
+    let mut draw_std_errors = fit.std_errors.as_ref().map(|_| Array2::zeros((n_bootstrap, n_coefs)));
+    for b in 0..n_bootstrap {
+        let sample = match scheme {
+            BootstrapScheme::Pairs => {
+                let rows: Vec<usize> = (0..n).map(|_| rng.gen_range(0..n)).collect();
+                (y.select(Axis(0), &rows), x_endog.select(Axis(0), &rows), x_exog.select(Axis(0), &rows), z.select(Axis(0), &rows))
+            }
+            BootstrapScheme::Residual => {
+                let rows: Vec<usize> = (0..n).map(|_| rng.gen_range(0..n)).collect();
+                regenerate(u_centred.select(Axis(0), &rows), v_centred.select(Axis(0), &rows))
+            }
+            BootstrapScheme::Wild(weights) | BootstrapScheme::WildRestrictedEfficient { weights, .. } => {
+                let eta = Array1::from_shape_fn(n, |_| weights.draw(rng));
+                regenerate(&structural_resid * &eta, &first_stage_resid * &eta.insert_axis(Axis(1)))
+            }
+        };
+
+        let replicate = estimator(&sample.0, &sample.1, &sample.2, &sample.3)
+            .map_err(|e| format!("Bootstrap replication {} failed: {}", b, e))?;
+        draws.row_mut(b).assign(&replicate.params);
+        if let Some(std_errors) = draw_std_errors.as_mut() {
+            match replicate.std_errors {
+                Some(se) => std_errors.row_mut(b).assign(&se),
+                None => return Err(format!("Bootstrap replication {} did not report standard errors", b)),
+            }
+        }
+    }
+
+    // leave-one-out estimates for the BCa acceleration
+    let jackknife = match scheme {
+        BootstrapScheme::WildRestrictedEfficient { .. } => None,
+        _ => {
+            let mut estimates = Array2::zeros((n, n_coefs));
+            for i in 0..n {
+                let rows: Vec<usize> = (0..n).filter(|&r| r != i).collect();
+                let loo = estimator(&y.select(Axis(0), &rows), &x_endog.select(Axis(0), &rows), &x_exog.select(Axis(0), &rows), &z.select(Axis(0), &rows))
+                    .map_err(|e| format!("Jackknife replication {} failed: {}", i, e))?;
+                estimates.row_mut(i).assign(&loo.params);
+            }
+            Some(estimates)
+        }
+    };
+
+    Ok(BootstrapResult {
+        estimate: fit.params,
+        std_errors: fit.std_errors,
+        draws,
+        draw_std_errors,
+        centre,
+        jackknife,
+        scheme: *scheme,
+    })
+}
@@ -0,0 +1,248 @@
+use super::covariance::{leverage_adjusted, score_meat, CovarianceType};
+use super::linear_regression::run_regression;
+use super::matrix_ops::{add_intercept, hstack, inverse_spd};
+use ndarray::{s, Array1, Array2, Axis};
+use statrs::distribution::{ChiSquared, ContinuousCDF};
+
+/// The result of a control-function fit, as returned by `run_control_function`.
+///
+/// This structure holds:
+/// - `params`: The slope coefficients of the structural equation, ordered as the endogenous
+///   regressors followed by the exogenous controls.
+/// - `intercept`: The fitted intercept, or `0.0` when the model was fit without one.
+/// - `residual_params`: The coefficients on the first-stage residuals, one per endogenous regressor.
+/// - `std_errors`: The standard errors of `params`, corrected for the first-stage estimation.
+/// - `intercept_std_error`: The corrected standard error of the intercept, or `None` without one.
+/// - `residual_std_errors`: The standard errors of `residual_params`.
+/// - `endogeneity_statistic`: The Wald statistic for the null that every `residual_params` entry
+///   is zero, i.e. that the regressors are exogenous.
+/// - `endogeneity_p_value`: The chi-squared p-value of `endogeneity_statistic`.
+/// - `covariance_type`: The `CovarianceType` used for the corrected standard errors.
+#[derive(Debug, Clone)]
+pub struct FittedControlFunction {
+    params: Array1<f64>,
+    intercept: f64,
+    residual_params: Array1<f64>,
+    std_errors: Array1<f64>,
+    intercept_std_error: Option<f64>,
+    residual_std_errors: Array1<f64>,
+    endogeneity_statistic: f64,
+    endogeneity_p_value: f64,
+    covariance_type: CovarianceType,
+}
+
+impl FittedControlFunction {
+    /// The slope coefficients: endogenous regressors first, then exogenous controls.
+    pub fn params(&self) -> &Array1<f64> {
+        &self.params
+    }
+
+    /// The fitted intercept, `0.0` if the model has none.
+    pub fn intercept(&self) -> f64 {
+        self.intercept
+    }
+
+    /// The coefficients on the first-stage residuals.
+    pub fn residual_params(&self) -> &Array1<f64> {
+        &self.residual_params
+    }
+
+    /// The corrected standard errors of the slope coefficients.
+    pub fn std_errors(&self) -> &Array1<f64> {
+        &self.std_errors
+    }
+
+    /// The corrected standard error of the intercept, `None` if the model has none.
+    pub fn intercept_std_error(&self) -> Option<f64> {
+        self.intercept_std_error
+    }
+
+    /// The corrected standard errors of the coefficients on the first-stage residuals.
+    pub fn residual_std_errors(&self) -> &Array1<f64> {
+        &self.residual_std_errors
+    }
+
+    /// The Wald statistic testing that the regressors are exogenous.
+    pub fn endogeneity_statistic(&self) -> f64 {
+        self.endogeneity_statistic
+    }
+
+    /// The chi-squared p-value of the endogeneity test.
+    pub fn endogeneity_p_value(&self) -> f64 {
+        self.endogeneity_p_value
+    }
+
+    /// The `CovarianceType` used for the corrected standard errors.
+    pub fn covariance_type(&self) -> &CovarianceType {
+        &self.covariance_type
+    }
+}
+
+/// Runs a control-function regression for endogenous regressors.
+///
+/// Each endogenous regressor is first regressed on the excluded instruments `z` and the exogenous
+/// controls `x_exog`. The first-stage residuals `v_hat` are then added to the structural equation,
+///
+/// `y = x_endog * beta + x_exog * gamma + v_hat * rho + e`,
+///
+/// which is fit by OLS. In this linear model the estimate of `beta` equals 2SLS, and the
+/// coefficients `rho` absorb the part of the structural error that is correlated with `x_endog`.
+/// Both stages are fit with `run_regression`.
+///
+/// Because `v_hat` is itself estimated, the usual OLS standard errors of the second stage are
+/// wrong. The covariance reported here stacks the influence functions of both stages, so that
+/// sampling error in the first-stage coefficients is carried into the second stage (the
+/// Murphy-Topel correction). The score of observation `i` is `r_i e_i + m_i u_i`, where `r_i` is
+/// its second-stage regressors, `e_i` the second-stage residual, `u_i = v_hat_i' rho` the
+/// first-stage residuals weighted by their coefficients and `m_i` maps the first-stage error
+/// into the second stage. `covariance` weights these scores as `run_regression` weights its own:
+/// `Classical` assumes `(e_i, u_i)` are homoskedastic, `HC2` and `HC3` adjust `e_i` and `u_i`
+/// by the second- and first-stage leverages, and the cluster and HAC types sum the scores within
+/// clusters or over nearby lags. Under the null of exogeneity `rho = 0` and the correction
+/// vanishes, so the Wald test on `rho` is the regression-based Durbin-Wu-Hausman test.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `covariance` - The `CovarianceType` used for the corrected standard errors.
+/// * `intercept` - A boolean indicating whether to include an intercept term in both stages.
+///
+/// # Returns
+///
+/// * `Ok(FittedControlFunction)` if the model fits successfully.
+/// * `Err(String)` if the inputs have mismatched rows, the model is under-identified, either
+///   stage fails, or the covariance cannot be computed.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::control_function::run_control_function;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+///
+/// let z = Array2::from_shape_vec((8, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + 2.0 * &u + &e;
+/// let fit = run_control_function(&y, &x, &Array2::zeros((8, 0)), &z, &CovarianceType::HC0, true).unwrap();
+/// println!("beta = {} ({})", fit.params()[0], fit.std_errors()[0]);
+/// println!("endogeneity p-value = {}", fit.endogeneity_p_value());
+/// ```
+pub fn run_control_function(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    covariance: &CovarianceType,
+    intercept: bool,
+) -> Result<FittedControlFunction, String> {
+    let n = y.nrows();
+    if y.ncols() != 1 {
+        return Err("y must have exactly one column".into());
+    }
+    if x_endog.nrows() != n || x_exog.nrows() != n || z.nrows() != n {
+        return Err("y, x_endog, x_exog and z must have the same number of rows".into());
+    }
+    if x_endog.ncols() == 0 {
+        return Err("x_endog must have at least one column".into());
+    }
+    if z.ncols() < x_endog.ncols() {
+        return Err(format!(
+            "Model is under-identified: {} instruments for {} endogenous regressors",
+            z.ncols(),
+            x_endog.ncols()
+        ));
+    }
+    let n_endog = x_endog.ncols();
+
+    // first stage: regress each endogenous regressor on [z, x_exog] and keep the residuals
+    let first_stage_x = hstack(&[z, x_exog])?;
+    let mut v_hat = Array2::<f64>::zeros((n, n_endog));
+    for j in 0..n_endog {
+        let x_j = x_endog.slice(s![.., j..j + 1]).to_owned();
+        let fit = run_regression(&first_stage_x, &x_j, &CovarianceType::Classical, intercept)
+            .map_err(|e| format!("First-stage regression failed: {}", e))?;
+        let fitted = first_stage_x.dot(fit.params()) + fit.intercept();
+        v_hat.column_mut(j).assign(&(&x_j.column(0) - &fitted));
+    }
+
+    // second stage: regress y on [x_endog, x_exog, v_hat]
+    let second_stage_x = hstack(&[x_endog, x_exog, &v_hat])?;
+    let fit = run_regression(&second_stage_x, y, &CovarianceType::Classical, intercept)
+        .map_err(|e| format!("Second-stage regression failed: {}", e))?;
+    let coefs = fit.params().to_owned();
+    let resid = y.column(0).to_owned() - second_stage_x.dot(&coefs) - fit.intercept();
+    let rho = coefs.slice(s![coefs.len() - n_endog..]).to_owned();
+
+    // influence functions of the first stage, one block of rows per endogenous regressor
+    let (z_full, r) = if intercept {
+        (add_intercept(&first_stage_x), add_intercept(&second_stage_x))
+    } else {
+        (first_stage_x, second_stage_x)
+    };
+    let n_f = n as f64;
+    let q_inv = inverse_spd(&(z_full.t().dot(&z_full) / n_f))
+        .map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+    let a_inv = inverse_spd(&(r.t().dot(&r) / n_f))
+        .map_err(|e| format!("Second-stage cross-product is singular: {}", e))?;
+
+    // the second-stage residual moves by rho_j * z_i' (pi_j_hat - pi_j), so the first-stage
+    // errors feed through m_i = z_i' Q^{-1} Z'R / n, weighted together by u_i = v_hat_i' rho
+    let m = z_full.dot(&q_inv).dot(&(z_full.t().dot(&r) / n_f));
+    let u = v_hat.dot(&rho);
+    let p = r.ncols();
+    let scores = |e: &Array1<f64>, u: &Array1<f64>| {
+        &r * &e.view().insert_axis(Axis(1)) + &m * &u.view().insert_axis(Axis(1))
+    };
+    let meat = match covariance {
+        CovarianceType::Classical => {
+            if n <= p {
+                return Err("Covariance estimation requires more observations than coefficients".into());
+            }
+            let df = (n - p) as f64;
+            let (s_ee, s_eu, s_uu) = (resid.dot(&resid) / df, resid.dot(&u) / df, u.dot(&u) / df);
+            let rm = r.t().dot(&m);
+            s_ee * r.t().dot(&r) + s_eu * (&rm + &rm.t()) + s_uu * m.t().dot(&m)
+        }
+        CovarianceType::HC2 | CovarianceType::HC3 => {
+            let second_leverage = (&r.dot(&a_inv) * &r).sum_axis(Axis(1)) / n_f;
+            let first_leverage = (&z_full.dot(&q_inv) * &z_full).sum_axis(Axis(1)) / n_f;
+            let resid = leverage_adjusted(&resid, &second_leverage, covariance)?;
+            let u = leverage_adjusted(&u, &first_leverage, covariance)?;
+            score_meat(&scores(&resid, &u), &CovarianceType::HC0, p)?
+        }
+        _ => score_meat(&scores(&resid, &u), covariance, p)?,
+    };
+    let coef_covariance = a_inv.dot(&meat).dot(&a_inv) / (n_f * n_f);
+
+    let std_all = coef_covariance.diag().mapv(f64::sqrt);
+    let n_slopes = x_endog.ncols() + x_exog.ncols();
+    let std_errors = std_all.slice(s![..n_slopes]).to_owned();
+    let residual_std_errors = std_all.slice(s![n_slopes..n_slopes + n_endog]).to_owned();
+    // the intercept is the last column of the second-stage design
+    let intercept_std_error = if intercept { Some(std_all[n_slopes + n_endog]) } else { None };
+
+    // Wald test of rho = 0
+    let v_rho = coef_covariance.slice(s![n_slopes..n_slopes + n_endog, n_slopes..n_slopes + n_endog]).to_owned();
+    let v_rho_inv = inverse_spd(&v_rho)
+        .map_err(|e| format!("Covariance of the residual coefficients is singular: {}", e))?;
+    let endogeneity_statistic = rho.dot(&v_rho_inv.dot(&rho));
+    let chi2 = ChiSquared::new(n_endog as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+    let endogeneity_p_value = 1.0 - chi2.cdf(endogeneity_statistic);
+
+    Ok(FittedControlFunction {
+        params: coefs.slice(s![..n_slopes]).to_owned(),
+        intercept: fit.intercept(),
+        residual_params: rho,
+        std_errors,
+        intercept_std_error,
+        residual_std_errors,
+        endogeneity_statistic,
+        endogeneity_p_value,
+        covariance_type: covariance.clone(),
+    })
+}
@@ -0,0 +1,298 @@
+use ndarray::{s, Array1, Array2, Axis};
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+/// The kernel used to weight the autocovariances of the scores in a `CovarianceType::Hac` fit.
+///
+/// - `Bartlett`: `k(x) = 1 - |x|` for `|x| <= 1`, the kernel of Newey and West (1987).
+/// - `Parzen`: `k(x) = 1 - 6x^2 + 6|x|^3` for `|x| <= 1/2` and `2(1 - |x|)^3` for
+///   `1/2 < |x| <= 1`.
+/// - `QuadraticSpectral`: `k(x) = 25 / (12 pi^2 x^2) (sin(6 pi x / 5) / (6 pi x / 5) - cos(6 pi x / 5))`,
+///   which puts weight on every lag and is optimal among positive semi-definite kernels
+///   (Andrews, 1991).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HacKernel {
+    Bartlett,
+    Parzen,
+    QuadraticSpectral,
+}
+
+impl HacKernel {
+    /// The kernel weight `k(x)`.
+    fn weight(&self, x: f64) -> f64 {
+        let x = x.abs();
+        match self {
+            HacKernel::Bartlett => (1.0 - x).max(0.0),
+            HacKernel::Parzen if x <= 0.5 => 1.0 - 6.0 * x.powi(2) + 6.0 * x.powi(3),
+            HacKernel::Parzen if x <= 1.0 => 2.0 * (1.0 - x).powi(3),
+            HacKernel::Parzen => 0.0,
+            HacKernel::QuadraticSpectral if x == 0.0 => 1.0,
+            HacKernel::QuadraticSpectral => {
+                let u = 6.0 * PI * x / 5.0;
+                25.0 / (12.0 * PI * PI * x * x) * (u.sin() / u - u.cos())
+            }
+        }
+    }
+}
+
+/// The estimator of the coefficient covariance matrix used by the regression functions.
+///
+/// - `Classical`: `s^2 (A'W)^{-1}`, valid under homoskedastic errors.
+/// - `HC0`: White's sandwich `(A'W)^{-1} A' diag(e_i^2) A (A'W)^{-1}`.
+/// - `HC1`: `HC0` scaled by `n / (n - p)`, the small-sample correction used by Stata's `robust`.
+/// - `HC2`: Weights each squared residual by `1 / (1 - h_i)`, where `h_i` is the leverage of
+///   observation `i`.
+/// - `HC3`: Weights each squared residual by `1 / (1 - h_i)^2`, which approximates the jackknife
+///   and is the most conservative of the four in small samples (MacKinnon and White, 1985).
+/// - `Cluster`: The one-way cluster-robust sandwich with meat `sum_g A_g' e_g e_g' A_g`, where the
+///   array holds one cluster identifier (e.g. a store) per observation. The meat is scaled by the
+///   usual `G / (G - 1) * (n - 1) / (n - p)` correction for `G` clusters.
+/// - `TwoWayCluster`: The two-way cluster-robust estimator of Cameron, Gelbach and Miller (2011),
+///   `V_1 + V_2 - V_12`, where `V_1` and `V_2` cluster on each set of identifiers (e.g. store and
+///   week) and `V_12` on their intersection. Each term carries its own small-sample correction.
+///   The difference is not guaranteed to be positive semi-definite in small samples.
+/// - `Hac`: The heteroskedasticity- and autocorrelation-consistent estimator with meat
+///   `Gamma_0 + sum_j k(j / b) (Gamma_j + Gamma_j')`, where `Gamma_j = sum_i s_i s_{i-j}'` are the
+///   autocovariances of the scores `s_i = a_i e_i`, so the observations must be in time order.
+///   `bandwidth` is `b`; with `None` it is chosen by the plug-in rule of Newey and West (1994).
+///   The Bartlett kernel with `b = L + 1` reproduces Newey-West with `L` lags. The meat is scaled
+///   by `n / (n - p)`, as in Stata's `newey`.
+///
+/// For OLS, `A` is the design matrix `W` itself. For the IV estimators, `A` holds the regressors
+/// as projected by the estimator, e.g. `P_Z W` for 2SLS. The variants without data can be parsed
+/// from `"classical"`, `"hc0"`, `"hc1"`, `"hc2"` or `"hc3"`, and the automatic-bandwidth HAC
+/// estimators from `"hac-bartlett"` (or `"hac"`), `"hac-parzen"` and `"hac-qs"`, ignoring case.
+///
+/// # Example
+///
+/// ```
+/// use marketing_iv_methods::utils::covariance::{CovarianceType, HacKernel};
+///
+/// let covariance: CovarianceType = "HC3".parse().unwrap();
+/// assert_eq!(covariance, CovarianceType::HC3);
+///
+/// // observations 0-1 belong to store 0 and observations 2-3 to store 1
+/// let by_store = CovarianceType::Cluster(ndarray::array![0, 0, 1, 1]);
+///
+/// // Newey-West with four lags
+/// let newey_west = CovarianceType::Hac { kernel: HacKernel::Bartlett, bandwidth: Some(5.0) };
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum CovarianceType {
+    Classical,
+    HC0,
+    HC1,
+    HC2,
+    HC3,
+    Cluster(Array1<usize>),
+    TwoWayCluster(Array1<usize>, Array1<usize>),
+    Hac { kernel: HacKernel, bandwidth: Option<f64> },
+}
+
+impl FromStr for CovarianceType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "classical" => Ok(CovarianceType::Classical),
+            "hc0" => Ok(CovarianceType::HC0),
+            "hc1" => Ok(CovarianceType::HC1),
+            "hc2" => Ok(CovarianceType::HC2),
+            "hc3" => Ok(CovarianceType::HC3),
+            "hac" | "hac-bartlett" => Ok(CovarianceType::Hac { kernel: HacKernel::Bartlett, bandwidth: None }),
+            "hac-parzen" => Ok(CovarianceType::Hac { kernel: HacKernel::Parzen, bandwidth: None }),
+            "hac-qs" => Ok(CovarianceType::Hac { kernel: HacKernel::QuadraticSpectral, bandwidth: None }),
+            _ => Err(format!("Unknown covariance type: {}", s)),
+        }
+    }
+}
+
+// a short label for reports, which leaves out the cluster identifiers
+impl fmt::Display for CovarianceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CovarianceType::Classical => write!(f, "classical"),
+            CovarianceType::HC0 => write!(f, "HC0"),
+            CovarianceType::HC1 => write!(f, "HC1"),
+            CovarianceType::HC2 => write!(f, "HC2"),
+            CovarianceType::HC3 => write!(f, "HC3"),
+            CovarianceType::Cluster(_) => write!(f, "cluster"),
+            CovarianceType::TwoWayCluster(_, _) => write!(f, "two-way cluster"),
+            CovarianceType::Hac { kernel, bandwidth: Some(bandwidth) } => write!(f, "HAC ({:?}, {})", kernel, bandwidth),
+            CovarianceType::Hac { kernel, bandwidth: None } => write!(f, "HAC ({:?}, auto)", kernel),
+        }
+    }
+}
+
+/// Computes the covariance of the coefficients `b = (A'W)^{-1} A'y` of a linear estimator.
+///
+/// `projected` is the matrix `A`, `bread` is `(A'W)^{-1}` and `resid` are the residuals
+/// `y - W b`. The leverages used by `HC2` and `HC3` are `h_i = a_i' (A'W)^{-1} a_i`, which for
+/// OLS are the diagonal of the hat matrix.
+pub(crate) fn coefficient_covariance(
+    projected: &Array2<f64>,
+    bread: &Array2<f64>,
+    resid: &Array1<f64>,
+    covariance: &CovarianceType,
+) -> Result<Array2<f64>, String> {
+    let n = projected.nrows();
+    let p = projected.ncols();
+    if n <= p {
+        return Err("Covariance estimation requires more observations than coefficients".into());
+    }
+
+    let meat = match covariance {
+        CovarianceType::Classical => {
+            let s2 = resid.mapv(|e| e * e).sum() / (n - p) as f64;
+            return Ok(s2 * bread);
+        }
+        CovarianceType::HC2 | CovarianceType::HC3 => {
+            let leverage = (&projected.dot(bread) * projected).sum_axis(Axis(1));
+            let resid = leverage_adjusted(resid, &leverage, covariance)?;
+            score_meat(&(projected * &resid.insert_axis(Axis(1))), &CovarianceType::HC0, p)?
+        }
+        _ => score_meat(&(projected * &resid.view().insert_axis(Axis(1))), covariance, p)?,
+    };
+
+    Ok(bread.dot(&meat).dot(&bread.t()))
+}
+
+/// Divides each residual by `sqrt(1 - h_i)` for `HC2` and by `1 - h_i` for `HC3`, so that the
+/// squared residuals carry the weights of the chosen estimator. Other covariance types leave
+/// the residuals unchanged.
+pub(crate) fn leverage_adjusted(
+    resid: &Array1<f64>,
+    leverage: &Array1<f64>,
+    covariance: &CovarianceType,
+) -> Result<Array1<f64>, String> {
+    let power = match covariance {
+        CovarianceType::HC2 => 0.5,
+        CovarianceType::HC3 => 1.0,
+        _ => return Ok(resid.to_owned()),
+    };
+    if leverage.iter().any(|h| 1.0 - h < 1e-10) {
+        return Err("An observation has leverage one, so HC2 and HC3 are undefined".into());
+    }
+    Ok(resid / &leverage.mapv(|h| (1.0 - h).powf(power)))
+}
+
+/// Computes the meat `sum_i s_i s_i'` of a sandwich covariance from the scores `s_i`, one row
+/// per observation, for an estimator with `p` coefficients.
+///
+/// `HC0` uses the scores as they are and `HC1` scales the result by `n / (n - p)`. The cluster
+/// and HAC types sum the scores within clusters or over nearby lags, with the small-sample
+/// corrections described on `CovarianceType`. `Classical`, `HC2` and `HC3` depend on the
+/// residuals and leverages rather than the scores alone, so they return an error here.
+pub(crate) fn score_meat(scores: &Array2<f64>, covariance: &CovarianceType, p: usize) -> Result<Array2<f64>, String> {
+    let n = scores.nrows();
+    if n <= p {
+        return Err("Covariance estimation requires more observations than coefficients".into());
+    }
+
+    match covariance {
+        CovarianceType::HC0 => Ok(scores.t().dot(scores)),
+        CovarianceType::HC1 => Ok(scores.t().dot(scores) * (n as f64 / (n - p) as f64)),
+        CovarianceType::Cluster(ids) => cluster_meat(scores, &dense_cluster_ids(ids.iter().copied(), n)?, p),
+        CovarianceType::TwoWayCluster(first, second) => {
+            if first.len() != n || second.len() != n {
+                return Err("Cluster identifiers must have one entry per observation".into());
+            }
+            let first_meat = cluster_meat(scores, &dense_cluster_ids(first.iter().copied(), n)?, p)?;
+            let second_meat = cluster_meat(scores, &dense_cluster_ids(second.iter().copied(), n)?, p)?;
+            let intersection = dense_cluster_ids(first.iter().copied().zip(second.iter().copied()), n)?;
+            Ok(first_meat + second_meat - cluster_meat(scores, &intersection, p)?)
+        }
+        CovarianceType::Hac { kernel, bandwidth } => {
+            let bandwidth = match bandwidth {
+                Some(b) if *b > 0.0 => *b,
+                Some(_) => return Err("HAC bandwidth must be positive".into()),
+                None => automatic_bandwidth(scores, *kernel),
+            };
+            let mut meat = scores.t().dot(scores);
+            for lag in 1..n {
+                let weight = kernel.weight(lag as f64 / bandwidth);
+                if weight == 0.0 && *kernel != HacKernel::QuadraticSpectral {
+                    break;
+                }
+                let gamma = scores.slice(s![lag.., ..]).t().dot(&scores.slice(s![..n - lag, ..]));
+                meat = meat + weight * (&gamma + &gamma.t());
+            }
+            Ok(meat * (n as f64 / (n - p) as f64))
+        }
+        CovarianceType::Classical | CovarianceType::HC2 | CovarianceType::HC3 => {
+            Err(format!("{} covariance cannot be computed from the scores alone", covariance))
+        }
+    }
+}
+
+/// Relabels cluster identifiers as `0..G` in order of first appearance, returning the labels and
+/// the number of clusters `G`.
+fn dense_cluster_ids<T, I>(ids: I, n: usize) -> Result<(Vec<usize>, usize), String>
+where
+    T: Eq + std::hash::Hash,
+    I: Iterator<Item = T>,
+{
+    let mut labels = HashMap::new();
+    let dense: Vec<usize> = ids
+        .map(|id| {
+            let next = labels.len();
+            *labels.entry(id).or_insert(next)
+        })
+        .collect();
+    if dense.len() != n {
+        return Err("Cluster identifiers must have one entry per observation".into());
+    }
+    Ok((dense, labels.len()))
+}
+
+/// Returns the small-sample corrected meat `c * sum_g s_g s_g'`, where `s_g` is the sum of the
+/// rows of `scores` in cluster `g` and `c = G / (G - 1) * (n - 1) / (n - p)`.
+fn cluster_meat(scores: &Array2<f64>, clusters: &(Vec<usize>, usize), p: usize) -> Result<Array2<f64>, String> {
+    let (labels, n_clusters) = clusters;
+    if *n_clusters < 2 {
+        return Err("Cluster-robust covariance requires at least two clusters".into());
+    }
+    let n = scores.nrows();
+    let mut cluster_scores = Array2::<f64>::zeros((*n_clusters, scores.ncols()));
+    for (row, &label) in scores.outer_iter().zip(labels.iter()) {
+        let mut total = cluster_scores.row_mut(label);
+        total += &row;
+    }
+    let correction = *n_clusters as f64 / (*n_clusters - 1) as f64 * (n - 1) as f64 / (n - p) as f64;
+    Ok(correction * cluster_scores.t().dot(&cluster_scores))
+}
+
+/// Returns the Newey and West (1994) plug-in bandwidth for `kernel`.
+///
+/// The scores are summed across coefficients into a single series `f_i`, whose autocovariances
+/// `sigma_j` up to a pre-set lag `m` give `s_0 = sigma_0 + 2 sum_j sigma_j` and
+/// `s_q = 2 sum_j j^q sigma_j`. The bandwidth is `c (s_q / s_0)^{2 / (2q + 1)} n^{1 / (2q + 1)}`,
+/// with `q = 1, c = 1.1447` for Bartlett, `q = 2, c = 2.6614` for Parzen and
+/// `q = 2, c = 1.3221` for the quadratic-spectral kernel.
+fn automatic_bandwidth(scores: &Array2<f64>, kernel: HacKernel) -> f64 {
+    let n = scores.nrows();
+    let n_f = n as f64;
+    let (q, c, exponent) = match kernel {
+        HacKernel::Bartlett => (1, 1.1447, 2.0 / 9.0),
+        HacKernel::Parzen => (2, 2.6614, 4.0 / 25.0),
+        HacKernel::QuadraticSpectral => (2, 1.3221, 2.0 / 25.0),
+    };
+    let max_lag = ((4.0 * (n_f / 100.0).powf(exponent)) as usize).min(n - 1);
+
+    let f = scores.sum_axis(Axis(1));
+    let sigma = |j: usize| f.slice(s![j..]).dot(&f.slice(s![..n - j])) / n_f;
+    let mut s0 = sigma(0);
+    let mut sq = 0.0;
+    for j in 1..=max_lag {
+        let sigma_j = sigma(j);
+        s0 += 2.0 * sigma_j;
+        sq += 2.0 * (j as f64).powi(q) * sigma_j;
+    }
+
+    let rate = 1.0 / (2 * q + 1) as f64;
+    let bandwidth = c * ((sq / s0).powi(2)).powf(rate) * n_f.powf(rate);
+    // fall back to no autocorrelation correction when the scores carry no serial dependence
+    if bandwidth.is_finite() && bandwidth > 0.0 { bandwidth } else { 1.0 }
+}
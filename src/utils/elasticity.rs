@@ -0,0 +1,234 @@
+use super::linear_regression::{FittedIvRegression, FittedOls};
+use ndarray::{s, Array1, Array2, Axis};
+use statrs::distribution::{ContinuousCDF, Normal};
+
+/// The functional form of a demand equation fit by `run_regression` or an IV estimator.
+///
+/// - `Linear`: `q = a + beta * p + ...`, whose own-price elasticity `beta * p / q` varies along
+///   the demand curve.
+/// - `LogLog`: `ln q = a + beta * ln p + ...`, whose own-price elasticity is `beta` everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DemandSpecification {
+    Linear,
+    LogLog,
+}
+
+/// The part of a fitted demand model that the elasticity functions need.
+///
+/// This structure holds:
+/// - `params`: The slope coefficients, in the order of the columns of the regressors.
+/// - `intercept`: The fitted intercept, or `0.0` when the model was fit without one.
+/// - `covariance`: The covariance matrix of the slopes, followed by the intercept when the model
+///   has one.
+///
+/// `From` conversions are provided for the fits returned by `run_regression` and the k-class
+/// estimators.
+#[derive(Debug, Clone)]
+pub struct DemandFit {
+    pub params: Array1<f64>,
+    pub intercept: f64,
+    pub covariance: Array2<f64>,
+}
+
+impl From<&FittedOls> for DemandFit {
+    fn from(fit: &FittedOls) -> Self {
+        DemandFit { params: fit.params().clone(), intercept: fit.intercept(), covariance: fit.covariance().clone() }
+    }
+}
+
+impl From<&FittedIvRegression> for DemandFit {
+    fn from(fit: &FittedIvRegression) -> Self {
+        DemandFit { params: fit.params().clone(), intercept: fit.intercept(), covariance: fit.covariance().clone() }
+    }
+}
+
+/// Own-price elasticities with delta-method standard errors, as returned by
+/// `elasticity_at_means` and `elasticity_per_observation`.
+///
+/// This structure holds:
+/// - `estimates`: The elasticities, one per evaluation point.
+/// - `std_errors`: Their delta-method standard errors.
+/// - `specification`: The `DemandSpecification` of the demand model.
+#[derive(Debug, Clone)]
+pub struct Elasticity {
+    estimates: Array1<f64>,
+    std_errors: Array1<f64>,
+    specification: DemandSpecification,
+}
+
+impl Elasticity {
+    /// The elasticities, one per evaluation point.
+    pub fn estimates(&self) -> &Array1<f64> {
+        &self.estimates
+    }
+
+    /// The delta-method standard errors of the elasticities.
+    pub fn std_errors(&self) -> &Array1<f64> {
+        &self.std_errors
+    }
+
+    /// The functional form of the demand model.
+    pub fn specification(&self) -> DemandSpecification {
+        self.specification
+    }
+
+    /// Normal-approximation confidence intervals, one row of lower and upper bounds per
+    /// evaluation point.
+    pub fn conf_int(&self, confidence: f64) -> Result<Array2<f64>, String> {
+        if !(confidence > 0.0 && confidence < 1.0) {
+            return Err("confidence must be between 0 and 1".into());
+        }
+        let normal = Normal::new(0.0, 1.0).map_err(|e| format!("Invalid normal distribution: {}", e))?;
+        let critical = normal.inverse_cdf(0.5 + confidence / 2.0);
+        let mut intervals = Array2::zeros((self.estimates.len(), 2));
+        for (i, (estimate, se)) in self.estimates.iter().zip(self.std_errors.iter()).enumerate() {
+            intervals[[i, 0]] = estimate - critical * se;
+            intervals[[i, 1]] = estimate + critical * se;
+        }
+        Ok(intervals)
+    }
+}
+
+/// Computes the elasticity and its delta-method standard error at each row of `points`.
+fn elasticity_at_points(
+    fit: &DemandFit,
+    points: &Array2<f64>,
+    price: usize,
+    specification: DemandSpecification,
+) -> Result<Elasticity, String> {
+    let k = fit.params.len();
+    if points.ncols() != k {
+        return Err(format!("The regressors have {} columns for {} coefficients", points.ncols(), k));
+    }
+    if price >= k {
+        return Err(format!("The price column {} is out of range for {} coefficients", price, k));
+    }
+    let has_intercept = match fit.covariance.dim() {
+        (rows, cols) if rows == k && cols == k => false,
+        (rows, cols) if rows == k + 1 && cols == k + 1 => true,
+        _ => return Err("The covariance must cover the slopes, and the intercept when there is one".into()),
+    };
+    let beta = fit.params[price];
+
+    let mut estimates = Array1::zeros(points.nrows());
+    let mut std_errors = Array1::zeros(points.nrows());
+    let mut gradient = Array1::zeros(fit.covariance.nrows());
+    for (i, point) in points.outer_iter().enumerate() {
+        gradient.fill(0.0);
+        match specification {
+            DemandSpecification::LogLog => {
+                estimates[i] = beta;
+                gradient[price] = 1.0;
+            }
+            DemandSpecification::Linear => {
+                // e = beta p / q(x), with q(x) = a + x'b
+                let p = point[price];
+                let q = fit.intercept + point.dot(&fit.params);
+                if q.abs() < 1e-12 {
+                    return Err("Predicted demand is zero at an evaluation point, so the elasticity is undefined".into());
+                }
+                estimates[i] = beta * p / q;
+                gradient.slice_mut(s![..k]).assign(&(&point * (-beta * p / (q * q))));
+                gradient[price] += p / q;
+                if has_intercept {
+                    gradient[k] = -beta * p / (q * q);
+                }
+            }
+        }
+        std_errors[i] = gradient.dot(&fit.covariance.dot(&gradient)).max(0.0).sqrt();
+    }
+
+    Ok(Elasticity { estimates, std_errors, specification })
+}
+
+/// Computes the own-price elasticity of demand at the sample means of the regressors.
+///
+/// For a `Linear` demand model the elasticity at the means is `beta * p_bar / q_hat`, where
+/// `q_hat = a + x_bar' b` is the predicted demand at the means of every regressor. For a
+/// `LogLog` model `x` holds log prices and the elasticity is `beta` itself.
+///
+/// The standard error follows from the delta method, `sqrt(g' V g)`, with `g` the gradient of
+/// the elasticity with respect to the slopes and the intercept and `V` their covariance. The
+/// means are treated as fixed, so the standard error inherits whatever robustness the
+/// covariance of the fit has.
+///
+/// # Arguments
+///
+/// * `fit` - The `DemandFit` of the demand model, e.g. `DemandFit::from(&fitted_ols)`.
+/// * `x` - A 2D array (`Array2<f64>`) of the regressors the model was fit on, one column per
+///   slope coefficient.
+/// * `price` - The column of `x` holding the price, or the log price for `LogLog`.
+/// * `specification` - The `DemandSpecification` of the model.
+///
+/// # Returns
+///
+/// * `Ok(Elasticity)` with a single elasticity.
+/// * `Err(String)` if `x` does not match the coefficients, `price` is out of range, the
+///   covariance has the wrong shape, or predicted demand at the means is zero.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::elasticity::{elasticity_at_means, DemandFit, DemandSpecification};
+/// use marketing_iv_methods::utils::linear_regression::run_regression;
+///
+/// let price = Array2::from_shape_vec((6, 1), vec![8.0, 9.0, 10.0, 11.0, 12.0, 10.0]).unwrap();
+/// let quantity = Array2::from_shape_vec((6, 1), vec![24.1, 23.4, 23.1, 22.4, 22.1, 22.9]).unwrap();
+/// let fit = run_regression(&price, &quantity, &CovarianceType::HC1, true).unwrap();
+/// let elasticity = elasticity_at_means(&DemandFit::from(&fit), &price, 0, DemandSpecification::Linear).unwrap();
+/// println!("{} ({})", elasticity.estimates()[0], elasticity.std_errors()[0]);
+/// ```
+pub fn elasticity_at_means(
+    fit: &DemandFit,
+    x: &Array2<f64>,
+    price: usize,
+    specification: DemandSpecification,
+) -> Result<Elasticity, String> {
+    let means = x.mean_axis(Axis(0)).ok_or("The regressors have no observations")?;
+    elasticity_at_points(fit, &means.insert_axis(Axis(0)), price, specification)
+}
+
+/// Computes the own-price elasticity of demand at every observation.
+///
+/// For a `Linear` demand model observation `i` has elasticity `beta * p_i / q_hat_i`, with
+/// `q_hat_i = a + x_i' b` its predicted demand, so the elasticity grows in magnitude along the
+/// demand curve. For a `LogLog` model every observation has elasticity `beta`. Each elasticity
+/// has a delta-method standard error computed as in `elasticity_at_means`.
+///
+/// # Arguments
+///
+/// * `fit` - The `DemandFit` of the demand model.
+/// * `x` - A 2D array (`Array2<f64>`) of the regressors, one row per evaluation point.
+/// * `price` - The column of `x` holding the price, or the log price for `LogLog`.
+/// * `specification` - The `DemandSpecification` of the model.
+///
+/// # Returns
+///
+/// * `Ok(Elasticity)` with one elasticity per row of `x`.
+/// * `Err(String)` if `x` does not match the coefficients, `price` is out of range, the
+///   covariance has the wrong shape, or predicted demand is zero at some observation.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::elasticity::{elasticity_per_observation, DemandFit, DemandSpecification};
+/// use marketing_iv_methods::utils::linear_regression::run_regression;
+///
+/// let price = Array2::from_shape_vec((6, 1), vec![8.0, 9.0, 10.0, 11.0, 12.0, 10.0]).unwrap();
+/// let quantity = Array2::from_shape_vec((6, 1), vec![24.1, 23.4, 23.1, 22.4, 22.1, 22.9]).unwrap();
+/// let fit = run_regression(&price, &quantity, &CovarianceType::Classical, true).unwrap();
+/// let elasticities = elasticity_per_observation(&DemandFit::from(&fit), &price, 0, DemandSpecification::Linear).unwrap();
+/// assert_eq!(elasticities.estimates().len(), 6);
+/// ```
+pub fn elasticity_per_observation(
+    fit: &DemandFit,
+    x: &Array2<f64>,
+    price: usize,
+    specification: DemandSpecification,
+) -> Result<Elasticity, String> {
+    elasticity_at_points(fit, x, price, specification)
+}
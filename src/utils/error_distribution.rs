@@ -0,0 +1,359 @@
+use super::random_vector_gen::random_vector_generate;
+use ndarray::Array2;
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::{Distribution, Exp1, StandardNormal, StudentT};
+use statrs::distribution::{ContinuousCDF, Normal, StudentsT};
+use std::f64::consts::{FRAC_1_SQRT_2, PI};
+use std::fmt;
+
+/// The shape of the distribution of a simulated confounder or error term.
+///
+/// Every variant is standardised to mean zero and variance one, so the scale of a draw is set
+/// separately by a standard deviation such as `sigma_ex`, and the bias formulas that depend
+/// only on variances hold whatever the shape:
+///
+/// - `Normal`: The standard normal distribution.
+/// - `StudentT { df }`: Student's t with `df` degrees of freedom, for heavy tails. `df` must be
+///   greater than 2 for the variance to exist.
+/// - `SkewNormal { shape }`: Azzalini's skew-normal distribution, skewed to the right when
+///   `shape` is positive and to the left when it is negative. `shape = 0` is the normal.
+/// - `LogNormal { log_sd }`: The centred log-normal distribution `exp(log_sd * u)`, with `u`
+///   standard normal, which is strongly right-skewed for large `log_sd`.
+/// - `Laplace`: The double exponential distribution, with a sharper peak and heavier tails than
+///   the normal.
+/// - `NormalMixture { weights, means, std_devs }`: A finite mixture of normals, which can be
+///   skewed, heavy-tailed or multimodal. The weights must be positive and sum to one.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::utils::error_distribution::ErrorDistribution;
+///
+/// let heavy = ErrorDistribution::StudentT { df: 3.0 };
+/// assert!(heavy.validate().is_ok());
+/// assert!(ErrorDistribution::StudentT { df: 2.0 }.validate().is_err());
+/// println!("{}", heavy);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ErrorDistribution {
+    #[default]
+    Normal,
+    StudentT { df: f64 },
+    SkewNormal { shape: f64 },
+    LogNormal { log_sd: f64 },
+    Laplace,
+    NormalMixture { weights: Vec<f64>, means: Vec<f64>, std_devs: Vec<f64> },
+}
+
+impl ErrorDistribution {
+    /// Checks the parameters of the distribution.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the distribution has mean zero and variance one after standardisation.
+    /// * `Err(String)` if `df` is not greater than 2, `shape` or `log_sd` is not finite, `log_sd`
+    ///   is not positive, or the mixture is empty, has components of different lengths, weights
+    ///   that are not positive or do not sum to one, or standard deviations that are not positive.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ErrorDistribution::Normal | ErrorDistribution::Laplace => Ok(()),
+            ErrorDistribution::StudentT { df } => {
+                if *df > 2.0 {
+                    Ok(())
+                } else {
+                    Err(format!("The degrees of freedom of Student's t must be greater than 2, got {}", df))
+                }
+            }
+            ErrorDistribution::SkewNormal { shape } => {
+                if shape.is_finite() {
+                    Ok(())
+                } else {
+                    Err(format!("The skew-normal shape must be finite, got {}", shape))
+                }
+            }
+            ErrorDistribution::LogNormal { log_sd } => {
+                if *log_sd > 0.0 && log_sd.is_finite() {
+                    Ok(())
+                } else {
+                    Err(format!("The log-normal log_sd must be positive and finite, got {}", log_sd))
+                }
+            }
+            ErrorDistribution::NormalMixture { weights, means, std_devs } => {
+                if weights.is_empty() {
+                    return Err("The normal mixture must have at least one component".into());
+                }
+                if means.len() != weights.len() || std_devs.len() != weights.len() {
+                    return Err("The normal mixture must have as many means and standard deviations as weights".into());
+                }
+                if weights.iter().any(|w| !(*w > 0.0 && w.is_finite())) {
+                    return Err("The normal mixture weights must be positive and finite".into());
+                }
+                if (weights.iter().sum::<f64>() - 1.0).abs() > 1e-8 {
+                    return Err("The normal mixture weights must sum to one".into());
+                }
+                if means.iter().any(|m| !m.is_finite()) {
+                    return Err("The normal mixture means must be finite".into());
+                }
+                if std_devs.iter().any(|s| !(*s > 0.0 && s.is_finite())) {
+                    return Err("The normal mixture standard deviations must be positive and finite".into());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The distribution standardised to mean zero and variance one, ready to sample from or to
+    /// evaluate.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(StandardizedDistribution)` that draws standardised values and gives their CDF and
+    ///   quantiles.
+    /// * `Err(String)` if the parameters are invalid, as reported by `validate`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use marketing_iv_methods::utils::error_distribution::ErrorDistribution;
+    ///
+    /// let laplace = ErrorDistribution::Laplace.standardized().unwrap();
+    /// assert!(laplace.quantile(0.5).abs() < 1e-12);
+    /// assert!((laplace.cdf(laplace.quantile(0.9)) - 0.9).abs() < 1e-12);
+    /// ```
+    pub fn standardized(&self) -> Result<StandardizedDistribution, String> {
+        self.validate()?;
+        let standardized = match self {
+            ErrorDistribution::Normal => Standardized::Normal,
+            ErrorDistribution::StudentT { df } => Standardized::StudentT {
+                dist: StudentT::new(*df).map_err(|e| format!("Invalid Student's t: {}", e))?,
+                cdf: StudentsT::new(0.0, 1.0, *df).map_err(|e| format!("Invalid Student's t: {}", e))?,
+                scale: ((df - 2.0) / df).sqrt(),
+            },
+            ErrorDistribution::SkewNormal { shape } => {
+                // x = delta |u0| + sqrt(1 - delta^2) u1 has mean delta sqrt(2 / pi)
+                let delta = shape / (1.0 + shape * shape).sqrt();
+                let mean = delta * (2.0 / PI).sqrt();
+                Standardized::SkewNormal { shape: *shape, delta, mean, sd: (1.0 - mean * mean).sqrt() }
+            }
+            ErrorDistribution::LogNormal { log_sd } => {
+                let variance = log_sd * log_sd;
+                Standardized::LogNormal {
+                    log_sd: *log_sd,
+                    mean: (variance / 2.0).exp(),
+                    sd: ((variance.exp() - 1.0) * variance.exp()).sqrt(),
+                }
+            }
+            ErrorDistribution::Laplace => Standardized::Laplace,
+            ErrorDistribution::NormalMixture { weights, means, std_devs } => {
+                let mean = weights.iter().zip(means).map(|(w, m)| w * m).sum::<f64>();
+                let second_moment = weights.iter().zip(means.iter().zip(std_devs)).map(|(w, (m, s))| w * (s * s + m * m)).sum::<f64>();
+                let cumulative = weights.iter().scan(0.0, |total, w| { *total += w; Some(*total) }).collect();
+                Standardized::NormalMixture {
+                    cumulative,
+                    means: means.clone(),
+                    std_devs: std_devs.clone(),
+                    mean,
+                    sd: (second_moment - mean * mean).sqrt(),
+                }
+            }
+        };
+        Ok(StandardizedDistribution { standardized })
+    }
+}
+
+impl fmt::Display for ErrorDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorDistribution::Normal => write!(f, "normal"),
+            ErrorDistribution::StudentT { df } => write!(f, "t({})", df),
+            ErrorDistribution::SkewNormal { shape } => write!(f, "skew-normal({})", shape),
+            ErrorDistribution::LogNormal { log_sd } => write!(f, "log-normal({})", log_sd),
+            ErrorDistribution::Laplace => write!(f, "laplace"),
+            ErrorDistribution::NormalMixture { weights, .. } => write!(f, "normal mixture({})", weights.len()),
+        }
+    }
+}
+
+/// The constants that standardise each `ErrorDistribution`.
+#[derive(Debug, Clone)]
+enum Standardized {
+    Normal,
+    StudentT { dist: StudentT<f64>, cdf: StudentsT, scale: f64 },
+    SkewNormal { shape: f64, delta: f64, mean: f64, sd: f64 },
+    LogNormal { log_sd: f64, mean: f64, sd: f64 },
+    Laplace,
+    NormalMixture { cumulative: Vec<f64>, means: Vec<f64>, std_devs: Vec<f64>, mean: f64, sd: f64 },
+}
+
+/// An `ErrorDistribution` standardised to mean zero and variance one, as returned by
+/// `ErrorDistribution::standardized`.
+///
+/// It implements `Distribution<f64>`, so it can be passed to `random_vector_generate`, and
+/// provides the CDF and quantile function that turn the uniform draws of a copula into draws
+/// with this shape.
+#[derive(Debug, Clone)]
+pub struct StandardizedDistribution {
+    standardized: Standardized,
+}
+
+impl StandardizedDistribution {
+    /// The cumulative distribution function at `x`.
+    pub fn cdf(&self, x: f64) -> f64 {
+        let unit_normal = standard_normal();
+        match &self.standardized {
+            Standardized::Normal => unit_normal.cdf(x),
+            Standardized::StudentT { cdf, scale, .. } => cdf.cdf(x / scale),
+            Standardized::SkewNormal { shape, mean, sd, .. } => {
+                let raw = mean + sd * x;
+                (unit_normal.cdf(raw) - 2.0 * owens_t(raw, *shape)).clamp(0.0, 1.0)
+            }
+            Standardized::LogNormal { log_sd, mean, sd } => {
+                let raw = mean + sd * x;
+                if raw <= 0.0 { 0.0 } else { unit_normal.cdf(raw.ln() / log_sd) }
+            }
+            Standardized::Laplace => {
+                // unit variance needs the scale 1 / sqrt(2)
+                if x < 0.0 { 0.5 * (x / FRAC_1_SQRT_2).exp() } else { 1.0 - 0.5 * (-x / FRAC_1_SQRT_2).exp() }
+            }
+            Standardized::NormalMixture { cumulative, means, std_devs, mean, sd } => {
+                let raw = mean + sd * x;
+                let mut previous = 0.0;
+                let mut total = 0.0;
+                for ((c, m), s) in cumulative.iter().zip(means).zip(std_devs) {
+                    total += (c - previous) * unit_normal.cdf((raw - m) / s);
+                    previous = *c;
+                }
+                total
+            }
+        }
+    }
+
+    /// The quantile function at the probability `p`, which must lie strictly between 0 and 1.
+    ///
+    /// The normal, Student's t, log-normal and Laplace quantiles are exact. The skew-normal and
+    /// normal-mixture quantiles invert the CDF by bisection, to an absolute accuracy of about
+    /// `1e-10`.
+    pub fn quantile(&self, p: f64) -> f64 {
+        let unit_normal = standard_normal();
+        match &self.standardized {
+            Standardized::Normal => unit_normal.inverse_cdf(p),
+            Standardized::StudentT { cdf, scale, .. } => scale * cdf.inverse_cdf(p),
+            Standardized::LogNormal { log_sd, mean, sd } => ((log_sd * unit_normal.inverse_cdf(p)).exp() - mean) / sd,
+            Standardized::Laplace => -FRAC_1_SQRT_2 * (p - 0.5).signum() * (1.0 - 2.0 * (p - 0.5).abs()).ln(),
+            Standardized::SkewNormal { .. } | Standardized::NormalMixture { .. } => self.invert_cdf(p),
+        }
+    }
+
+    /// Finds the `x` with `cdf(x) = p` by bracketing and bisection.
+    fn invert_cdf(&self, p: f64) -> f64 {
+        let (mut lower, mut upper) = (-1.0, 1.0);
+        while self.cdf(lower) > p && lower > -1e6 {
+            lower *= 2.0;
+        }
+        while self.cdf(upper) < p && upper < 1e6 {
+            upper *= 2.0;
+        }
+        while upper - lower > 1e-10 {
+            let middle = 0.5 * (lower + upper);
+            if self.cdf(middle) < p {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+        0.5 * (lower + upper)
+    }
+}
+
+fn standard_normal() -> Normal {
+    Normal::new(0.0, 1.0).expect("the standard normal is valid")
+}
+
+/// Owen's T function, `T(h, a) = (1 / 2 pi) * integral over [0, a] of
+/// exp(-h^2 (1 + t^2) / 2) / (1 + t^2) dt`, which gives the skew-normal CDF.
+///
+/// The substitution `t = tan(u)` turns the integral into one of `exp(-h^2 / (2 cos^2 u))` over
+/// `[0, atan(a)]`, whose integrand is smooth and bounded, so Simpson's rule converges quickly.
+fn owens_t(h: f64, a: f64) -> f64 {
+    const INTERVALS: usize = 200;
+    let end = a.atan();
+    let step = end / INTERVALS as f64;
+    let integrand = |u: f64| (-0.5 * h * h / (u.cos() * u.cos())).exp();
+    let mut total = integrand(0.0) + integrand(end);
+    for i in 1..INTERVALS {
+        let weight = if i % 2 == 1 { 4.0 } else { 2.0 };
+        total += weight * integrand(i as f64 * step);
+    }
+    total * step / 3.0 / (2.0 * PI)
+}
+
+impl Distribution<f64> for StandardizedDistribution {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match &self.standardized {
+            Standardized::Normal => rng.sample(StandardNormal),
+            Standardized::StudentT { dist, scale, .. } => scale * dist.sample(rng),
+            Standardized::SkewNormal { delta, mean, sd, .. } => {
+                let u0: f64 = rng.sample(StandardNormal);
+                let u1: f64 = rng.sample(StandardNormal);
+                (delta * u0.abs() + (1.0 - delta * delta).sqrt() * u1 - mean) / sd
+            }
+            Standardized::LogNormal { log_sd, mean, sd } => {
+                let u: f64 = rng.sample(StandardNormal);
+                ((log_sd * u).exp() - mean) / sd
+            }
+            Standardized::Laplace => {
+                // the difference of two unit exponentials is Laplace with variance 2
+                let a: f64 = rng.sample(Exp1);
+                let b: f64 = rng.sample(Exp1);
+                (a - b) / 2.0_f64.sqrt()
+            }
+            Standardized::NormalMixture { cumulative, means, std_devs, mean, sd } => {
+                let u: f64 = rng.gen();
+                let component = cumulative.iter().position(|c| u < *c).unwrap_or(cumulative.len() - 1);
+                let draw: f64 = rng.sample(StandardNormal);
+                (means[component] + std_devs[component] * draw - mean) / sd
+            }
+        }
+    }
+}
+
+/// Generates a vector of `n` draws with standard deviation `sigma` and the shape of
+/// `distribution`.
+///
+/// Each draw is `sigma` times a draw from the standardised `distribution`, so the result has
+/// mean zero and standard deviation `sigma` whatever the shape. With
+/// `ErrorDistribution::Normal` this is `random_vector_generate` with `Normal::new(0.0, sigma)`.
+///
+/// # Parameters
+///
+/// - `n`: The number of draws.
+/// - `distribution`: The `ErrorDistribution` giving the shape of the draws.
+/// - `sigma`: The standard deviation of the draws.
+/// - `rng`: The random number generator the draws are taken from.
+///
+/// # Returns
+///
+/// - `Ok(Array2<f64>)` of shape `(n, 1)` holding the draws.
+/// - `Err(String)` if `sigma` is not positive and finite or `distribution` is invalid.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::utils::error_distribution::{error_vector_generate, ErrorDistribution};
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let skewed = ErrorDistribution::LogNormal { log_sd: 1.0 };
+/// let draws = error_vector_generate(1000, &skewed, 2.0, &mut seeded_rng(42)).unwrap();
+/// assert_eq!(draws.shape(), &[1000, 1]);
+/// ```
+pub fn error_vector_generate<R: Rng + ?Sized>(
+    n: usize,
+    distribution: &ErrorDistribution,
+    sigma: f64,
+    rng: &mut R,
+) -> Result<Array2<f64>, String> {
+    if !(sigma > 0.0 && sigma.is_finite()) {
+        return Err(format!("The standard deviation must be positive and finite, got {}", sigma));
+    }
+    Ok(sigma * random_vector_generate(n, distribution.standardized()?, rng))
+}
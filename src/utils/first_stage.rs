@@ -0,0 +1,453 @@
+use super::matrix_ops::{
+    add_intercept, eigh_descending, generalized_eigenvalues, inverse_spd, kron, min_generalized_eigenvalue, residualize,
+    solve_general, solve_spd, symmetric_sqrt,
+};
+use ndarray::{s, Array1, Array2, Axis};
+use statrs::distribution::{ChiSquared, ContinuousCDF, FisherSnedecor};
+use statrs::function::gamma::ln_gamma;
+use std::fmt;
+
+// Stock and Yogo (2005), Table 5.1: critical values of the Cragg-Donald F for a maximal 2SLS
+// bias relative to OLS of 5%, 10%, 20% and 30%, by (endogenous regressors, instruments)
+const STOCK_YOGO_BIAS_LEVELS: [f64; 4] = [0.05, 0.10, 0.20, 0.30];
+const STOCK_YOGO_BIAS: [(usize, usize, [f64; 4]); 9] = [
+    (1, 3, [13.91, 9.08, 6.46, 5.39]),
+    (1, 4, [16.85, 10.27, 6.71, 5.34]),
+    (1, 5, [18.37, 10.83, 6.77, 5.25]),
+    (1, 6, [19.28, 11.12, 6.76, 5.15]),
+    (1, 7, [19.86, 11.29, 6.73, 5.07]),
+    (1, 8, [20.25, 11.39, 6.69, 4.99]),
+    (1, 9, [20.53, 11.46, 6.65, 4.93]),
+    (1, 10, [20.74, 11.49, 6.61, 4.87]),
+    (2, 4, [11.04, 7.56, 5.57, 4.73]),
+];
+
+// Stock and Yogo (2005), Table 5.2: critical values of the Cragg-Donald F for a maximal size of
+// a nominal 5% 2SLS Wald test of 10%, 15%, 20% and 25%
+const STOCK_YOGO_SIZE_LEVELS: [f64; 4] = [0.10, 0.15, 0.20, 0.25];
+#[allow(clippy::approx_constant)]
+const STOCK_YOGO_SIZE: [(usize, usize, [f64; 4]); 13] = [
+    (1, 1, [16.38, 8.96, 6.66, 5.53]),
+    (1, 2, [19.93, 11.59, 8.75, 7.25]),
+    (1, 3, [22.30, 12.83, 9.54, 7.80]),
+    (1, 4, [24.58, 13.96, 10.26, 8.31]),
+    (1, 5, [26.87, 15.09, 10.98, 8.84]),
+    (1, 6, [29.18, 16.23, 11.72, 9.38]),
+    (1, 7, [31.50, 17.38, 12.48, 9.93]),
+    (1, 8, [33.84, 18.54, 13.24, 10.50]),
+    (1, 9, [36.19, 19.71, 14.01, 11.07]),
+    (1, 10, [38.54, 20.88, 14.78, 11.65]),
+    (2, 2, [7.03, 4.58, 3.95, 3.63]),
+    (2, 3, [13.43, 8.18, 6.40, 5.45]),
+    (2, 4, [16.87, 9.93, 7.54, 6.28]),
+];
+
+// worst-case bias threshold and level of the Montiel Olea-Pflueger critical value
+const EFFECTIVE_F_TAU: f64 = 0.10;
+const EFFECTIVE_F_LEVEL: f64 = 0.05;
+
+fn stock_yogo_lookup(
+    table: &[(usize, usize, [f64; 4])],
+    levels: &[f64; 4],
+    n_endog: usize,
+    n_instruments: usize,
+    level: f64,
+    kind: &str,
+) -> Result<f64, String> {
+    let column = levels
+        .iter()
+        .position(|l| (l - level).abs() < 1e-9)
+        .ok_or_else(|| format!("Stock-Yogo {} critical values are tabulated for levels {:?}", kind, levels))?;
+    table
+        .iter()
+        .find(|(endog, instruments, _)| *endog == n_endog && *instruments == n_instruments)
+        .map(|(_, _, values)| values[column])
+        .ok_or_else(|| {
+            format!(
+                "No Stock-Yogo {} critical value is tabulated for {} endogenous regressors and {} instruments",
+                kind, n_endog, n_instruments
+            )
+        })
+}
+
+/// Returns the Stock-Yogo critical value of the Cragg-Donald F for a maximal 2SLS bias, relative
+/// to the bias of OLS, of `max_bias`.
+///
+/// The embedded table covers one endogenous regressor with 3 to 10 instruments and two
+/// endogenous regressors with 4 instruments, for `max_bias` of `0.05`, `0.10`, `0.20` or `0.30`.
+///
+/// # Example
+///
+/// ```
+/// use marketing_iv_methods::utils::first_stage::stock_yogo_bias_critical_value;
+///
+/// assert_eq!(stock_yogo_bias_critical_value(1, 3, 0.10).unwrap(), 9.08);
+/// ```
+pub fn stock_yogo_bias_critical_value(n_endog: usize, n_instruments: usize, max_bias: f64) -> Result<f64, String> {
+    stock_yogo_lookup(&STOCK_YOGO_BIAS, &STOCK_YOGO_BIAS_LEVELS, n_endog, n_instruments, max_bias, "relative-bias")
+}
+
+/// Returns the Stock-Yogo critical value of the Cragg-Donald F for a maximal size of `max_size`
+/// of a nominal 5% 2SLS Wald test.
+///
+/// The embedded table covers one endogenous regressor with 1 to 10 instruments and two
+/// endogenous regressors with 2 to 4 instruments, for `max_size` of `0.10`, `0.15`, `0.20` or
+/// `0.25`.
+///
+/// # Example
+///
+/// ```
+/// use marketing_iv_methods::utils::first_stage::stock_yogo_size_critical_value;
+///
+/// assert_eq!(stock_yogo_size_critical_value(1, 1, 0.10).unwrap(), 16.38);
+/// ```
+pub fn stock_yogo_size_critical_value(n_endog: usize, n_instruments: usize, max_size: f64) -> Result<f64, String> {
+    stock_yogo_lookup(&STOCK_YOGO_SIZE, &STOCK_YOGO_SIZE_LEVELS, n_endog, n_instruments, max_size, "size")
+}
+
+/// A report on the strength of the first stage, as returned by `run_first_stage_diagnostics`.
+///
+/// This structure holds:
+/// - `f_statistics`: The F statistic of the excluded instruments in the first stage of each
+///   endogenous regressor, with `f_df` degrees of freedom.
+/// - `f_p_values`: The p-values of the F statistics.
+/// - `partial_r_squared`: The share of the variance of each endogenous regressor, net of the
+///   exogenous controls, that the excluded instruments explain.
+/// - `f_df`: The numerator and denominator degrees of freedom of the F statistics.
+/// - `cragg_donald_f`: The Cragg-Donald Wald F statistic, valid under homoskedasticity.
+/// - `kleibergen_paap_f`: The Kleibergen-Paap rk Wald F statistic, robust to heteroskedasticity.
+/// - `effective_f`: The Montiel Olea-Pflueger effective F statistic, `None` unless there is
+///   exactly one endogenous regressor.
+/// - `effective_f_critical_value`: The simplified critical value of the effective F for a 10%
+///   worst-case bias at the 5% level, `None` without an effective F.
+///
+/// The `Display` implementation prints the full report together with the Stock-Yogo critical
+/// values for the Cragg-Donald F, where they are tabulated.
+#[derive(Debug, Clone)]
+pub struct FirstStageDiagnostics {
+    f_statistics: Array1<f64>,
+    f_p_values: Array1<f64>,
+    partial_r_squared: Array1<f64>,
+    f_df: (usize, usize),
+    cragg_donald_f: f64,
+    kleibergen_paap_f: f64,
+    effective_f: Option<f64>,
+    effective_f_critical_value: Option<f64>,
+}
+
+impl FirstStageDiagnostics {
+    /// The first-stage F statistics of the excluded instruments, one per endogenous regressor.
+    pub fn f_statistics(&self) -> &Array1<f64> {
+        &self.f_statistics
+    }
+
+    /// The p-values of the first-stage F statistics.
+    pub fn f_p_values(&self) -> &Array1<f64> {
+        &self.f_p_values
+    }
+
+    /// The partial R-squared of the excluded instruments, one per endogenous regressor.
+    pub fn partial_r_squared(&self) -> &Array1<f64> {
+        &self.partial_r_squared
+    }
+
+    /// The numerator and denominator degrees of freedom of the first-stage F statistics.
+    pub fn f_df(&self) -> (usize, usize) {
+        self.f_df
+    }
+
+    /// The number of endogenous regressors.
+    pub fn n_endog(&self) -> usize {
+        self.f_statistics.len()
+    }
+
+    /// The number of excluded instruments.
+    pub fn n_instruments(&self) -> usize {
+        self.f_df.0
+    }
+
+    /// The Cragg-Donald Wald F statistic.
+    pub fn cragg_donald_f(&self) -> f64 {
+        self.cragg_donald_f
+    }
+
+    /// The Kleibergen-Paap rk Wald F statistic.
+    pub fn kleibergen_paap_f(&self) -> f64 {
+        self.kleibergen_paap_f
+    }
+
+    /// The Montiel Olea-Pflueger effective F statistic, `None` with several endogenous regressors.
+    pub fn effective_f(&self) -> Option<f64> {
+        self.effective_f
+    }
+
+    /// The simplified critical value of the effective F for a 10% worst-case bias at the 5% level.
+    pub fn effective_f_critical_value(&self) -> Option<f64> {
+        self.effective_f_critical_value
+    }
+}
+
+impl fmt::Display for FirstStageDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (n_endog, n_instruments) = (self.n_endog(), self.n_instruments());
+        writeln!(f, "First-stage diagnostics ({} endogenous, {} excluded instruments)", n_endog, n_instruments)?;
+        for j in 0..n_endog {
+            writeln!(
+                f,
+                "  x_endog[{}]: F({}, {}) = {:.4} (p = {:.4}), partial R-squared = {:.4}",
+                j, self.f_df.0, self.f_df.1, self.f_statistics[j], self.f_p_values[j], self.partial_r_squared[j]
+            )?;
+        }
+        writeln!(f, "Cragg-Donald Wald F: {:.4}", self.cragg_donald_f)?;
+        writeln!(f, "Kleibergen-Paap rk Wald F: {:.4}", self.kleibergen_paap_f)?;
+        if let (Some(effective_f), Some(critical_value)) = (self.effective_f, self.effective_f_critical_value) {
+            writeln!(
+                f,
+                "Montiel Olea-Pflueger effective F: {:.4} (critical value {:.4} for a 10% worst-case bias at the 5% level)",
+                effective_f, critical_value
+            )?;
+        }
+
+        writeln!(f, "Stock-Yogo critical values for the Cragg-Donald F:")?;
+        let bias: Vec<String> = STOCK_YOGO_BIAS_LEVELS
+            .iter()
+            .filter_map(|level| {
+                stock_yogo_bias_critical_value(n_endog, n_instruments, *level)
+                    .ok()
+                    .map(|value| format!("{:.0}%: {:.2}", level * 100.0, value))
+            })
+            .collect();
+        let size: Vec<String> = STOCK_YOGO_SIZE_LEVELS
+            .iter()
+            .filter_map(|level| {
+                stock_yogo_size_critical_value(n_endog, n_instruments, *level)
+                    .ok()
+                    .map(|value| format!("{:.0}%: {:.2}", level * 100.0, value))
+            })
+            .collect();
+        for (label, values) in [("maximal relative bias", bias), ("maximal size", size)] {
+            if values.is_empty() {
+                writeln!(f, "  {}: not tabulated", label)?;
+            } else {
+                writeln!(f, "  {}: {}", label, values.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The CDF of the noncentral chi-squared distribution, as a Poisson mixture of central ones.
+fn noncentral_chi_squared_cdf(x: f64, df: f64, noncentrality: f64) -> Result<f64, String> {
+    let half = noncentrality / 2.0;
+    let max_terms = (half + 40.0 * half.sqrt() + 40.0) as usize;
+    let mut cdf = 0.0;
+    for j in 0..=max_terms {
+        let log_weight = if half > 0.0 {
+            -half + j as f64 * half.ln() - ln_gamma(j as f64 + 1.0)
+        } else if j == 0 {
+            0.0
+        } else {
+            break;
+        };
+        let chi2 = ChiSquared::new(df + 2.0 * j as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+        cdf += log_weight.exp() * chi2.cdf(x);
+    }
+    Ok(cdf)
+}
+
+/// The `p` quantile of the noncentral chi-squared distribution, found by bisection.
+fn noncentral_chi_squared_quantile(p: f64, df: f64, noncentrality: f64) -> Result<f64, String> {
+    let mut upper = df + noncentrality + 1.0;
+    while noncentral_chi_squared_cdf(upper, df, noncentrality)? < p {
+        upper *= 2.0;
+    }
+    let mut lower = 0.0;
+    for _ in 0..100 {
+        let middle = (lower + upper) / 2.0;
+        if noncentral_chi_squared_cdf(middle, df, noncentrality)? < p {
+            lower = middle;
+        } else {
+            upper = middle;
+        }
+    }
+    Ok((lower + upper) / 2.0)
+}
+
+/// Returns the Kleibergen-Paap rk Wald statistic for the null that the `k2 x k1` first-stage
+/// coefficient matrix `pi` has rank `k1 - 1`, given the robust covariance of `vec(pi)`.
+fn kleibergen_paap_statistic(
+    pi: &Array2<f64>,
+    var_pi: &Array2<f64>,
+    zz: &Array2<f64>,
+    xx: &Array2<f64>,
+) -> Result<f64, String> {
+    let (k2, k1) = pi.dim();
+    let q = k1 - 1;
+
+    // normalised coefficients theta = F pi G^{-1}, with F'F = Z'Z and G'G = X'X
+    let f_half = symmetric_sqrt(zz)?;
+    let g_inv = inverse_spd(&symmetric_sqrt(xx)?).map_err(|e| format!("Regressor cross-product is singular: {}", e))?;
+    let theta = f_half.dot(pi).dot(&g_inv);
+    let var_theta = {
+        let transform = kron(&g_inv, &f_half);
+        transform.dot(var_pi).dot(&transform.t())
+    };
+
+    // singular value decomposition theta = U S V', with the full K2 x K2 matrix U
+    let (singular_squared, u) = eigh_descending(&theta.dot(&theta.t()))?;
+    if singular_squared[k1 - 1] <= 1e-12 * singular_squared[0].max(f64::MIN_POSITIVE) {
+        return Err("The first-stage coefficient matrix is rank deficient".into());
+    }
+    let mut v = Array2::zeros((k1, k1));
+    for j in 0..k1 {
+        v.column_mut(j).assign(&(theta.t().dot(&u.column(j)) / singular_squared[j].sqrt()));
+    }
+
+    // the part of theta beyond its best rank-q approximation, lambda = vec(A' theta B')
+    let u22 = u.slice(s![q.., q..]).to_owned();
+    let u22_inv = solve_general(&u22, &Array2::eye(k2 - q))?;
+    let a_perp = u.slice(s![.., q..]).dot(&u22_inv).dot(&symmetric_sqrt(&u22.dot(&u22.t()))?);
+    let v22 = v.slice(s![q.., q..]).to_owned();
+    let v22_inv_t = solve_general(&v22.t().to_owned(), &Array2::eye(k1 - q))?;
+    let b_perp = symmetric_sqrt(&v22.dot(&v22.t()))?.dot(&v22_inv_t).dot(&v.slice(s![.., q..]).t());
+
+    let remainder = a_perp.t().dot(&theta).dot(&b_perp.t());
+    let lambda = Array1::from_iter(remainder.t().iter().cloned());
+    let transform = kron(&b_perp, &a_perp.t().to_owned());
+    let omega = transform.dot(&var_theta).dot(&transform.t());
+    let weighted = solve_spd(&omega, &lambda.clone().insert_axis(Axis(1)))
+        .map_err(|e| format!("Covariance of the rank statistic is singular: {}", e))?;
+    Ok(lambda.dot(&weighted.column(0)))
+}
+
+/// Computes first-stage strength diagnostics for a linear IV model.
+///
+/// Each endogenous regressor is regressed on the excluded instruments `z` and the exogenous
+/// controls `x_exog`. The report contains:
+///
+/// - The F statistic of the excluded instruments and their partial R-squared in each first stage.
+/// - The Cragg-Donald Wald F, `min eig(Sigma_vv^{-1/2} X' P_Z X Sigma_vv^{-1/2}) / K2` with `X`
+///   and `Z` net of the controls, which equals the first-stage F with one endogenous regressor
+///   and is the statistic the Stock-Yogo critical values refer to.
+/// - The Kleibergen-Paap rk Wald F, the heteroskedasticity-robust rank test of Kleibergen and
+///   Paap (2006) for the null that the first stage is rank deficient, divided by `K2`. With one
+///   endogenous regressor it is the robust Wald F of the excluded instruments.
+/// - With one endogenous regressor, the effective F of Montiel Olea and Pflueger (2013),
+///   `pi' Z'Z pi / tr(W)` with `W` the robust covariance of the first stage, and its simplified
+///   critical value for a worst-case 2SLS bias of 10% of the OLS benchmark at the 5% level.
+///
+/// The robust covariances are scaled by `n / (n - L)`, where `L` counts the excluded
+/// instruments, the controls and the constant.
+///
+/// # Arguments
+///
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `intercept` - A boolean indicating whether the first stage includes an intercept term.
+///
+/// # Returns
+///
+/// * `Ok(FirstStageDiagnostics)` with the report.
+/// * `Err(String)` if the inputs are invalid, the model is under-identified, there are no more
+///   observations than instruments, or a cross-product matrix is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::first_stage::run_first_stage_diagnostics;
+///
+/// let z = Array2::from_shape_vec((8, 2), vec![
+///     1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0, 7.0, 0.0, 8.0, 1.0,
+/// ]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let report = run_first_stage_diagnostics(&x, &Array2::zeros((8, 0)), &z, true).unwrap();
+/// println!("{}", report);
+/// ```
+pub fn run_first_stage_diagnostics(
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    intercept: bool,
+) -> Result<FirstStageDiagnostics, String> {
+    let n = x_endog.nrows();
+    if x_exog.nrows() != n || z.nrows() != n {
+        return Err("x_endog, x_exog and z must have the same number of rows".into());
+    }
+    let (k1, k2) = (x_endog.ncols(), z.ncols());
+    if k1 == 0 {
+        return Err("x_endog must have at least one column".into());
+    }
+    if k2 < k1 {
+        return Err(format!("Model is under-identified: {} instruments for {} endogenous regressors", k2, k1));
+    }
+    let exog = if intercept { add_intercept(x_exog) } else { x_exog.to_owned() };
+    let n_instruments_total = exog.ncols() + k2;
+    if n <= n_instruments_total {
+        return Err("First-stage diagnostics require more observations than instruments".into());
+    }
+    let df_resid = n - n_instruments_total;
+
+    // partial out the controls, then fit the first stage of every endogenous regressor on z
+    let x_tilde = residualize(&exog, x_endog)?;
+    let z_tilde = residualize(&exog, z)?;
+    let zz = z_tilde.t().dot(&z_tilde);
+    let pi = solve_spd(&zz, &z_tilde.t().dot(&x_tilde)).map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+    let v = &x_tilde - &z_tilde.dot(&pi);
+
+    let f_dist = FisherSnedecor::new(k2 as f64, df_resid as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+    let mut f_statistics = Array1::zeros(k1);
+    let mut f_p_values = Array1::zeros(k1);
+    let mut partial_r_squared = Array1::zeros(k1);
+    for j in 0..k1 {
+        let ssr_restricted = x_tilde.column(j).dot(&x_tilde.column(j));
+        let ssr = v.column(j).dot(&v.column(j));
+        partial_r_squared[j] = 1.0 - ssr / ssr_restricted;
+        f_statistics[j] = (ssr_restricted - ssr) / k2 as f64 / (ssr / df_resid as f64);
+        f_p_values[j] = 1.0 - f_dist.cdf(f_statistics[j]);
+    }
+
+    let explained = pi.t().dot(&zz).dot(&pi);
+    let sigma_vv = v.t().dot(&v) / df_resid as f64;
+    let cragg_donald_f = min_generalized_eigenvalue(&explained, &sigma_vv)? / k2 as f64;
+
+    // robust covariance of vec(pi) from the scores v_i ⊗ z_i
+    let scale = n as f64 / df_resid as f64;
+    let mut scores = Array2::zeros((n, k1 * k2));
+    for j in 0..k1 {
+        let block = &z_tilde * &v.column(j).insert_axis(Axis(1));
+        scores.slice_mut(s![.., j * k2..(j + 1) * k2]).assign(&block);
+    }
+    let zz_inv = inverse_spd(&zz).map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+    let bread = kron(&Array2::eye(k1), &zz_inv);
+    let var_pi = bread.dot(&(scale * scores.t().dot(&scores))).dot(&bread);
+    let kleibergen_paap_f = kleibergen_paap_statistic(&pi, &var_pi, &zz, &x_tilde.t().dot(&x_tilde))? / k2 as f64;
+
+    let (effective_f, effective_f_critical_value) = if k1 == 1 {
+        let moment_covariance = scale * scores.t().dot(&scores);
+        let w_eigenvalues = generalized_eigenvalues(&moment_covariance, &zz)?;
+        let trace = w_eigenvalues.sum();
+        let trace_squared = w_eigenvalues.mapv(|w| w * w).sum();
+        let max_eigenvalue = w_eigenvalues.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        let x = 1.0 / EFFECTIVE_F_TAU;
+        let k_eff = trace.powi(2) * (1.0 + 2.0 * x) / (trace_squared + 2.0 * x * trace * max_eigenvalue);
+        let critical_value = noncentral_chi_squared_quantile(1.0 - EFFECTIVE_F_LEVEL, k_eff, k_eff * x)? / k_eff;
+        (Some(explained[[0, 0]] / trace), Some(critical_value))
+    } else {
+        (None, None)
+    };
+
+    Ok(FirstStageDiagnostics {
+        f_statistics,
+        f_p_values,
+        partial_r_squared,
+        f_df: (k2, df_resid),
+        cragg_donald_f,
+        kleibergen_paap_f,
+        effective_f,
+        effective_f_critical_value,
+    })
+}
@@ -0,0 +1,335 @@
+use super::covariance::{leverage_adjusted, score_meat, CovarianceType};
+use super::linear_regression::{iv_design, split_intercept};
+use super::matrix_ops::{inverse_spd, solve_spd};
+use super::optimize::nelder_mead;
+use ndarray::{s, Array1, Array2, Axis};
+use statrs::distribution::{ChiSquared, ContinuousCDF};
+
+/// The weighting scheme used by `run_gmm`.
+///
+/// - `OneStep`: `W = (Z'Z / n)^{-1}`, which reproduces 2SLS.
+/// - `TwoStep`: The efficient weighting matrix `W = S^{-1}`, with `S` the covariance of the
+///   moments evaluated at the one-step residuals, estimated as chosen by the `CovarianceType`
+///   passed to `run_gmm`.
+/// - `Iterated`: Re-estimates `S` from the latest residuals until the coefficients change by less
+///   than `tol` in every element, or `max_iter` updates have been made. `max_iter` must be at
+///   least one.
+/// - `ContinuouslyUpdated`: Minimises `n * g(b)' S(b)^{-1} g(b)` jointly over `b`, letting the
+///   weighting matrix move with the coefficients (Hansen, Heaton and Yaron, 1996).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GmmWeighting {
+    OneStep,
+    TwoStep,
+    Iterated { max_iter: usize, tol: f64 },
+    ContinuouslyUpdated,
+}
+
+/// The result of a linear GMM fit, as returned by `run_gmm`.
+///
+/// This structure holds:
+/// - `params`: The slope coefficients, ordered as the endogenous regressors followed by the
+///   exogenous controls.
+/// - `intercept`: The fitted intercept, or `0.0` when the model was fit without one.
+/// - `std_errors`: The standard errors of `params`, from the sandwich covariance.
+/// - `intercept_std_error`: The standard error of the intercept, or `None` without one.
+/// - `covariance`: The sandwich covariance matrix of the coefficients, the slopes followed by the
+///   intercept when there is one.
+/// - `j_statistic`: Hansen's J statistic for the overidentifying restrictions.
+/// - `j_df`: The degrees of freedom of J, i.e. the number of instruments minus the number of
+///   regressors.
+/// - `j_p_value`: The chi-squared p-value of J, or `NaN` when the model is exactly identified.
+/// - `weighting_matrix`: The weighting matrix used to compute the final estimate.
+/// - `covariance_type`: The `CovarianceType` used to estimate the covariance of the moments.
+/// - `iterations`: The number of times the weighting matrix was re-estimated from residuals
+///   (`0` for one-step and continuously-updated GMM, `1` for two-step GMM).
+#[derive(Debug, Clone)]
+pub struct FittedGmm {
+    params: Array1<f64>,
+    intercept: f64,
+    std_errors: Array1<f64>,
+    intercept_std_error: Option<f64>,
+    covariance: Array2<f64>,
+    j_statistic: f64,
+    j_df: usize,
+    j_p_value: f64,
+    weighting_matrix: Array2<f64>,
+    covariance_type: CovarianceType,
+    iterations: usize,
+}
+
+impl FittedGmm {
+    /// The slope coefficients: endogenous regressors first, then exogenous controls.
+    pub fn params(&self) -> &Array1<f64> {
+        &self.params
+    }
+
+    /// The fitted intercept, `0.0` if the model has none.
+    pub fn intercept(&self) -> f64 {
+        self.intercept
+    }
+
+    /// The standard errors of the slope coefficients.
+    pub fn std_errors(&self) -> &Array1<f64> {
+        &self.std_errors
+    }
+
+    /// The standard error of the intercept, `None` if the model has none.
+    pub fn intercept_std_error(&self) -> Option<f64> {
+        self.intercept_std_error
+    }
+
+    /// The covariance matrix of the coefficients: slopes first, then the intercept if any.
+    pub fn covariance(&self) -> &Array2<f64> {
+        &self.covariance
+    }
+
+    /// Hansen's J statistic for the overidentifying restrictions.
+    pub fn j_statistic(&self) -> f64 {
+        self.j_statistic
+    }
+
+    /// The degrees of freedom of the J statistic.
+    pub fn j_df(&self) -> usize {
+        self.j_df
+    }
+
+    /// The chi-squared p-value of the J statistic, `NaN` if the model is exactly identified.
+    pub fn j_p_value(&self) -> f64 {
+        self.j_p_value
+    }
+
+    /// The weighting matrix used to compute the final estimate.
+    pub fn weighting_matrix(&self) -> &Array2<f64> {
+        &self.weighting_matrix
+    }
+
+    /// The `CovarianceType` used to estimate the covariance of the moments.
+    pub fn covariance_type(&self) -> &CovarianceType {
+        &self.covariance_type
+    }
+
+    /// The number of times the weighting matrix was re-estimated from residuals.
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+}
+
+/// Computes `b(A) = (W'Z A Z'W)^{-1} W'Z A Z'y` for a given weighting matrix `A`.
+pub(crate) fn gmm_coefficients(
+    y: &Array2<f64>,
+    w: &Array2<f64>,
+    z: &Array2<f64>,
+    weight: &Array2<f64>,
+) -> Result<Array2<f64>, String> {
+    let zw = z.t().dot(w);
+    let zy = z.t().dot(y);
+    let lhs = zw.t().dot(weight).dot(&zw);
+    let rhs = zw.t().dot(weight).dot(&zy);
+    solve_spd(&lhs, &rhs).map_err(|e| format!("GMM cross-product is singular: {}", e))
+}
+
+/// Computes the heteroskedasticity-robust covariance of the moments, `S = Z' diag(e^2) Z / n`.
+pub(crate) fn moment_covariance(z: &Array2<f64>, resid: &Array2<f64>) -> Array2<f64> {
+    let n = z.nrows() as f64;
+    let scaled = z * resid;
+    scaled.t().dot(&scaled) / n
+}
+
+/// Computes the covariance of the moments `z_i e_i` for a model with `p` coefficients, as chosen
+/// by `covariance`.
+///
+/// `Classical` gives `s^2 Z'Z / n` with `s^2 = e'e / n`, under which the efficient estimate is
+/// 2SLS. `HC2` and `HC3` weight the squared residuals by the leverages
+/// `h_i = z_i' (Z'Z)^{-1} z_i`, and the other types are the meat of the matching coefficient
+/// covariance divided by `n`. `HC0` reproduces `moment_covariance`.
+pub(crate) fn weighted_moment_covariance(
+    z: &Array2<f64>,
+    resid: &Array2<f64>,
+    covariance: &CovarianceType,
+    p: usize,
+) -> Result<Array2<f64>, String> {
+    let n = z.nrows() as f64;
+    let resid = resid.column(0).to_owned();
+    let meat = match covariance {
+        CovarianceType::Classical => return Ok(resid.dot(&resid) / n * z.t().dot(z) / n),
+        CovarianceType::HC2 | CovarianceType::HC3 => {
+            let zz_inv = inverse_spd(&z.t().dot(z)).map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+            let leverage = (&z.dot(&zz_inv) * z).sum_axis(Axis(1));
+            let resid = leverage_adjusted(&resid, &leverage, covariance)?;
+            score_meat(&(z * &resid.insert_axis(Axis(1))), &CovarianceType::HC0, p)?
+        }
+        _ => score_meat(&(z * &resid.insert_axis(Axis(1))), covariance, p)?,
+    };
+    Ok(meat / n)
+}
+
+/// Computes `n * g' A g`, where `g = Z'e / n` is the sample mean of the moments.
+pub(crate) fn j_value(z: &Array2<f64>, resid: &Array2<f64>, weight: &Array2<f64>) -> f64 {
+    let n = z.nrows() as f64;
+    let g = z.t().dot(resid) / n;
+    n * g.t().dot(weight).dot(&g)[[0, 0]]
+}
+
+/// Runs a linear instrumental-variables regression by the generalized method of moments (GMM).
+///
+/// The moment conditions are `E[z_i (y_i - w_i' b)] = 0`, where `w_i` stacks the endogenous
+/// regressors and the exogenous controls, and `z_i` stacks the excluded instruments and the
+/// exogenous controls (each with a constant when `intercept` is `true`). The estimate minimises
+/// `n * g(b)' A g(b)` for a weighting matrix `A` chosen according to `weighting`. The one-step
+/// estimate is identical to 2SLS. The other schemes are efficient for the error structure that
+/// `covariance` allows: heteroskedasticity of unknown form for the `HC` types, dependence within
+/// clusters for `Cluster` and `TwoWayCluster`, and serial correlation for `Hac`. With
+/// `Classical` they reduce to 2SLS.
+///
+/// The J statistic is evaluated at the final estimate with the efficient weighting matrix: the
+/// matrix used in the final step for the two-step, iterated and continuously-updated schemes, and
+/// `S^{-1}` at the one-step residuals for `OneStep`. Under valid instruments it is
+/// asymptotically chi-squared with as many degrees of freedom as there are overidentifying
+/// restrictions.
+///
+/// The standard errors come from the sandwich `(G'AG)^{-1} G'A S A G (G'AG)^{-1} / n`, where
+/// `G = Z'W / n`, `A` is the final weighting matrix and `S` is the moment covariance at the final
+/// residuals, estimated as chosen by `covariance`. For `OneStep` with `HC0` or `HC1` these are
+/// the robust 2SLS standard errors.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `weighting` - The `GmmWeighting` scheme used to build the weighting matrix.
+/// * `covariance` - The `CovarianceType` used to estimate the covariance of the moments `S`.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(FittedGmm)` if the model fits successfully.
+/// * `Err(String)` if the inputs are invalid, the model is under-identified, `Iterated` allows no
+///   updates, or a weighting matrix cannot be inverted.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::gmm::{run_gmm, GmmWeighting};
+///
+/// let z = Array2::from_shape_vec((6, 2), vec![1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0]).unwrap();
+/// let u = Array2::from_shape_vec((6, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let e = Array2::from_shape_vec((6, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2]).unwrap();
+/// let y = -0.5 * &x + &u + &e;
+/// let fit = run_gmm(&y, &x, &Array2::zeros((6, 0)), &z, GmmWeighting::TwoStep, &CovarianceType::HC0, true).unwrap();
+/// println!("beta = {} ({}), J = {}", fit.params()[0], fit.std_errors()[0], fit.j_statistic());
+/// assert_eq!(fit.j_df(), 1);
+/// ```
+pub fn run_gmm(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    weighting: GmmWeighting,
+    covariance: &CovarianceType,
+    intercept: bool,
+) -> Result<FittedGmm, String> {
+    if let GmmWeighting::Iterated { max_iter: 0, .. } = weighting {
+        return Err("Iterated GMM needs max_iter of at least one".into());
+    }
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    let w = &design.w;
+    let z_full = &design.z_full;
+    let n = z_full.nrows() as f64;
+    let j_df = z_full.ncols() - w.ncols();
+
+    let efficient_weight = |resid: &Array2<f64>| {
+        inverse_spd(&weighted_moment_covariance(z_full, resid, covariance, w.ncols())?)
+            .map_err(|e| format!("Moment covariance is singular: {}", e))
+    };
+
+    // one-step estimate with A = (Z'Z / n)^{-1}, i.e. 2SLS
+    let one_step_weight = inverse_spd(&(z_full.t().dot(z_full) / n))
+        .map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+    let one_step = gmm_coefficients(y, w, z_full, &one_step_weight)?;
+    let one_step_resid = y - &w.dot(&one_step);
+
+    let (coefs, weight, j_weight, iterations) = match weighting {
+        GmmWeighting::OneStep => {
+            let j_weight = efficient_weight(&one_step_resid)?;
+            (one_step, one_step_weight, j_weight, 0)
+        }
+        GmmWeighting::TwoStep => {
+            let weight = efficient_weight(&one_step_resid)?;
+            let coefs = gmm_coefficients(y, w, z_full, &weight)?;
+            (coefs, weight.clone(), weight, 1)
+        }
+        GmmWeighting::Iterated { max_iter, tol } => {
+            let mut coefs = one_step;
+            let mut weight = one_step_weight;
+            let mut iterations = 0;
+            while iterations < max_iter {
+                weight = efficient_weight(&(y - &w.dot(&coefs)))?;
+                let updated = gmm_coefficients(y, w, z_full, &weight)?;
+                let change = (&updated - &coefs).iter().fold(0.0_f64, |acc, d| acc.max(d.abs()));
+                coefs = updated;
+                iterations += 1;
+                if change < tol {
+                    break;
+                }
+            }
+            (coefs, weight.clone(), weight, iterations)
+        }
+        GmmWeighting::ContinuouslyUpdated => {
+            // start from the two-step estimate and minimise the CUE objective directly
+            let start_weight = efficient_weight(&one_step_resid)?;
+            let start = gmm_coefficients(y, w, z_full, &start_weight)?.index_axis(Axis(1), 0).to_owned();
+            let objective = |b: &Array1<f64>| {
+                let resid = y - &w.dot(&b.view().insert_axis(Axis(1)));
+                match efficient_weight(&resid) {
+                    Ok(weight) => j_value(z_full, &resid, &weight),
+                    Err(_) => f64::INFINITY,
+                }
+            };
+            let step = start.mapv(|b| 0.1 * b.abs().max(0.1));
+            let (best, _) = nelder_mead(objective, &start, &step, 1e-12, 2000 * start.len());
+            let coefs = best.insert_axis(Axis(1));
+            let weight = efficient_weight(&(y - &w.dot(&coefs)))?;
+            (coefs, weight.clone(), weight, 0)
+        }
+    };
+
+    let resid = y - &w.dot(&coefs);
+    let j_statistic = j_value(z_full, &resid, &j_weight);
+    let j_p_value = if j_df > 0 {
+        let chi2 = ChiSquared::new(j_df as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+        1.0 - chi2.cdf(j_statistic)
+    } else {
+        f64::NAN
+    };
+
+    // sandwich covariance (G'AG)^{-1} G'A S A G (G'AG)^{-1} / n, with G = Z'W / n, A the final
+    // weighting matrix and S the moment covariance at the final residuals
+    let g = z_full.t().dot(w) / n;
+    let ga = g.t().dot(&weight);
+    let bread = inverse_spd(&ga.dot(&g)).map_err(|e| format!("GMM cross-product is singular: {}", e))?;
+    let moment_cov = weighted_moment_covariance(z_full, &resid, covariance, w.ncols())?;
+    let coef_covariance = bread.dot(&ga.dot(&moment_cov).dot(&ga.t())).dot(&bread) / n;
+    let std_all = coef_covariance.diag().mapv(f64::sqrt);
+    let n_slopes = w.ncols() - intercept as usize;
+
+    let (params, intercept_value) = split_intercept(coefs.index_axis(Axis(1), 0).to_owned(), intercept);
+
+    Ok(FittedGmm {
+        params,
+        intercept: intercept_value,
+        std_errors: std_all.slice(s![..n_slopes]).to_owned(),
+        intercept_std_error: if intercept { Some(std_all[n_slopes]) } else { None },
+        covariance: coef_covariance,
+        j_statistic,
+        j_df,
+        j_p_value,
+        weighting_matrix: weight,
+        covariance_type: covariance.clone(),
+        iterations,
+    })
+}
@@ -0,0 +1,222 @@
+use super::covariance::{coefficient_covariance, CovarianceType};
+use super::linear_regression::iv_design;
+use super::matrix_ops::{hstack, inverse_spd, residualize, solve_general, solve_spd};
+use ndarray::{s, Array1, Array2, Axis};
+use statrs::distribution::{ChiSquared, ContinuousCDF};
+
+/// The form of the Durbin-Wu-Hausman test computed by `run_hausman`.
+///
+/// - `Regression`: Adds the first-stage residuals of the endogenous regressors to the OLS
+///   regression of `y` and tests that their coefficients are zero. The residuals absorb the part
+///   of the structural error that moves with `x_endog`, so a nonzero coefficient means `x_endog`
+///   is endogenous.
+/// - `Contrast`: Compares the 2SLS and OLS coefficients on the endogenous regressors. Under
+///   exogeneity both are consistent and the difference vanishes; under endogeneity only 2SLS is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HausmanMethod {
+    Regression,
+    Contrast,
+}
+
+/// The result of a Durbin-Wu-Hausman endogeneity test, as returned by `run_hausman`.
+///
+/// This structure holds:
+/// - `method`: The `HausmanMethod` used.
+/// - `statistic`: The Wald statistic for the null that `x_endog` is exogenous.
+/// - `df`: The degrees of freedom of the chi-squared reference distribution, the number of
+///   endogenous regressors.
+/// - `p_value`: The chi-squared p-value of `statistic`.
+/// - `difference`: The difference between the 2SLS and OLS coefficients on the endogenous
+///   regressors.
+/// - `covariance_type`: The `CovarianceType` of the variance in the Wald statistic.
+#[derive(Debug, Clone)]
+pub struct HausmanTest {
+    method: HausmanMethod,
+    statistic: f64,
+    df: usize,
+    p_value: f64,
+    difference: Array1<f64>,
+    covariance_type: CovarianceType,
+}
+
+impl HausmanTest {
+    /// The form of the test.
+    pub fn method(&self) -> HausmanMethod {
+        self.method
+    }
+
+    /// The Wald statistic for the null of exogeneity.
+    pub fn statistic(&self) -> f64 {
+        self.statistic
+    }
+
+    /// The degrees of freedom of the statistic.
+    pub fn df(&self) -> usize {
+        self.df
+    }
+
+    /// The chi-squared p-value of the statistic.
+    pub fn p_value(&self) -> f64 {
+        self.p_value
+    }
+
+    /// The 2SLS minus OLS coefficients on the endogenous regressors.
+    pub fn difference(&self) -> &Array1<f64> {
+        &self.difference
+    }
+
+    /// The `CovarianceType` used for the variance in the statistic.
+    pub fn covariance_type(&self) -> &CovarianceType {
+        &self.covariance_type
+    }
+}
+
+/// Runs a Durbin-Wu-Hausman test of whether the regressors `x_endog` are endogenous.
+///
+/// Both forms compare a model that treats `x_endog` as exogenous with one that instruments it
+/// with `z`, and both have a chi-squared distribution with one degree of freedom per endogenous
+/// regressor under the null that `x_endog` is exogenous.
+///
+/// - `HausmanMethod::Regression` fits `y = x_endog * beta + x_exog * gamma + v_hat * rho + e` by
+///   OLS, where `v_hat` are the residuals of `x_endog` on `[z, x_exog]`, and returns the Wald
+///   statistic for `rho = 0` with the requested `covariance`. No correction for the estimated
+///   `v_hat` is needed, since it vanishes under the null.
+/// - `HausmanMethod::Contrast` returns `d' V^{-1} d`, where `d` is the 2SLS minus OLS estimate of
+///   the endogenous coefficients. With `CovarianceType::Classical`, `V` is
+///   `s^2 [(W' P_Z W)^{-1} - (W'W)^{-1}]` with `s^2` from OLS (Durbin's form), which is
+///   positive semi-definite by construction. Any other `covariance` computes `V` from the
+///   influence of each observation on `d`, using the OLS residuals, so the test stays valid
+///   under heteroskedasticity, clustering or autocorrelation, where `V_IV - V_OLS` is not the
+///   variance of the difference.
+///
+/// With the classical covariance and one endogenous regressor the two statistics are closely
+/// related; with a robust covariance they differ in finite samples but agree asymptotically.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of regressors suspected to be endogenous.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `method` - The `HausmanMethod` to compute.
+/// * `covariance` - The `CovarianceType` used for the variance in the Wald statistic.
+/// * `intercept` - A boolean indicating whether the models include an intercept term.
+///
+/// # Returns
+///
+/// * `Ok(HausmanTest)` with the statistic and its p-value.
+/// * `Err(String)` if the inputs are invalid, the model is under-identified, or a cross-product
+///   matrix or the variance of the contrast is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::hausman::{run_hausman, HausmanMethod};
+///
+/// let z = Array2::from_shape_vec((8, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + 2.0 * &u + &e;
+/// let none = Array2::zeros((8, 0));
+/// let test = run_hausman(&y, &x, &none, &z, HausmanMethod::Contrast, &CovarianceType::HC1, true).unwrap();
+/// println!("statistic = {}, p-value = {}", test.statistic(), test.p_value());
+/// ```
+pub fn run_hausman(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    method: HausmanMethod,
+    covariance: &CovarianceType,
+    intercept: bool,
+) -> Result<HausmanTest, String> {
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    let n_endog = x_endog.ncols();
+    if n_endog == 0 {
+        return Err("x_endog must have at least one column".into());
+    }
+    let w = &design.w;
+    let n = w.nrows();
+    if n <= w.ncols() + n_endog {
+        return Err("The Hausman test requires more observations than coefficients".into());
+    }
+
+    // 2SLS and OLS both have the form b = bread A'y, with A = P_Z W for 2SLS and A = W for OLS
+    let w_resid = residualize(&design.z_full, w).map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+    let projected = w - &w_resid;
+    let iv_bread = solve_general(&projected.t().dot(w), &Array2::eye(w.ncols()))
+        .map_err(|e| format!("Second-stage cross-product is singular: {}", e))?;
+    let ols_bread = inverse_spd(&w.t().dot(w)).map_err(|e| format!("Regressor cross-product is singular: {}", e))?;
+    let b_iv = iv_bread.dot(&projected.t().dot(y)).index_axis(Axis(1), 0).to_owned();
+    let b_ols = ols_bread.dot(&w.t().dot(y)).index_axis(Axis(1), 0).to_owned();
+    let difference = (&b_iv - &b_ols).slice(s![..n_endog]).to_owned();
+
+    let (estimate, variance) = match method {
+        HausmanMethod::Regression => {
+            // the first-stage residuals of x_endog are the first columns of M_Z W
+            let augmented = hstack(&[w, &w_resid.slice(s![.., ..n_endog]).to_owned()])?;
+            let bread = inverse_spd(&augmented.t().dot(&augmented))
+                .map_err(|e| format!("Augmented regressor cross-product is singular: {}", e))?;
+            let coefs = bread.dot(&augmented.t().dot(y)).index_axis(Axis(1), 0).to_owned();
+            let resid = y.column(0).to_owned() - augmented.dot(&coefs);
+            let variance = coefficient_covariance(&augmented, &bread, &resid, covariance)?;
+            let k = w.ncols();
+            (coefs.slice(s![k..]).to_owned(), variance.slice(s![k.., k..]).to_owned())
+        }
+        HausmanMethod::Contrast => {
+            // d = C'y with C = A_iv bread_iv' - W bread_ols', and C'W = 0, so d = C'u under the null
+            let contrast = projected.dot(&iv_bread.t()) - w.dot(&ols_bread.t());
+            let resid = y.column(0).to_owned() - w.dot(&b_ols);
+            let variance = contrast_covariance(&contrast, w, &ols_bread, &resid, covariance)?;
+            (difference.clone(), variance.slice(s![..n_endog, ..n_endog]).to_owned())
+        }
+    };
+
+    let weighted = solve_spd(&variance, &estimate.clone().insert_axis(Axis(1)))
+        .map_err(|e| format!("The variance of the Hausman contrast is singular: {}", e))?;
+    let statistic = estimate.dot(&weighted.column(0));
+    let chi2 = ChiSquared::new(n_endog as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+
+    Ok(HausmanTest {
+        method,
+        statistic,
+        df: n_endog,
+        p_value: 1.0 - chi2.cdf(statistic),
+        difference,
+        covariance_type: covariance.clone(),
+    })
+}
+
+/// The covariance of the contrast `d = C'y`, given the OLS residuals.
+///
+/// The leverage adjustments of `HC2` and `HC3` use the OLS hat matrix, and are applied to the
+/// residuals before the `HC0` sandwich, since `C` is not itself a projection.
+fn contrast_covariance(
+    contrast: &Array2<f64>,
+    w: &Array2<f64>,
+    ols_bread: &Array2<f64>,
+    resid: &Array1<f64>,
+    covariance: &CovarianceType,
+) -> Result<Array2<f64>, String> {
+    let (n, p) = w.dim();
+    let identity = Array2::eye(p);
+    match covariance {
+        CovarianceType::Classical => {
+            let s2 = resid.dot(resid) / (n - p) as f64;
+            Ok(s2 * contrast.t().dot(contrast))
+        }
+        CovarianceType::HC2 | CovarianceType::HC3 => {
+            let leverage = (&w.dot(ols_bread) * w).sum_axis(Axis(1));
+            if leverage.iter().any(|h| 1.0 - h < 1e-10) {
+                return Err("An observation has leverage one, so HC2 and HC3 are undefined".into());
+            }
+            let power = if *covariance == CovarianceType::HC2 { 0.5 } else { 1.0 };
+            let adjusted = resid / &leverage.mapv(|h| (1.0 - h).powf(power));
+            coefficient_covariance(contrast, &identity, &adjusted, &CovarianceType::HC0)
+        }
+        _ => coefficient_covariance(contrast, &identity, resid, covariance),
+    }
+}
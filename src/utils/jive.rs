@@ -0,0 +1,189 @@
+use super::linear_regression::{iv_design, split_intercept};
+use super::matrix_ops::{hstack, inverse_spd, residualize, solve_general, solve_spd};
+use ndarray::{concatenate, Array1, Array2, Axis};
+
+/// The jackknife instrumental-variables estimator used by `run_jive`.
+///
+/// - `Jive1`: The first-stage fit for observation `i` comes from a regression that leaves `i`
+///   out, `(z_i' pi_hat - h_i x_i) / (1 - h_i)`, where `h_i` is the leverage of observation `i`
+///   (Angrist, Imbens and Krueger, 1999).
+/// - `Jive2`: Replaces the observation-specific `1 - h_i` in the denominator with `1 - 1/n`,
+///   which keeps the estimator well behaved when some leverages are close to one.
+/// - `Ujive`: The unbiased JIVE of Kolesár (2013), which also leaves observation `i` out of the
+///   fit on the exogenous controls, so that the controls do not reintroduce the many-instrument
+///   bias that JIVE1 removes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JiveVariant {
+    Jive1,
+    Jive2,
+    Ujive,
+}
+
+/// The result of a jackknife IV fit, as returned by `run_jive`.
+///
+/// This structure holds:
+/// - `params`: The slope coefficients, ordered as the endogenous regressors followed by the
+///   exogenous controls.
+/// - `intercept`: The fitted intercept, or `0.0` when the model was fit without one.
+/// - `has_intercept`: Whether the model was fit with an intercept.
+/// - `variant`: The `JiveVariant` used for the fit.
+#[derive(Debug, Clone)]
+pub struct FittedJive {
+    params: Array1<f64>,
+    intercept: f64,
+    has_intercept: bool,
+    variant: JiveVariant,
+}
+
+impl FittedJive {
+    /// The slope coefficients: endogenous regressors first, then exogenous controls.
+    pub fn params(&self) -> &Array1<f64> {
+        &self.params
+    }
+
+    /// The fitted intercept, `0.0` if the model has none.
+    pub fn intercept(&self) -> f64 {
+        self.intercept
+    }
+
+    /// Whether the model was fit with an intercept.
+    pub fn has_intercept(&self) -> bool {
+        self.has_intercept
+    }
+
+    /// The jackknife variant used for the fit.
+    pub fn variant(&self) -> JiveVariant {
+        self.variant
+    }
+}
+
+/// Returns the fitted values and leverages from regressing each column of `b` on `a`.
+///
+/// The leverages `h_i = a_i' (A'A)^{-1} a_i` are computed row by row, so the `n x n` hat matrix
+/// is never formed.
+fn fitted_and_leverage(a: &Array2<f64>, b: &Array2<f64>) -> Result<(Array2<f64>, Array1<f64>), String> {
+    let n = a.nrows();
+    if a.ncols() == 0 {
+        return Ok((Array2::zeros(b.raw_dim()), Array1::zeros(n)));
+    }
+    let gram_inv = inverse_spd(&a.t().dot(a)).map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+    let fitted = b - &residualize(a, b)?;
+    let leverage = (&a.dot(&gram_inv) * a).sum_axis(Axis(1));
+    Ok((fitted, leverage))
+}
+
+/// Returns the leave-one-out fitted values `(fitted_i - h_i b_i) / (1 - h_i)` of `b` on `a`.
+fn leave_one_out_fitted(a: &Array2<f64>, b: &Array2<f64>) -> Result<Array2<f64>, String> {
+    let (fitted, leverage) = fitted_and_leverage(a, b)?;
+    if leverage.iter().any(|h| 1.0 - h < 1e-10) {
+        return Err("An observation has leverage one, so its leave-one-out fit is undefined".into());
+    }
+    let denominator = leverage.mapv(|h| 1.0 - h).insert_axis(Axis(1));
+    Ok((fitted - b * &leverage.insert_axis(Axis(1))) / denominator)
+}
+
+/// Runs a jackknife instrumental-variables (JIVE) regression.
+///
+/// With many instruments, the 2SLS first-stage fit of observation `i` uses `x_i` itself, so the
+/// fitted value is correlated with the structural error of the same observation and 2SLS is
+/// biased towards OLS. The jackknife estimators build the first-stage fit of each observation
+/// from a regression that leaves it out, which removes this own-observation correlation. The
+/// leave-one-out fits are computed in closed form from the full-sample fit and the leverages,
+/// so no regression is refit.
+///
+/// For `Jive1` and `Jive2` the jackknifed first-stage fits `x_hat` of the endogenous regressors
+/// are used as instruments, together with the exogenous controls, in a just-identified IV
+/// regression of `y` on `[x_endog, x_exog]`. For `Ujive` the instrument for the endogenous
+/// regressors is the difference `g` between the leave-one-out fits of `x_endog` on `[z, x_exog]`
+/// and on `x_exog` alone,
+///
+/// `beta = (g' x_endog)^{-1} g' y`,
+///
+/// and the coefficients on the controls are then the OLS fit of `y - x_endog * beta` on
+/// `x_exog`. The constant counts as a control when `intercept` is `true`. Because `g` is only
+/// orthogonal to the controls in expectation, UJIVE does not reproduce an exact linear fit in
+/// finite samples the way JIVE1 and JIVE2 do.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `variant` - The `JiveVariant` to compute.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(FittedJive)` if the model fits successfully.
+/// * `Err(String)` if the inputs are invalid, the model is under-identified, an observation has
+///   leverage one, or a cross-product matrix is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::jive::{run_jive, JiveVariant};
+///
+/// let z = Array2::from_shape_vec((8, 2), vec![
+///     1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0, 7.0, 0.0, 8.0, 1.0,
+/// ]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let fit = run_jive(&y, &x, &Array2::zeros((8, 0)), &z, JiveVariant::Ujive, true).unwrap();
+/// println!("beta = {}, intercept = {}", fit.params()[0], fit.intercept());
+/// ```
+pub fn run_jive(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    variant: JiveVariant,
+    intercept: bool,
+) -> Result<FittedJive, String> {
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    let n = y.nrows();
+
+    let coefs = match variant {
+        JiveVariant::Jive1 | JiveVariant::Jive2 => {
+            let x_hat = match variant {
+                JiveVariant::Jive1 => leave_one_out_fitted(&design.z_full, x_endog)?,
+                _ => {
+                    let (fitted, leverage) = fitted_and_leverage(&design.z_full, x_endog)?;
+                    (fitted - x_endog * &leverage.insert_axis(Axis(1))) / (1.0 - 1.0 / n as f64)
+                }
+            };
+            // just-identified IV with the jackknifed fits standing in for x_endog
+            let instruments = hstack(&[&x_hat, &design.exog])?;
+            solve_general(&instruments.t().dot(&design.w), &instruments.t().dot(y))
+                .map_err(|e| format!("JIVE cross-product is singular: {}", e))?
+        }
+        JiveVariant::Ujive => {
+            let g = leave_one_out_fitted(&design.z_full, x_endog)? - leave_one_out_fitted(&design.exog, x_endog)?;
+            let beta = solve_general(&g.t().dot(x_endog), &g.t().dot(y))
+                .map_err(|e| format!("UJIVE cross-product is singular: {}", e))?;
+            // the controls are then fit by OLS on what beta leaves unexplained
+            let structural_resid = y - &x_endog.dot(&beta);
+            let gamma = if design.exog.ncols() > 0 {
+                let exog = &design.exog;
+                solve_spd(&exog.t().dot(exog), &exog.t().dot(&structural_resid))
+                    .map_err(|e| format!("Control cross-product is singular: {}", e))?
+            } else {
+                Array2::zeros((0, 1))
+            };
+            concatenate(Axis(0), &[beta.view(), gamma.view()])
+                .map_err(|e| format!("Failed to stack coefficients: {}", e))?
+        }
+    };
+
+    let (params, intercept_value) = split_intercept(coefs.index_axis(Axis(1), 0).to_owned(), intercept);
+
+    Ok(FittedJive {
+        params,
+        intercept: intercept_value,
+        has_intercept: intercept,
+        variant,
+    })
+}
@@ -1,46 +1,691 @@
-use linfa::prelude::*;
-use linfa::dataset::Dataset;
-use linfa_linear::{LinearRegression, FittedLinearRegression};
-use ndarray::{Array2, Array1};
+use linfa_linalg::qr::QR;
+use linfa_linalg::triangular::{SolveTriangular, UPLO};
+use ndarray::{s, Array2, Array1, Axis};
+use statrs::distribution::{ContinuousCDF, StudentsT};
+use super::covariance::{coefficient_covariance, CovarianceType};
+use super::matrix_ops::{add_intercept, hstack, min_generalized_eigenvalue, residualize, solve_general};
 
-/// Runs a linear regression model using the provided feature matrix `x` and response vector `y`.
+/// The result of an instrumental-variables fit, as returned by `run_2sls` and the other
+/// k-class estimators.
+///
+/// Mirrors the `params` and `intercept` accessors of `FittedOls` so that IV and OLS fits can be
+/// inspected side by side:
+/// - `params`: The slope coefficients, ordered as the endogenous regressors followed by the
+///   exogenous controls.
+/// - `intercept`: The fitted intercept, or `0.0` when the model was fit without one.
+/// - `k`: The k-class parameter used for the fit (`0` for OLS, `1` for 2SLS, data-dependent
+///   for LIML and Fuller).
+/// - `std_errors`: The standard errors of `params`, computed as requested by `covariance_type`.
+/// - `intercept_std_error`: The standard error of the intercept, or `None` without one.
+/// - `covariance`: The covariance matrix of the coefficients, the slopes followed by the
+///   intercept when there is one.
+/// - `covariance_type`: The `CovarianceType` used for the standard errors.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::linear_regression::run_2sls;
+///
+/// let z = Array2::from_shape_fn((6, 1), |(i, _)| i as f64);
+/// let x = &z * 2.0;
+/// let y = &x * -0.5;
+/// let controls = Array2::zeros((6, 0));
+/// let fit = run_2sls(&y, &x, &controls, &z, &CovarianceType::Classical, false).unwrap();
+/// assert!((fit.params()[0] + 0.5).abs() < 1e-10);
+/// assert_eq!(fit.intercept(), 0.0);
+/// assert_eq!(fit.k(), 1.0);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FittedIvRegression {
+    params: Array1<f64>,
+    intercept: f64,
+    k: f64,
+    std_errors: Array1<f64>,
+    intercept_std_error: Option<f64>,
+    covariance: Array2<f64>,
+    covariance_type: CovarianceType,
+}
+
+impl FittedIvRegression {
+    /// The slope coefficients: endogenous regressors first, then exogenous controls.
+    pub fn params(&self) -> &Array1<f64> {
+        &self.params
+    }
+
+    /// The fitted intercept, `0.0` if the model has none.
+    pub fn intercept(&self) -> f64 {
+        self.intercept
+    }
+
+    /// The k-class parameter used for the fit.
+    pub fn k(&self) -> f64 {
+        self.k
+    }
+
+    /// The standard errors of the slope coefficients.
+    pub fn std_errors(&self) -> &Array1<f64> {
+        &self.std_errors
+    }
+
+    /// The standard error of the intercept, `None` if the model has none.
+    pub fn intercept_std_error(&self) -> Option<f64> {
+        self.intercept_std_error
+    }
+
+    /// The covariance matrix of the coefficients: slopes first, then the intercept if any.
+    pub fn covariance(&self) -> &Array2<f64> {
+        &self.covariance
+    }
+
+    /// The covariance estimator used for the standard errors.
+    pub fn covariance_type(&self) -> &CovarianceType {
+        &self.covariance_type
+    }
+}
+
+/// The regressor and instrument matrices shared by the IV estimators.
+pub(crate) struct IvDesign {
+    // regressors [x_endog, x_exog, (1)]
+    pub(crate) w: Array2<f64>,
+    // instruments [z, x_exog, (1)]
+    pub(crate) z_full: Array2<f64>,
+    // included exogenous regressors [x_exog, (1)]
+    pub(crate) exog: Array2<f64>,
+}
+
+/// Checks the inputs of an IV regression and builds the regressor and instrument matrices.
+pub(crate) fn iv_design(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    intercept: bool,
+) -> Result<IvDesign, String> {
+    let n = y.nrows();
+    if y.ncols() != 1 {
+        return Err("y must have exactly one column".into());
+    }
+    if x_endog.nrows() != n || x_exog.nrows() != n || z.nrows() != n {
+        return Err("y, x_endog, x_exog and z must have the same number of rows".into());
+    }
+    if z.ncols() < x_endog.ncols() {
+        return Err(format!(
+            "Model is under-identified: {} instruments for {} endogenous regressors",
+            z.ncols(),
+            x_endog.ncols()
+        ));
+    }
+
+    // build the regressor and instrument matrices, sharing the exogenous controls
+    let mut w = hstack(&[x_endog, x_exog])?;
+    let mut z_full = hstack(&[z, x_exog])?;
+    let mut exog = x_exog.to_owned();
+    if intercept {
+        w = add_intercept(&w);
+        z_full = add_intercept(&z_full);
+        exog = add_intercept(&exog);
+    }
+
+    Ok(IvDesign { w, z_full, exog })
+}
+
+/// The result of an ordinary least squares fit, as returned by `run_regression`.
+///
+/// This structure holds:
+/// - `params`: The slope coefficients, one per column of the design matrix.
+/// - `intercept`: The fitted intercept, or `0.0` when the model was fit without one.
+/// - `std_errors`: The standard errors of `params`, computed as requested by `covariance_type`.
+/// - `intercept_std_error`: The standard error of the intercept, or `None` without one.
+/// - `covariance`: The covariance matrix of the coefficients, the slopes followed by the
+///   intercept when there is one.
+/// - `covariance_type`: The `CovarianceType` used for the standard errors.
+/// - `residuals`: The residuals `y - x * params - intercept`.
+/// - `r_squared`: The coefficient of determination. Without an intercept it is the uncentered
+///   version, `1 - SSR / y'y`.
+/// - `adj_r_squared`: `r_squared` adjusted for the number of regressors.
+/// - `df_model`: The model degrees of freedom, i.e. the number of slope coefficients.
+/// - `df_resid`: The residual degrees of freedom, `n` minus the number of coefficients.
+///
+/// The t-statistics, p-values and confidence intervals are derived from the coefficients and
+/// standard errors and use the t distribution with `df_resid` degrees of freedom.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::linear_regression::run_regression;
+///
+/// let x = Array2::from_shape_vec((6, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+/// let y = Array2::from_shape_vec((6, 1), vec![2.1, 3.9, 6.2, 7.8, 10.1, 12.0]).unwrap();
+/// let fit = run_regression(&x, &y, &CovarianceType::Classical, true).unwrap();
+/// let ci = fit.conf_int(0.95).unwrap();
+/// println!("slope = {} ({}), 95% CI [{}, {}]", fit.params()[0], fit.std_errors()[0], ci[[0, 0]], ci[[0, 1]]);
+/// assert_eq!(fit.df_resid(), 4);
+/// assert!(fit.r_squared() > 0.99);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FittedOls {
+    params: Array1<f64>,
+    intercept: f64,
+    std_errors: Array1<f64>,
+    intercept_std_error: Option<f64>,
+    covariance: Array2<f64>,
+    covariance_type: CovarianceType,
+    residuals: Array1<f64>,
+    r_squared: f64,
+    adj_r_squared: f64,
+    df_model: usize,
+    df_resid: usize,
+}
+
+impl FittedOls {
+    /// The slope coefficients, one per column of the design matrix.
+    pub fn params(&self) -> &Array1<f64> {
+        &self.params
+    }
+
+    /// The fitted intercept, `0.0` if the model has none.
+    pub fn intercept(&self) -> f64 {
+        self.intercept
+    }
+
+    /// The standard errors of the slope coefficients.
+    pub fn std_errors(&self) -> &Array1<f64> {
+        &self.std_errors
+    }
+
+    /// The standard error of the intercept, `None` if the model has none.
+    pub fn intercept_std_error(&self) -> Option<f64> {
+        self.intercept_std_error
+    }
+
+    /// The covariance matrix of the coefficients: slopes first, then the intercept if any.
+    pub fn covariance(&self) -> &Array2<f64> {
+        &self.covariance
+    }
+
+    /// The covariance estimator used for the standard errors.
+    pub fn covariance_type(&self) -> &CovarianceType {
+        &self.covariance_type
+    }
+
+    /// The t-statistics of the slope coefficients against zero.
+    pub fn t_values(&self) -> Array1<f64> {
+        &self.params / &self.std_errors
+    }
+
+    /// The t-statistic of the intercept against zero, `None` if the model has none.
+    pub fn intercept_t_value(&self) -> Option<f64> {
+        self.intercept_std_error.map(|se| self.intercept / se)
+    }
+
+    /// The two-sided p-values of the slope coefficients against zero.
+    pub fn p_values(&self) -> Array1<f64> {
+        self.t_values().mapv(|t| two_sided_t_p_value(t, self.df_resid))
+    }
+
+    /// The two-sided p-value of the intercept against zero, `None` if the model has none.
+    pub fn intercept_p_value(&self) -> Option<f64> {
+        self.intercept_t_value().map(|t| two_sided_t_p_value(t, self.df_resid))
+    }
+
+    /// The confidence intervals of the slope coefficients at the given coverage, one row per
+    /// coefficient with the lower bound in the first column and the upper bound in the second.
+    pub fn conf_int(&self, confidence: f64) -> Result<Array2<f64>, String> {
+        let critical = t_critical_value(confidence, self.df_resid)?;
+        Ok(Array2::from_shape_fn((self.params.len(), 2), |(i, j)| {
+            let sign = if j == 0 { -1.0 } else { 1.0 };
+            self.params[i] + sign * critical * self.std_errors[i]
+        }))
+    }
+
+    /// The confidence interval of the intercept at the given coverage, `None` if the model has
+    /// none.
+    pub fn intercept_conf_int(&self, confidence: f64) -> Result<Option<(f64, f64)>, String> {
+        let critical = t_critical_value(confidence, self.df_resid)?;
+        Ok(self
+            .intercept_std_error
+            .map(|se| (self.intercept - critical * se, self.intercept + critical * se)))
+    }
+
+    /// The residuals `y - x * params - intercept`.
+    pub fn residuals(&self) -> &Array1<f64> {
+        &self.residuals
+    }
+
+    /// The coefficient of determination (uncentered without an intercept).
+    pub fn r_squared(&self) -> f64 {
+        self.r_squared
+    }
+
+    /// The coefficient of determination adjusted for the number of regressors.
+    pub fn adj_r_squared(&self) -> f64 {
+        self.adj_r_squared
+    }
+
+    /// The model degrees of freedom, i.e. the number of slope coefficients.
+    pub fn df_model(&self) -> usize {
+        self.df_model
+    }
+
+    /// The residual degrees of freedom.
+    pub fn df_resid(&self) -> usize {
+        self.df_resid
+    }
+}
+
+/// Returns the two-sided p-value of `t` under the t distribution with `df` degrees of freedom.
+pub(crate) fn two_sided_t_p_value(t: f64, df: usize) -> f64 {
+    match StudentsT::new(0.0, 1.0, df as f64) {
+        Ok(dist) => 2.0 * (1.0 - dist.cdf(t.abs())),
+        Err(_) => f64::NAN,
+    }
+}
+
+/// Returns the two-sided critical value of the t distribution with `df` degrees of freedom.
+pub(crate) fn t_critical_value(confidence: f64, df: usize) -> Result<f64, String> {
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err("confidence must be between 0 and 1".into());
+    }
+    let dist = StudentsT::new(0.0, 1.0, df as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+    Ok(dist.inverse_cdf(0.5 + confidence / 2.0))
+}
+
+/// Runs an ordinary least squares regression of `y` on the columns of `x`.
+///
+/// The coefficients are computed from a QR factorisation of the design matrix, `X = QR`, as the
+/// solution of `R b = Q'y`, which avoids forming `X'X` and is accurate even when the regressors
+/// are strongly correlated. `(X'X)^{-1} = R^{-1} R^{-T}` is obtained from the same
+/// factorisation and forms the bread of the covariance selected by `covariance`: the classical
+/// `s^2 (X'X)^{-1}` with `s^2 = SSR / df_resid`, or one of the heteroskedasticity-robust
+/// sandwiches.
 ///
 /// # Arguments
 ///
 /// * `x` - A 2D array (`Array2<f64>`) representing the design matrix (independent variables).
 /// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `covariance` - The `CovarianceType` used for the standard errors.
 /// * `intercept` - A boolean indicating whether to include an intercept term in the model.
 ///
 /// # Returns
 ///
-/// * `Ok(FittedLinearRegression<f64>)` if the model fits successfully.
-/// * `Err(String)` if the regression fitting process fails.
+/// * `Ok(FittedOls)` if the model fits successfully.
+/// * `Err(String)` if `y` does not have a single column, the rows do not match, there are no
+///   more observations than coefficients, or the design matrix is rank deficient.
 ///
 /// # Example
 ///
 /// ```
 /// use ndarray::Array2;
-/// use linfa_linear::FittedLinearRegression;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
 /// use marketing_iv_methods::utils::linear_regression::run_regression;
 ///
-/// let y: Array2<f64> = Array2::from_elem((10, 1), 1.0);
-/// let x: Array2<f64> = Array2::from_elem((10, 1), 0.5);
-/// let result = run_regression(&x, &y, false).unwrap();
+/// let y: Array2<f64> = Array2::from_shape_vec((5, 1), vec![1.0, 1.2, 0.9, 1.1, 1.0]).unwrap();
+/// let x: Array2<f64> = Array2::from_elem((5, 1), 0.5);
+/// let result = run_regression(&x, &y, &CovarianceType::HC1, false).unwrap();
 /// let coefficients = result.params();
+/// let p_values = result.p_values();
 /// ```
 pub fn run_regression(
     x: &Array2<f64>,
     y: &Array2<f64>,
+    covariance: &CovarianceType,
+    intercept: bool,
+) -> Result<FittedOls, String> {
+    let n = y.nrows();
+    if y.ncols() != 1 {
+        return Err("y must have exactly one column".into());
+    }
+    if x.nrows() != n {
+        return Err("x and y must have the same number of rows".into());
+    }
+    let design = if intercept { add_intercept(x) } else { x.to_owned() };
+    let n_coefs = design.ncols();
+    if n_coefs == 0 {
+        return Err("The regression has no coefficients".into());
+    }
+    if n <= n_coefs {
+        return Err("OLS requires more observations than coefficients".into());
+    }
+
+    let (q, r) = design
+        .qr()
+        .map_err(|e| format!("Failed to factorise the design matrix: {}", e))?
+        .into_decomp();
+    let diag = r.diag().mapv(f64::abs);
+    let largest = diag.fold(0.0_f64, |acc, d| acc.max(*d));
+    if diag.iter().any(|d| *d <= 1e-10 * largest.max(1.0)) {
+        return Err("The design matrix is rank deficient".into());
+    }
+
+    let coefs = r
+        .solve_triangular(&q.t().dot(y), UPLO::Upper)
+        .map_err(|e| format!("Failed to solve the triangular system: {}", e))?
+        .index_axis(Axis(1), 0)
+        .to_owned();
+    let residuals = y.column(0).to_owned() - design.dot(&coefs);
+
+    // the bread (X'X)^{-1} = R^{-1} R^{-T}
+    let df_resid = n - n_coefs;
+    let ssr = residuals.dot(&residuals);
+    let r_inv = r
+        .solve_triangular(&Array2::eye(n_coefs), UPLO::Upper)
+        .map_err(|e| format!("Failed to invert the triangular factor: {}", e))?;
+    let bread = r_inv.dot(&r_inv.t());
+    let coef_covariance = coefficient_covariance(&design, &bread, &residuals, covariance)?;
+    let std_all = coef_covariance.diag().mapv(f64::sqrt);
+
+    let y_col = y.column(0);
+    let total = if intercept {
+        let mean = y_col.mean().unwrap_or(0.0);
+        y_col.mapv(|v| (v - mean).powi(2)).sum()
+    } else {
+        y_col.dot(&y_col)
+    };
+    let r_squared = 1.0 - ssr / total;
+    let n_const = if intercept { 1 } else { 0 };
+    let adj_r_squared = 1.0 - (1.0 - r_squared) * (n - n_const) as f64 / df_resid as f64;
+
+    let (params, intercept_value) = split_intercept(coefs, intercept);
+    let n_slopes = params.len();
+
+    Ok(FittedOls {
+        params,
+        intercept: intercept_value,
+        std_errors: std_all.slice(s![..n_slopes]).to_owned(),
+        intercept_std_error: if intercept { Some(std_all[n_slopes]) } else { None },
+        covariance: coef_covariance,
+        covariance_type: covariance.clone(),
+        residuals,
+        r_squared,
+        adj_r_squared,
+        df_model: n_slopes,
+        df_resid,
+    })
+}
+
+/// Runs a k-class instrumental-variables regression.
+///
+/// The structural equation is `y = x_endog * beta + x_exog * gamma + e`, where the columns of
+/// `x_endog` are correlated with `e`. The excluded instruments `z` together with the exogenous
+/// controls `x_exog` form the instrument set, so each control acts as its own instrument.
+/// The k-class estimator is
+///
+/// `b(k) = (W' (I - k M_Z) W)^{-1} W' (I - k M_Z) y`,
+///
+/// where `W = [x_endog, x_exog]` and `M_Z` is the annihilator of `[z, x_exog]` (both with a
+/// column of ones appended when `intercept` is `true`). Setting `k = 0` gives OLS, `k = 1`
+/// gives 2SLS, and LIML and Fuller use the data-dependent values computed by `run_liml` and
+/// `run_fuller`.
+///
+/// The standard errors treat the estimator as `b = (A'W)^{-1} A'y` with
+/// `A = (I - k M_Z) W`, and are computed from `A` as requested by `covariance`.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls. Pass an array with zero
+///   columns if there are none.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments. It needs at least as many
+///   columns as `x_endog` for the model to be identified.
+/// * `k` - The k-class parameter.
+/// * `covariance` - The `CovarianceType` used for the standard errors.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(FittedIvRegression)` if the model fits successfully.
+/// * `Err(String)` if the inputs have mismatched rows, the model is under-identified, or the
+///   instrument or regressor cross-products are singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::linear_regression::run_k_class;
+///
+/// let z = Array2::from_shape_vec((5, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+/// let u = Array2::from_shape_vec((5, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + &u;
+/// let controls = Array2::zeros((5, 0));
+/// let ols = run_k_class(&y, &x, &controls, &z, 0.0, &CovarianceType::Classical, true).unwrap();
+/// let tsls = run_k_class(&y, &x, &controls, &z, 1.0, &CovarianceType::Classical, true).unwrap();
+/// println!("OLS = {}, 2SLS = {}", ols.params()[0], tsls.params()[0]);
+/// ```
+pub fn run_k_class(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    k: f64,
+    covariance: &CovarianceType,
+    intercept: bool,
+) -> Result<FittedIvRegression, String> {
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    k_class_fit(y, &design, k, covariance, intercept)
+}
+
+/// Splits a coefficient vector whose last entry is the intercept (when `intercept` is `true`)
+/// into the slope coefficients and the intercept.
+pub(crate) fn split_intercept(coefs: Array1<f64>, intercept: bool) -> (Array1<f64>, f64) {
+    if intercept {
+        let value = coefs[coefs.len() - 1];
+        (coefs.slice(s![..-1]).to_owned(), value)
+    } else {
+        (coefs, 0.0)
+    }
+}
+
+/// Fits the k-class estimator on an already validated design.
+fn k_class_fit(
+    y: &Array2<f64>,
+    design: &IvDesign,
+    k: f64,
+    covariance: &CovarianceType,
+    intercept: bool,
+) -> Result<FittedIvRegression, String> {
+    let w = &design.w;
+
+    // first-stage residuals of every regressor on the full instrument set, i.e. M_Z W
+    let w_resid = residualize(&design.z_full, w)
+        .map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+
+    // A = (I - k M_Z) W, so that A'W = W' (I - k M_Z) W and A'y = W' (I - k M_Z) y
+    let projected = w - &(k * &w_resid);
+    let bread = solve_general(&projected.t().dot(w), &Array2::eye(w.ncols()))
+        .map_err(|e| format!("Second-stage cross-product is singular: {}", e))?;
+    let coefs = bread.dot(&projected.t().dot(y)).index_axis(Axis(1), 0).to_owned();
+    let resid = y.column(0).to_owned() - w.dot(&coefs);
+    let coef_covariance = coefficient_covariance(&projected, &bread, &resid, covariance)?;
+    let std_all = coef_covariance.diag().mapv(f64::sqrt);
+
+    let (params, intercept_value) = split_intercept(coefs, intercept);
+    let n_slopes = params.len();
+
+    Ok(FittedIvRegression {
+        params,
+        intercept: intercept_value,
+        k,
+        std_errors: std_all.slice(s![..n_slopes]).to_owned(),
+        intercept_std_error: if intercept { Some(std_all[n_slopes]) } else { None },
+        covariance: coef_covariance,
+        covariance_type: covariance.clone(),
+    })
+}
+
+/// Computes the LIML value of `k`: the smallest root of `det(Y' M_X Y - k Y' M_Z Y) = 0`,
+/// where `Y = [y, x_endog]` and `M_X` is the annihilator of the included exogenous regressors.
+fn liml_kappa(y: &Array2<f64>, x_endog: &Array2<f64>, design: &IvDesign) -> Result<f64, String> {
+    let y_all = hstack(&[y, x_endog])?;
+    let resid_x = residualize(&design.exog, &y_all)?;
+    let resid_z = residualize(&design.z_full, &y_all)?;
+    min_generalized_eigenvalue(&resid_x.t().dot(&resid_x), &resid_z.t().dot(&resid_z))
+        .map_err(|e| format!("Failed to compute the LIML kappa: {}", e))
+}
+
+/// Runs a two-stage least squares (2SLS) instrumental-variables regression.
+///
+/// This is the k-class estimator with `k = 1`:
+///
+/// `b = (W' P_Z W)^{-1} W' P_Z y`,
+///
+/// where `W = [x_endog, x_exog]` and `P_Z` is the projection onto `[z, x_exog]`. See
+/// `run_k_class` for the layout of the inputs.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `covariance` - The `CovarianceType` used for the standard errors.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(FittedIvRegression)` if the model fits successfully.
+/// * `Err(String)` if the inputs are invalid or the fit fails.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::linear_regression::run_2sls;
+///
+/// let z = Array2::from_shape_vec((5, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+/// let u = Array2::from_shape_vec((5, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + &u;
+/// let controls = Array2::zeros((5, 0));
+/// let fit = run_2sls(&y, &x, &controls, &z, &CovarianceType::HC1, true).unwrap();
+/// println!("beta = {}", fit.params()[0]);
+/// ```
+pub fn run_2sls(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    covariance: &CovarianceType,
+    intercept: bool,
+) -> Result<FittedIvRegression, String> {
+    run_k_class(y, x_endog, x_exog, z, 1.0, covariance, intercept)
+}
+
+/// Runs a limited-information maximum likelihood (LIML) instrumental-variables regression.
+///
+/// LIML is the k-class estimator with `k` equal to the smallest root of
+/// `det(Y' M_X Y - k Y' M_Z Y) = 0`, where `Y = [y, x_endog]`, `M_X` is the annihilator of the
+/// exogenous controls and `M_Z` that of the full instrument set. It is approximately median
+/// unbiased and much less biased towards OLS than 2SLS when the instruments are weak, at the
+/// cost of having no finite moments. The chosen value is reported by `FittedIvRegression::k`.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `covariance` - The `CovarianceType` used for the standard errors.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(FittedIvRegression)` if the model fits successfully.
+/// * `Err(String)` if the inputs are invalid or the fit fails.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::linear_regression::run_liml;
+///
+/// let z = Array2::from_shape_vec((6, 2), vec![1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0]).unwrap();
+/// let u = Array2::from_shape_vec((6, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let e = Array2::from_shape_vec((6, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2]).unwrap();
+/// let y = -0.5 * &x + &u + &e;
+/// let fit = run_liml(&y, &x, &Array2::zeros((6, 0)), &z, &CovarianceType::Classical, true).unwrap();
+/// assert!(fit.k() >= 1.0);
+/// ```
+pub fn run_liml(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    covariance: &CovarianceType,
+    intercept: bool,
+) -> Result<FittedIvRegression, String> {
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    let kappa = liml_kappa(y, x_endog, &design)?;
+    k_class_fit(y, &design, kappa, covariance, intercept)
+}
+
+/// Runs Fuller's modified LIML instrumental-variables regression.
+///
+/// Fuller(`alpha`) is the k-class estimator with `k = kappa_liml - alpha / (n - L)`, where `L`
+/// is the total number of instruments including the exogenous controls and the intercept.
+/// Unlike LIML it has finite moments; `alpha = 1` gives an approximately unbiased estimator and
+/// `alpha = 4` approximately minimises the mean squared error.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `alpha` - Fuller's constant. Must be non-negative and finite; `0` reproduces LIML.
+/// * `covariance` - The `CovarianceType` used for the standard errors.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(FittedIvRegression)` if the model fits successfully.
+/// * `Err(String)` if `alpha` is negative or not finite, there are no more observations than
+///   instruments, or the fit fails.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::linear_regression::{run_fuller, run_liml};
+///
+/// let z = Array2::from_shape_vec((6, 2), vec![1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0]).unwrap();
+/// let u = Array2::from_shape_vec((6, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let e = Array2::from_shape_vec((6, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2]).unwrap();
+/// let y = -0.5 * &x + &u + &e;
+/// let controls = Array2::zeros((6, 0));
+/// let liml = run_liml(&y, &x, &controls, &z, &CovarianceType::Classical, true).unwrap();
+/// let fuller = run_fuller(&y, &x, &controls, &z, 1.0, &CovarianceType::Classical, true).unwrap();
+/// assert!(fuller.k() < liml.k());
+/// ```
+pub fn run_fuller(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    alpha: f64,
+    covariance: &CovarianceType,
     intercept: bool,
-) -> Result<FittedLinearRegression<f64>, String> {
-    // Convert y from (n,1) to a flat array
-    let y_flat: Array1<f64> = y.column(0).to_owned();
-
-    // Create a Dataset from x and y
-    let dataset = Dataset::new(x.to_owned(), y_flat);
-
-    LinearRegression::default()
-        .with_intercept(intercept)
-        .fit(&dataset)
-        .map_err(|e| format!("Failed to fit linear regression: {}", e))    
-}
\ No newline at end of file
+) -> Result<FittedIvRegression, String> {
+    if !(alpha >= 0.0 && alpha.is_finite()) {
+        return Err("alpha must be non-negative and finite".into());
+    }
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    let n = design.z_full.nrows();
+    let n_instruments = design.z_full.ncols();
+    if n <= n_instruments {
+        return Err("Fuller requires more observations than instruments".into());
+    }
+    let kappa = liml_kappa(y, x_endog, &design)?;
+    k_class_fit(y, &design, kappa - alpha / (n - n_instruments) as f64, covariance, intercept)
+}
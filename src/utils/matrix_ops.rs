@@ -0,0 +1,111 @@
+use linfa_linalg::cholesky::{Cholesky, InverseC, SolveC};
+use linfa_linalg::eigh::{EigSort, EigValsh, Eigh};
+use linfa_linalg::qr::QR;
+use linfa_linalg::triangular::{SolveTriangular, UPLO};
+use ndarray::{concatenate, s, Array1, Array2, Axis};
+
+/// Appends a column of ones to the right of `x`.
+///
+/// Used by the estimators that accept an `intercept` flag, so that the constant is always
+/// the last regressor in the returned coefficient vector.
+pub(crate) fn add_intercept(x: &Array2<f64>) -> Array2<f64> {
+    let ones = Array2::<f64>::ones((x.nrows(), 1));
+    concatenate(Axis(1), &[x.view(), ones.view()]).unwrap()
+}
+
+/// Stacks the columns of the given matrices side by side, skipping any that have no columns.
+///
+/// All matrices must have the same number of rows.
+pub(crate) fn hstack(blocks: &[&Array2<f64>]) -> Result<Array2<f64>, String> {
+    let views: Vec<_> = blocks.iter().filter(|b| b.ncols() > 0).map(|b| b.view()).collect();
+    if views.is_empty() {
+        return Err("Cannot stack an empty set of matrices".into());
+    }
+    concatenate(Axis(1), &views).map_err(|e| format!("Failed to stack matrices: {}", e))
+}
+
+/// Solves `a * x = b` for a symmetric positive definite `a` using a Cholesky factorisation.
+pub(crate) fn solve_spd(a: &Array2<f64>, b: &Array2<f64>) -> Result<Array2<f64>, String> {
+    a.to_owned()
+        .solvec(b)
+        .map_err(|e| format!("Failed to solve linear system: {}", e))
+}
+
+/// Inverts a symmetric positive definite matrix using a Cholesky factorisation.
+pub(crate) fn inverse_spd(a: &Array2<f64>) -> Result<Array2<f64>, String> {
+    a.invc().map_err(|e| format!("Failed to invert matrix: {}", e))
+}
+
+/// Solves the square system `a * x = b` using a QR factorisation.
+///
+/// Unlike `solve_spd`, this does not require `a` to be symmetric or positive definite.
+pub(crate) fn solve_general(a: &Array2<f64>, b: &Array2<f64>) -> Result<Array2<f64>, String> {
+    a.qr()
+        .and_then(|qr| qr.solve(b))
+        .map_err(|e| format!("Failed to solve linear system: {}", e))
+}
+
+/// Returns the residuals from regressing each column of `b` on the columns of `a`, i.e. `M_a * b`.
+///
+/// If `a` has no columns, `b` is returned unchanged.
+pub(crate) fn residualize(a: &Array2<f64>, b: &Array2<f64>) -> Result<Array2<f64>, String> {
+    if a.ncols() == 0 {
+        return Ok(b.to_owned());
+    }
+    let coefs = solve_spd(&a.t().dot(a), &a.t().dot(b))?;
+    Ok(b - &a.dot(&coefs))
+}
+
+/// Returns the smallest root `k` of `det(a - k * b) = 0` for symmetric `a` and symmetric
+/// positive definite `b`.
+pub(crate) fn min_generalized_eigenvalue(a: &Array2<f64>, b: &Array2<f64>) -> Result<f64, String> {
+    Ok(generalized_eigenvalues(a, b)?.iter().cloned().fold(f64::INFINITY, f64::min))
+}
+
+/// Returns all roots `k` of `det(a - k * b) = 0` for symmetric `a` and symmetric positive
+/// definite `b`.
+///
+/// The problem is reduced to an ordinary symmetric eigenvalue problem through the Cholesky
+/// factor of `b`.
+pub(crate) fn generalized_eigenvalues(a: &Array2<f64>, b: &Array2<f64>) -> Result<Array1<f64>, String> {
+    let chol = b.cholesky().map_err(|e| format!("Failed to factorise matrix: {}", e))?;
+    let half = chol
+        .solve_triangular(a, UPLO::Lower)
+        .map_err(|e| format!("Failed to solve triangular system: {}", e))?;
+    let reduced = chol
+        .solve_triangular(&half.t().to_owned(), UPLO::Lower)
+        .map_err(|e| format!("Failed to solve triangular system: {}", e))?;
+    let symmetric = (&reduced + &reduced.t()) / 2.0;
+    symmetric
+        .eigvalsh()
+        .map_err(|e| format!("Failed to compute eigenvalues: {}", e))
+}
+
+/// Returns the eigenvalues and eigenvectors (as columns) of a symmetric matrix, in descending
+/// order of the eigenvalues.
+pub(crate) fn eigh_descending(a: &Array2<f64>) -> Result<(Array1<f64>, Array2<f64>), String> {
+    let symmetric = (a + &a.t()) / 2.0;
+    symmetric
+        .eigh()
+        .map(|decomposition| decomposition.sort_eig_desc())
+        .map_err(|e| format!("Failed to compute eigendecomposition: {}", e))
+}
+
+/// Returns the symmetric square root of a symmetric positive semi-definite matrix.
+pub(crate) fn symmetric_sqrt(a: &Array2<f64>) -> Result<Array2<f64>, String> {
+    let (values, vectors) = eigh_descending(a)?;
+    let scaled = &vectors * &values.mapv(|v| v.max(0.0).sqrt());
+    Ok(scaled.dot(&vectors.t()))
+}
+
+/// Returns the Kronecker product `a ⊗ b`.
+pub(crate) fn kron(a: &Array2<f64>, b: &Array2<f64>) -> Array2<f64> {
+    let (rows, cols) = b.dim();
+    let mut product = Array2::zeros((a.nrows() * rows, a.ncols() * cols));
+    for ((i, j), &value) in a.indexed_iter() {
+        product
+            .slice_mut(s![i * rows..(i + 1) * rows, j * cols..(j + 1) * cols])
+            .assign(&(b * value));
+    }
+    product
+}
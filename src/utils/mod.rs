@@ -1,3 +1,20 @@
 pub mod random_vector_gen;
+pub mod error_distribution;
 pub mod linear_regression;
-pub mod plot_bias;
\ No newline at end of file
+pub mod plot_bias;
+pub mod gmm;
+pub mod control_function;
+pub mod bayesian_iv;
+pub mod jive;
+pub mod plausibly_exogenous;
+pub mod weak_iv;
+pub mod covariance;
+pub mod bootstrap;
+pub mod elasticity;
+pub mod first_stage;
+pub mod hausman;
+pub mod overid;
+pub mod summary;
+pub(crate) mod matrix_ops;
+pub(crate) mod optimize;
+pub(crate) mod posterior_draws;
\ No newline at end of file
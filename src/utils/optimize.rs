@@ -0,0 +1,81 @@
+use ndarray::Array1;
+
+/// Minimises `f` with the Nelder-Mead simplex method, starting from `x0`.
+///
+/// The initial simplex moves each coordinate of `x0` by `step[i]`. The search stops once the
+/// spread of function values across the simplex falls below `tol`, or after `max_iter`
+/// iterations. Returns the best point found together with its function value.
+pub(crate) fn nelder_mead<F>(
+    f: F,
+    x0: &Array1<f64>,
+    step: &Array1<f64>,
+    tol: f64,
+    max_iter: usize,
+) -> (Array1<f64>, f64)
+where
+    F: Fn(&Array1<f64>) -> f64,
+{
+    let dim = x0.len();
+
+    // non-finite objective values are treated as infinitely bad so the simplex moves away
+    let eval = |x: &Array1<f64>| {
+        let value = f(x);
+        if value.is_finite() { value } else { f64::INFINITY }
+    };
+
+    let mut simplex: Vec<(Array1<f64>, f64)> = Vec::with_capacity(dim + 1);
+    simplex.push((x0.clone(), eval(x0)));
+    for i in 0..dim {
+        let mut vertex = x0.clone();
+        vertex[i] += step[i];
+        let value = eval(&vertex);
+        simplex.push((vertex, value));
+    }
+
+    for _ in 0..max_iter {
+        simplex.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        if (simplex[dim].1 - simplex[0].1).abs() < tol {
+            break;
+        }
+
+        // centroid of every vertex except the worst
+        let mut centroid = Array1::<f64>::zeros(dim);
+        for (vertex, _) in simplex.iter().take(dim) {
+            centroid += vertex;
+        }
+        centroid /= dim as f64;
+
+        let worst = simplex[dim].clone();
+        let reflected = &centroid + &(&centroid - &worst.0);
+        let reflected_value = eval(&reflected);
+
+        if reflected_value < simplex[0].1 {
+            let expanded = &centroid + &(2.0 * (&reflected - &centroid));
+            let expanded_value = eval(&expanded);
+            simplex[dim] = if expanded_value < reflected_value {
+                (expanded, expanded_value)
+            } else {
+                (reflected, reflected_value)
+            };
+        } else if reflected_value < simplex[dim - 1].1 {
+            simplex[dim] = (reflected, reflected_value);
+        } else {
+            let contracted = &centroid + &(0.5 * (&worst.0 - &centroid));
+            let contracted_value = eval(&contracted);
+            if contracted_value < worst.1 {
+                simplex[dim] = (contracted, contracted_value);
+            } else {
+                // shrink every vertex towards the best one
+                let best = simplex[0].0.clone();
+                for vertex in simplex.iter_mut().skip(1) {
+                    let shrunk = &best + &(0.5 * (&vertex.0 - &best));
+                    let value = eval(&shrunk);
+                    *vertex = (shrunk, value);
+                }
+            }
+        }
+    }
+
+    simplex.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    simplex.swap_remove(0)
+}
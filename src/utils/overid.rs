@@ -0,0 +1,256 @@
+use super::gmm::{gmm_coefficients, j_value, moment_covariance};
+use super::linear_regression::iv_design;
+use super::matrix_ops::inverse_spd;
+use ndarray::{Array2, Axis};
+use statrs::distribution::{ChiSquared, ContinuousCDF};
+
+/// The overidentification statistic computed by `run_overid_test` and
+/// `run_difference_in_sargan`.
+///
+/// - `Sargan`: `n` times the R-squared of the 2SLS residuals on the instruments, valid when the
+///   structural errors are homoskedastic.
+/// - `HansenJ`: The J statistic of two-step efficient GMM, whose weighting matrix is the
+///   heteroskedasticity-robust covariance of the moments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OveridStatistic {
+    Sargan,
+    HansenJ,
+}
+
+/// The result of a test of overidentifying restrictions, as returned by `run_overid_test` and
+/// `run_difference_in_sargan`.
+///
+/// This structure holds:
+/// - `kind`: The `OveridStatistic` computed.
+/// - `statistic`: The test statistic for the null that the instruments tested are valid.
+/// - `df`: The degrees of freedom of the chi-squared reference distribution.
+/// - `p_value`: The chi-squared p-value of `statistic`.
+#[derive(Debug, Clone)]
+pub struct OveridTest {
+    kind: OveridStatistic,
+    statistic: f64,
+    df: usize,
+    p_value: f64,
+}
+
+impl OveridTest {
+    /// The statistic computed.
+    pub fn kind(&self) -> OveridStatistic {
+        self.kind
+    }
+
+    /// The test statistic.
+    pub fn statistic(&self) -> f64 {
+        self.statistic
+    }
+
+    /// The degrees of freedom of the statistic.
+    pub fn df(&self) -> usize {
+        self.df
+    }
+
+    /// The chi-squared p-value of the statistic.
+    pub fn p_value(&self) -> f64 {
+        self.p_value
+    }
+}
+
+/// The covariance of the moments `z_i e_i` at the 2SLS residuals, under homoskedasticity for
+/// Sargan and robust to heteroskedasticity for Hansen's J.
+fn moment_variance(z_full: &Array2<f64>, resid: &Array2<f64>, kind: OveridStatistic) -> Array2<f64> {
+    match kind {
+        OveridStatistic::Sargan => {
+            let n = z_full.nrows() as f64;
+            let sigma2 = resid.iter().map(|e| e * e).sum::<f64>() / n;
+            sigma2 * z_full.t().dot(z_full) / n
+        }
+        OveridStatistic::HansenJ => moment_covariance(z_full, resid),
+    }
+}
+
+/// The minimised efficient GMM objective for the instruments `z_full`, given the moment
+/// covariance `s`.
+fn efficient_j(y: &Array2<f64>, w: &Array2<f64>, z_full: &Array2<f64>, s: &Array2<f64>) -> Result<f64, String> {
+    let weight = inverse_spd(s).map_err(|e| format!("Moment covariance is singular: {}", e))?;
+    let coefs = gmm_coefficients(y, w, z_full, &weight)?;
+    Ok(j_value(z_full, &(y - &w.dot(&coefs)), &weight))
+}
+
+fn chi_squared_p_value(statistic: f64, df: usize) -> Result<f64, String> {
+    let chi2 = ChiSquared::new(df as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+    Ok(1.0 - chi2.cdf(statistic))
+}
+
+/// Tests the overidentifying restrictions of a linear IV model.
+///
+/// With more excluded instruments than endogenous regressors, the moment conditions
+/// `E[z_i (y_i - w_i' b)] = 0` cannot all hold exactly in the sample, and a large minimised
+/// GMM objective is evidence that some instruments are correlated with the structural error.
+/// The statistic is `n g' S^{-1} g` at the efficient GMM estimate, where `g` is the sample mean
+/// of the moments and `S` their covariance evaluated at the 2SLS residuals:
+///
+/// - `OveridStatistic::Sargan` uses `S = s^2 Z'Z / n`, for which the efficient estimate is 2SLS
+///   and the statistic is `n` times the uncentered R-squared of the 2SLS residuals on `Z`.
+/// - `OveridStatistic::HansenJ` uses `S = Z' diag(e^2) Z / n`, and equals the J statistic of
+///   `run_gmm` with `GmmWeighting::TwoStep`.
+///
+/// Under the null that every instrument is valid the statistic is asymptotically chi-squared
+/// with `L - K` degrees of freedom, the number of instruments minus the number of regressors.
+/// The test only has power against violations that the instruments disagree about: if every
+/// instrument is invalid in proportion to its first-stage coefficient, all of them point to the
+/// same wrong estimate and the test has no power at all.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments, more than there are endogenous
+///   regressors.
+/// * `kind` - The `OveridStatistic` to compute.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(OveridTest)` with the statistic and its p-value.
+/// * `Err(String)` if the inputs are invalid, the model is not overidentified, or a
+///   cross-product matrix is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::overid::{run_overid_test, OveridStatistic};
+///
+/// let z = Array2::from_shape_vec((8, 2), vec![
+///     1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0, 7.0, 0.0, 8.0, 1.0,
+/// ]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let test = run_overid_test(&y, &x, &Array2::zeros((8, 0)), &z, OveridStatistic::Sargan, true).unwrap();
+/// assert_eq!(test.df(), 1);
+/// println!("Sargan = {}, p-value = {}", test.statistic(), test.p_value());
+/// ```
+pub fn run_overid_test(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    kind: OveridStatistic,
+    intercept: bool,
+) -> Result<OveridTest, String> {
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    let df = design.z_full.ncols() - design.w.ncols();
+    if df == 0 {
+        return Err("The model is exactly identified, so there are no overidentifying restrictions to test".into());
+    }
+
+    let s = two_sls_moment_variance(y, &design.w, &design.z_full, kind)?;
+    let statistic = efficient_j(y, &design.w, &design.z_full, &s)?;
+
+    Ok(OveridTest { kind, statistic, df, p_value: chi_squared_p_value(statistic, df)? })
+}
+
+/// The moment covariance of `kind` at the 2SLS residuals.
+fn two_sls_moment_variance(
+    y: &Array2<f64>,
+    w: &Array2<f64>,
+    z_full: &Array2<f64>,
+    kind: OveridStatistic,
+) -> Result<Array2<f64>, String> {
+    let zz_inv = inverse_spd(&z_full.t().dot(z_full)).map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+    let two_sls = gmm_coefficients(y, w, z_full, &zz_inv)?;
+    Ok(moment_variance(z_full, &(y - &w.dot(&two_sls)), kind))
+}
+
+/// Tests the validity of a subset of the excluded instruments, maintaining the validity of the
+/// others.
+///
+/// The difference-in-Sargan (or C) statistic is the overidentification statistic of the full
+/// model minus that of the model which drops the `suspect` instruments, both computed with the
+/// moment covariance of the full model so that the difference is non-negative. Under the null
+/// that the suspect instruments are valid it is asymptotically chi-squared with one degree of
+/// freedom per suspect instrument. The restricted model must still be identified, so the
+/// remaining excluded instruments have to be at least as many as the endogenous regressors.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `suspect` - The columns of `z` whose validity is tested.
+/// * `kind` - The `OveridStatistic` whose difference is computed.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(OveridTest)` with the difference statistic and its p-value.
+/// * `Err(String)` if the inputs are invalid, `suspect` is empty, repeats a column or refers to a
+///   column outside `z`, the restricted model is under-identified, or a cross-product matrix is
+///   singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::overid::{run_difference_in_sargan, OveridStatistic};
+///
+/// let z = Array2::from_shape_vec((8, 2), vec![
+///     1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0, 7.0, 0.0, 8.0, 1.0,
+/// ]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let none = Array2::zeros((8, 0));
+/// let test = run_difference_in_sargan(&y, &x, &none, &z, &[1], OveridStatistic::HansenJ, true).unwrap();
+/// println!("C = {}, p-value = {}", test.statistic(), test.p_value());
+/// ```
+pub fn run_difference_in_sargan(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    suspect: &[usize],
+    kind: OveridStatistic,
+    intercept: bool,
+) -> Result<OveridTest, String> {
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    if suspect.is_empty() {
+        return Err("At least one suspect instrument is required".into());
+    }
+    if suspect.iter().any(|&j| j >= z.ncols()) {
+        return Err(format!("Suspect instruments must be columns of z, which has {} columns", z.ncols()));
+    }
+    let mut sorted = suspect.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    if sorted.len() != suspect.len() {
+        return Err("Suspect instruments must not repeat".into());
+    }
+    if z.ncols() - suspect.len() < x_endog.ncols() {
+        return Err(format!(
+            "Dropping the suspect instruments leaves {} instruments for {} endogenous regressors",
+            z.ncols() - suspect.len(),
+            x_endog.ncols()
+        ));
+    }
+
+    // the excluded instruments are the leading columns of z_full
+    let keep: Vec<usize> = (0..design.z_full.ncols()).filter(|j| !suspect.contains(j)).collect();
+    let s = two_sls_moment_variance(y, &design.w, &design.z_full, kind)?;
+    let full = efficient_j(y, &design.w, &design.z_full, &s)?;
+    let restricted = efficient_j(
+        y,
+        &design.w,
+        &design.z_full.select(Axis(1), &keep),
+        &s.select(Axis(0), &keep).select(Axis(1), &keep),
+    )?;
+
+    let statistic = (full - restricted).max(0.0);
+    let df = suspect.len();
+    Ok(OveridTest { kind, statistic, df, p_value: chi_squared_p_value(statistic, df)? })
+}
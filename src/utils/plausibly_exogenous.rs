@@ -0,0 +1,302 @@
+use super::linear_regression::iv_design;
+use super::matrix_ops::{inverse_spd, residualize};
+use ndarray::{s, Array1, Array2, Axis};
+use statrs::distribution::{ContinuousCDF, Normal};
+
+/// Confidence bounds for the coefficients on the endogenous regressors when the instruments are
+/// only plausibly exogenous, as returned by `run_uci` and `run_ltz`.
+///
+/// This structure holds:
+/// - `lower`: The lower bound for each endogenous coefficient.
+/// - `upper`: The upper bound for each endogenous coefficient.
+/// - `confidence`: The nominal coverage of the bounds, e.g. `0.95`.
+#[derive(Debug, Clone)]
+pub struct PlausibleBounds {
+    lower: Array1<f64>,
+    upper: Array1<f64>,
+    confidence: f64,
+}
+
+impl PlausibleBounds {
+    /// The lower bound for each endogenous coefficient.
+    pub fn lower(&self) -> &Array1<f64> {
+        &self.lower
+    }
+
+    /// The upper bound for each endogenous coefficient.
+    pub fn upper(&self) -> &Array1<f64> {
+        &self.upper
+    }
+
+    /// The nominal coverage of the bounds.
+    pub fn confidence(&self) -> f64 {
+        self.confidence
+    }
+}
+
+/// The 2SLS pieces shared by both approaches: the projected regressors and the inverse of their
+/// cross-product.
+struct TslsProjection {
+    w: Array2<f64>,
+    w_hat: Array2<f64>,
+    bread: Array2<f64>,
+    n_endog: usize,
+}
+
+impl TslsProjection {
+    /// Returns the 2SLS coefficients of `y` and their homoskedastic covariance.
+    fn fit(&self, y: &Array2<f64>) -> (Array1<f64>, Array2<f64>) {
+        let coefs = self.bread.dot(&self.w_hat.t().dot(y));
+        let resid = y - &self.w.dot(&coefs);
+        let sigma2 = resid.iter().map(|e| e * e).sum::<f64>() / y.nrows() as f64;
+        (coefs.index_axis(Axis(1), 0).to_owned(), sigma2 * &self.bread)
+    }
+}
+
+/// Checks the inputs and projects the regressors on the instrument set.
+fn tsls_projection(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    intercept: bool,
+) -> Result<TslsProjection, String> {
+    let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+    if x_endog.ncols() == 0 {
+        return Err("x_endog must have at least one column".into());
+    }
+    let w_hat = &design.w - &residualize(&design.z_full, &design.w)?;
+    let bread = inverse_spd(&w_hat.t().dot(&w_hat))
+        .map_err(|e| format!("2SLS cross-product is singular: {}", e))?;
+    Ok(TslsProjection { w: design.w, w_hat, bread, n_endog: x_endog.ncols() })
+}
+
+/// Returns the two-sided standard normal critical value for the given coverage.
+fn normal_critical_value(confidence: f64) -> Result<f64, String> {
+    if !(confidence > 0.0 && confidence < 1.0) {
+        return Err("confidence must be between 0 and 1".into());
+    }
+    let normal = Normal::new(0.0, 1.0).map_err(|e| format!("Invalid normal distribution: {}", e))?;
+    Ok(normal.inverse_cdf(0.5 + confidence / 2.0))
+}
+
+/// Builds an evenly spaced grid over the box `[lower[j], upper[j]]` for each instrument `j`.
+///
+/// Each row of the result is one candidate value of `gamma`, so the grid can be passed straight
+/// to `run_uci` as the support of the direct effects. With `L` instruments the grid has
+/// `points_per_dim^L` rows.
+///
+/// # Arguments
+///
+/// * `lower` - The lower end of the support of each element of `gamma`.
+/// * `upper` - The upper end of the support of each element of `gamma`.
+/// * `points_per_dim` - The number of grid points along each dimension, at least 2.
+///
+/// # Returns
+///
+/// * `Ok(Array2<f64>)` with one candidate `gamma` per row.
+/// * `Err(String)` if the bounds have different lengths, are empty, or are not ordered, or if
+///   `points_per_dim` is below 2, or if the grid is too large to allocate.
+///
+/// # Example
+///
+/// ```
+/// use marketing_iv_methods::utils::plausibly_exogenous::gamma_box_grid;
+///
+/// let grid = gamma_box_grid(&[-0.1, 0.0], &[0.1, 0.2], 3).unwrap();
+/// assert_eq!(grid.shape(), &[9, 2]);
+/// ```
+pub fn gamma_box_grid(lower: &[f64], upper: &[f64], points_per_dim: usize) -> Result<Array2<f64>, String> {
+    if lower.len() != upper.len() || lower.is_empty() {
+        return Err("lower and upper must be non-empty and have the same length".into());
+    }
+    if lower.iter().zip(upper.iter()).any(|(lo, hi)| lo > hi) {
+        return Err("Each lower bound must not exceed its upper bound".into());
+    }
+    if points_per_dim < 2 {
+        return Err("points_per_dim must be at least 2".into());
+    }
+
+    let dim = lower.len();
+    // the grid holds n_points * dim values, whose byte count must fit in an isize to be allocated
+    let n_points = u32::try_from(dim)
+        .ok()
+        .and_then(|d| points_per_dim.checked_pow(d))
+        .filter(|n| {
+            n.checked_mul(dim)
+                .and_then(|values| values.checked_mul(std::mem::size_of::<f64>()))
+                .is_some_and(|bytes| bytes <= isize::MAX as usize)
+        })
+        .ok_or_else(|| format!("A grid of {} points in each of {} dimensions is too large", points_per_dim, dim))?;
+    Ok(Array2::from_shape_fn((n_points, dim), |(i, j)| {
+        // read the row index as a number in base points_per_dim, one digit per instrument
+        let digit = (i / points_per_dim.pow(j as u32)) % points_per_dim;
+        lower[j] + digit as f64 * (upper[j] - lower[j]) / (points_per_dim - 1) as f64
+    }))
+}
+
+/// Computes the union-of-confidence-intervals (UCI) bounds of Conley, Hansen and Rossi (2012).
+///
+/// The structural equation is `y = x_endog * beta + z * gamma + x_exog * delta + e`, where
+/// exclusion would require `gamma = 0`. Instead of assuming it, the user supplies a support for
+/// `gamma` as a set of candidate values. For each candidate `gamma_0`, `y - z * gamma_0` is
+/// regressed on `[x_endog, x_exog]` by 2SLS and the usual confidence interval for `beta` is
+/// formed. The reported bounds run from the smallest lower end to the largest upper end over the
+/// support, so they cover `beta` with at least the nominal probability whenever the true `gamma`
+/// lies in the support. A support made of the single point `0` reproduces the 2SLS interval.
+///
+/// The 2SLS standard errors assume homoskedastic errors.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `gamma_support` - A 2D array (`Array2<f64>`) with one candidate `gamma` per row and one
+///   column per instrument, e.g. built with `gamma_box_grid`.
+/// * `confidence` - The nominal coverage of each interval, between 0 and 1.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(PlausibleBounds)` with bounds for each endogenous coefficient.
+/// * `Err(String)` if the inputs are invalid, the support is empty or has the wrong number of
+///   columns, or the 2SLS cross-product is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::plausibly_exogenous::{gamma_box_grid, run_uci};
+///
+/// let z = Array2::from_shape_vec((8, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let support = gamma_box_grid(&[-0.05], &[0.05], 11).unwrap();
+/// let bounds = run_uci(&y, &x, &Array2::zeros((8, 0)), &z, &support, 0.95, true).unwrap();
+/// println!("beta in [{}, {}]", bounds.lower()[0], bounds.upper()[0]);
+/// ```
+pub fn run_uci(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    gamma_support: &Array2<f64>,
+    confidence: f64,
+    intercept: bool,
+) -> Result<PlausibleBounds, String> {
+    let projection = tsls_projection(y, x_endog, x_exog, z, intercept)?;
+    let critical = normal_critical_value(confidence)?;
+    if gamma_support.nrows() == 0 {
+        return Err("gamma_support must contain at least one candidate".into());
+    }
+    if gamma_support.ncols() != z.ncols() {
+        return Err(format!(
+            "gamma_support has {} columns but there are {} instruments",
+            gamma_support.ncols(),
+            z.ncols()
+        ));
+    }
+
+    let n_endog = projection.n_endog;
+    let mut lower = Array1::from_elem(n_endog, f64::INFINITY);
+    let mut upper = Array1::from_elem(n_endog, f64::NEG_INFINITY);
+    for gamma in gamma_support.outer_iter() {
+        let y_adjusted = y - &z.dot(&gamma).insert_axis(Axis(1));
+        let (coefs, covariance) = projection.fit(&y_adjusted);
+        for j in 0..n_endog {
+            let half_width = critical * covariance[[j, j]].sqrt();
+            lower[j] = lower[j].min(coefs[j] - half_width);
+            upper[j] = upper[j].max(coefs[j] + half_width);
+        }
+    }
+
+    Ok(PlausibleBounds { lower, upper, confidence })
+}
+
+/// Computes the local-to-zero (LTZ) bounds of Conley, Hansen and Rossi (2012).
+///
+/// Here `gamma` is treated as random with prior mean `gamma_mean` and covariance `gamma_cov`,
+/// and of the same order as the sampling error. The 2SLS estimate is then approximately
+///
+/// `b ~ N(beta + A * gamma_mean, V + A * gamma_cov * A')`,
+///
+/// where `V` is the usual 2SLS covariance and `A` is the 2SLS coefficient of `z` on
+/// `[x_endog, x_exog]`, i.e. the amount by which a unit direct effect of each instrument moves
+/// the estimate. The bounds re-centre the 2SLS estimate and widen its interval accordingly. A
+/// prior with zero mean and zero covariance reproduces the 2SLS interval.
+///
+/// The 2SLS standard errors assume homoskedastic errors.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) of endogenous regressors.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `gamma_mean` - The prior mean of `gamma`, one entry per instrument.
+/// * `gamma_cov` - The prior covariance of `gamma`, a symmetric positive semi-definite matrix.
+/// * `confidence` - The nominal coverage of the interval, between 0 and 1.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(PlausibleBounds)` with bounds for each endogenous coefficient.
+/// * `Err(String)` if the inputs are invalid, the prior has the wrong dimensions, or the 2SLS
+///   cross-product is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::{Array1, Array2};
+/// use marketing_iv_methods::utils::plausibly_exogenous::run_ltz;
+///
+/// let z = Array2::from_shape_vec((8, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let gamma_cov = Array2::from_elem((1, 1), 0.05_f64.powi(2));
+/// let bounds = run_ltz(&y, &x, &Array2::zeros((8, 0)), &z, &Array1::zeros(1), &gamma_cov, 0.95, true).unwrap();
+/// println!("beta in [{}, {}]", bounds.lower()[0], bounds.upper()[0]);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn run_ltz(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    gamma_mean: &Array1<f64>,
+    gamma_cov: &Array2<f64>,
+    confidence: f64,
+    intercept: bool,
+) -> Result<PlausibleBounds, String> {
+    let projection = tsls_projection(y, x_endog, x_exog, z, intercept)?;
+    let critical = normal_critical_value(confidence)?;
+    let n_instruments = z.ncols();
+    if gamma_mean.len() != n_instruments || gamma_cov.shape() != [n_instruments, n_instruments] {
+        return Err(format!(
+            "gamma_mean and gamma_cov must have dimension {}, the number of instruments",
+            n_instruments
+        ));
+    }
+
+    // A maps a direct effect of the instruments into a shift of the 2SLS estimate
+    let a = projection.bread.dot(&projection.w_hat.t().dot(z));
+    let (coefs, covariance) = projection.fit(y);
+    let centre = coefs - a.dot(gamma_mean);
+    let covariance = covariance + a.dot(gamma_cov).dot(&a.t());
+
+    let n_endog = projection.n_endog;
+    let half_width = covariance.diag().slice(s![..n_endog]).mapv(|v| critical * v.sqrt());
+    let centre = centre.slice(s![..n_endog]).to_owned();
+
+    Ok(PlausibleBounds {
+        lower: &centre - &half_width,
+        upper: &centre + &half_width,
+        confidence,
+    })
+}
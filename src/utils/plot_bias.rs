@@ -69,11 +69,11 @@ pub fn plot_bias_vs_alpha_y(
             &RED,
         ))?
         .label("Bias")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
 
     chart
         .configure_series_labels()
-        .border_style(&BLACK)
+        .border_style(BLACK)
         .draw()?; // ✅ renders the legend
 
     Ok(())
@@ -0,0 +1,64 @@
+use super::matrix_ops::inverse_spd;
+use linfa_linalg::cholesky::Cholesky;
+use linfa_linalg::triangular::{SolveTriangular, UPLO};
+use ndarray::{Array1, Array2, Axis};
+use ndarray_rand::rand::Rng;
+use ndarray_rand::rand_distr::{ChiSquared, Distribution, StandardNormal};
+
+/// Draws from `N(P^{-1} b, P^{-1})`, the usual form of a Gaussian full conditional with
+/// precision `P` and linear term `b`.
+pub(crate) fn draw_normal_from_precision<R: Rng + ?Sized>(
+    precision: &Array2<f64>,
+    linear: &Array1<f64>,
+    rng: &mut R,
+) -> Result<Array1<f64>, String> {
+    let chol = precision
+        .cholesky()
+        .map_err(|e| format!("Posterior precision is not positive definite: {}", e))?;
+    let solve = |b: Array2<f64>, uplo| {
+        let factor = match uplo {
+            UPLO::Lower => chol.to_owned(),
+            UPLO::Upper => chol.t().to_owned(),
+        };
+        factor
+            .solve_triangular(&b, uplo)
+            .map_err(|e| format!("Failed to solve triangular system: {}", e))
+    };
+
+    // mean = (L L')^{-1} b and noise = L'^{-1} e, so the draw has covariance (L L')^{-1}
+    let half = solve(linear.clone().insert_axis(Axis(1)), UPLO::Lower)?;
+    let mean = solve(half, UPLO::Upper)?;
+    let standard: Array1<f64> = (0..linear.len()).map(|_| rng.sample(StandardNormal)).collect();
+    let noise = solve(standard.insert_axis(Axis(1)), UPLO::Upper)?;
+    Ok((mean + noise).index_axis(Axis(1), 0).to_owned())
+}
+
+/// Draws from the inverse Wishart distribution `IW(nu, scale)`, parameterised so that
+/// `E[Sigma] = scale / (nu - p - 1)`.
+///
+/// The draw inverts a Wishart draw `W(nu, scale^{-1})` built with the Bartlett decomposition.
+pub(crate) fn draw_inverse_wishart<R: Rng + ?Sized>(
+    nu: f64,
+    scale: &Array2<f64>,
+    rng: &mut R,
+) -> Result<Array2<f64>, String> {
+    let p = scale.nrows();
+    if nu <= (p as f64) - 1.0 {
+        return Err(format!("Inverse Wishart degrees of freedom must exceed {}", p - 1));
+    }
+    let root = inverse_spd(scale)?
+        .cholesky()
+        .map_err(|e| format!("Inverse Wishart scale is not positive definite: {}", e))?;
+
+    let mut bartlett = Array2::<f64>::zeros((p, p));
+    for i in 0..p {
+        let chi2 = ChiSquared::new(nu - i as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+        bartlett[[i, i]] = chi2.sample(rng).sqrt();
+        for j in 0..i {
+            bartlett[[i, j]] = rng.sample(StandardNormal);
+        }
+    }
+
+    let factor = root.dot(&bartlett);
+    inverse_spd(&factor.dot(&factor.t()))
+}
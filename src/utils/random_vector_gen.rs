@@ -1,11 +1,18 @@
-use ndarray::Array2;
+use linfa_linalg::cholesky::Cholesky;
+use ndarray::{Array1, Array2, Axis};
 use ndarray_rand::RandomExt;
-use ndarray_rand::rand_distr::Distribution;
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand::{thread_rng, Rng, SeedableRng};
+use ndarray_rand::rand_distr::{ChiSquared, Distribution, Exp1, Gamma, StandardNormal};
+use statrs::distribution::{ContinuousCDF, Normal, StudentsT};
+use std::f64::consts::PI;
 
 /// Generates a random vector of length `n` using a specified 1D distribution.
 ///
 /// This function uses the `ndarray_rand` crate to generate a 1-dimensional random vector
 /// of length `n` from any distribution that implements the `Distribution<f64>` trait.
+/// The values are drawn from `rng`, so a generator created by `seeded_rng` gives the same
+/// vector on every run.
 ///
 /// # Parameters
 /// 
@@ -13,6 +20,7 @@ use ndarray_rand::rand_distr::Distribution;
 /// - `dist`: The distribution used to generate the random values. This must implement the
 ///   `Distribution<f64>` trait, which allows for various random distributions (e.g., uniform,
 ///   normal) from the `ndarray_rand` crate.
+/// - `rng`: The random number generator the values are drawn from.
 ///
 /// # Returns
 /// 
@@ -24,17 +32,318 @@ use ndarray_rand::rand_distr::Distribution;
 /// ```rust
 /// use ndarray::Array2;
 /// use ndarray_rand::rand_distr::Uniform;
-/// use ndarray_rand::RandomExt;
-/// use marketing_iv_methods::utils::random_vector_gen::random_vector_generate;
+/// use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
 /// 
 /// let n = 10;
 /// let dist = Uniform::new(0.0, 1.0);
-/// let random_vector = random_vector_generate(n, dist);
+/// let random_vector = random_vector_generate(n, dist, &mut seeded_rng(42));
+/// assert_eq!(random_vector, random_vector_generate(n, dist, &mut seeded_rng(42)));
 /// println!("{:?}", random_vector);
 /// ```
-pub fn random_vector_generate<D>(n: usize, dist: D) -> Array2<f64>
+pub fn random_vector_generate<D, R>(n: usize, dist: D, rng: &mut R) -> Array2<f64>
 where
-    D: Distribution<f64> + 'static,
+    D: Distribution<f64>,
+    R: Rng + ?Sized,
 {
-    Array2::random((n,1), dist)
+    Array2::random_using((n,1), dist, rng)
+}
+
+/// Generates `n` draws from a multivariate normal distribution.
+///
+/// Each row of the result is an independent draw from `N(mean, covariance)`. The draws are
+/// built as `mean + L u`, where `L` is the lower Cholesky factor of `covariance` and `u` is a
+/// vector of independent standard normals from `rng`, so the columns are correlated as
+/// `covariance` prescribes rather than drawn one at a time.
+///
+/// # Parameters
+///
+/// - `n`: The number of draws, i.e. the number of rows of the result.
+/// - `mean`: The mean vector, one entry per column.
+/// - `covariance`: The covariance matrix, which must be symmetric and positive definite.
+/// - `rng`: The random number generator the standard normals are drawn from.
+///
+/// # Returns
+///
+/// - `Ok(Array2<f64>)` of shape `(n, mean.len())` holding the draws.
+/// - `Err(String)` if `covariance` does not match `mean`, is not symmetric, or is not positive
+///   definite.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::{array, Array1};
+/// use marketing_iv_methods::utils::random_vector_gen::{multivariate_normal_generate, seeded_rng};
+///
+/// let covariance = array![[1.0, 0.5], [0.5, 2.0]];
+/// let draws = multivariate_normal_generate(1000, &Array1::zeros(2), &covariance, &mut seeded_rng(42)).unwrap();
+/// assert_eq!(draws.shape(), &[1000, 2]);
+/// ```
+pub fn multivariate_normal_generate<R>(
+    n: usize,
+    mean: &Array1<f64>,
+    covariance: &Array2<f64>,
+    rng: &mut R,
+) -> Result<Array2<f64>, String>
+where
+    R: Rng + ?Sized,
+{
+    let k = mean.len();
+    if covariance.dim() != (k, k) {
+        return Err(format!("The covariance must be {} by {} to match the mean", k, k));
+    }
+    let scale = covariance.iter().fold(0.0_f64, |m, c| m.max(c.abs()));
+    if (covariance - &covariance.t()).iter().any(|d| d.abs() > 1e-12 * scale.max(1.0)) {
+        return Err("The covariance must be symmetric".into());
+    }
+    let lower = covariance.cholesky().map_err(|_| "The covariance must be positive definite".to_string())?;
+
+    let standard: Array2<f64> = Array2::random_using((n, k), StandardNormal, rng);
+    Ok(standard.dot(&lower.t()) + mean.view().insert_axis(Axis(0)))
+}
+
+/// A copula, the dependence structure of a random vector stripped of its marginals.
+///
+/// - `Gaussian { correlation }`: The dependence of a multivariate normal with the given
+///   correlation matrix. It has no tail dependence, so extreme values are close to independent.
+/// - `StudentT { correlation, df }`: The dependence of a multivariate Student's t with the given
+///   correlation matrix and `df` degrees of freedom, whose joint extremes are more frequent than
+///   the Gaussian's, in both tails.
+/// - `Clayton { theta }`: The exchangeable Clayton copula with `theta > 0`, with lower-tail
+///   dependence: the variables crash together more often than they boom together. Kendall's tau
+///   is `theta / (theta + 2)`.
+/// - `Gumbel { theta }`: The exchangeable Gumbel copula with `theta >= 1`, with upper-tail
+///   dependence. Kendall's tau is `1 - 1 / theta`, and `theta = 1` is independence.
+///
+/// The correlation matrices fix the number of columns; Clayton and Gumbel give the same
+/// dependence to every pair, for any number of columns.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray::array;
+/// use marketing_iv_methods::utils::random_vector_gen::Copula;
+///
+/// let gaussian = Copula::Gaussian { correlation: array![[1.0, 0.5], [0.5, 1.0]] };
+/// assert!(gaussian.validate(2).is_ok());
+/// assert!(Copula::Clayton { theta: 0.0 }.validate(2).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Copula {
+    Gaussian { correlation: Array2<f64> },
+    StudentT { correlation: Array2<f64>, df: f64 },
+    Clayton { theta: f64 },
+    Gumbel { theta: f64 },
+}
+
+impl Copula {
+    /// Checks the parameters of the copula for `dimension` columns.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the copula is valid.
+    /// * `Err(String)` if a correlation matrix is not `dimension` by `dimension`, symmetric,
+    ///   positive definite and with a unit diagonal, `df` is not positive, or `theta` is out of
+    ///   range.
+    pub fn validate(&self, dimension: usize) -> Result<(), String> {
+        match self {
+            Copula::Gaussian { correlation } | Copula::StudentT { correlation, .. } => {
+                if correlation.dim() != (dimension, dimension) {
+                    return Err(format!("The copula correlation must be {} by {}", dimension, dimension));
+                }
+                if correlation.diag().iter().any(|d| (d - 1.0).abs() > 1e-12) {
+                    return Err("The copula correlation must have a unit diagonal".into());
+                }
+                if (correlation - &correlation.t()).iter().any(|d| d.abs() > 1e-12) {
+                    return Err("The copula correlation must be symmetric".into());
+                }
+                if correlation.cholesky().is_err() {
+                    return Err("The copula correlation must be positive definite".into());
+                }
+                if let Copula::StudentT { df, .. } = self {
+                    if !(*df > 0.0 && df.is_finite()) {
+                        return Err(format!("The degrees of freedom of the t copula must be positive and finite, got {}", df));
+                    }
+                }
+                Ok(())
+            }
+            Copula::Clayton { theta } => {
+                if *theta > 0.0 && theta.is_finite() {
+                    Ok(())
+                } else {
+                    Err(format!("The Clayton theta must be positive and finite, got {}", theta))
+                }
+            }
+            Copula::Gumbel { theta } => {
+                if *theta >= 1.0 && theta.is_finite() {
+                    Ok(())
+                } else {
+                    Err(format!("The Gumbel theta must be at least 1 and finite, got {}", theta))
+                }
+            }
+        }
+    }
+
+    /// Draws `n` rows of `dimension` dependent uniforms.
+    fn uniforms<R: Rng + ?Sized>(&self, n: usize, dimension: usize, rng: &mut R) -> Result<Array2<f64>, String> {
+        match self {
+            Copula::Gaussian { correlation } => {
+                let normal = Normal::new(0.0, 1.0).map_err(|e| format!("Invalid normal distribution: {}", e))?;
+                let z = multivariate_normal_generate(n, &Array1::zeros(dimension), correlation, rng)?;
+                Ok(z.mapv(|value| normal.cdf(value)))
+            }
+            Copula::StudentT { correlation, df } => {
+                // a multivariate normal divided by one common sqrt(chi2 / df) per row
+                let t = StudentsT::new(0.0, 1.0, *df).map_err(|e| format!("Invalid Student's t: {}", e))?;
+                let chi2 = ChiSquared::new(*df).map_err(|e| format!("Invalid chi-squared distribution: {}", e))?;
+                let mut z = multivariate_normal_generate(n, &Array1::zeros(dimension), correlation, rng)?;
+                for mut row in z.rows_mut() {
+                    let mixing = (chi2.sample(rng) / df).sqrt();
+                    row.mapv_inplace(|value| t.cdf(value / mixing));
+                }
+                Ok(z)
+            }
+            Copula::Clayton { theta } => {
+                // Marshall-Olkin: u = psi(e / w), with psi(s) = (1 + s)^(-1 / theta) the Laplace
+                // transform of the gamma frailty w
+                let frailty = Gamma::new(1.0 / theta, 1.0).map_err(|e| format!("Invalid gamma distribution: {}", e))?;
+                Ok(frailty_uniforms(n, dimension, rng, |rng| frailty.sample(rng), |s| (1.0 + s).powf(-1.0 / theta)))
+            }
+            Copula::Gumbel { theta } => {
+                // Marshall-Olkin with a positive stable frailty, whose Laplace transform is
+                // exp(-s^alpha), drawn by the Chambers-Mallows-Stuck method
+                let alpha = 1.0 / theta;
+                let stable = |rng: &mut R| {
+                    let angle = PI * rng.gen::<f64>();
+                    let exponential: f64 = rng.sample(Exp1);
+                    (alpha * angle).sin() / angle.sin().powf(1.0 / alpha)
+                        * (((1.0 - alpha) * angle).sin() / exponential).powf((1.0 - alpha) / alpha)
+                };
+                Ok(frailty_uniforms(n, dimension, rng, stable, |s| (-s.powf(alpha)).exp()))
+            }
+        }
+    }
+}
+
+/// Draws uniforms from an Archimedean copula with generator `psi`, given a sampler of the
+/// frailty whose Laplace transform is `psi`.
+fn frailty_uniforms<R, W, P>(n: usize, dimension: usize, rng: &mut R, mut frailty: W, psi: P) -> Array2<f64>
+where
+    R: Rng + ?Sized,
+    W: FnMut(&mut R) -> f64,
+    P: Fn(f64) -> f64,
+{
+    let mut uniforms = Array2::zeros((n, dimension));
+    for mut row in uniforms.rows_mut() {
+        let w = frailty(rng);
+        for value in row.iter_mut() {
+            let exponential: f64 = rng.sample(Exp1);
+            *value = psi(exponential / w);
+        }
+    }
+    uniforms
+}
+
+/// Generates `n` draws of a random vector with the dependence of `copula` and the given
+/// marginals.
+///
+/// The copula gives one row of dependent uniforms per draw, and column `j` is then mapped
+/// through `marginals[j]`, the quantile function of its marginal distribution. Any continuous
+/// marginal can be used, from a statrs `inverse_cdf` to the `quantile` of a standardised
+/// `ErrorDistribution`, and the dependence, including its tail behaviour, carries over
+/// whatever the marginals. The uniforms are kept within `[1e-12, 1 - 1e-12]`, so that
+/// quantile functions with unbounded support stay finite.
+///
+/// # Parameters
+///
+/// - `n`: The number of draws, i.e. the number of rows of the result.
+/// - `copula`: The `Copula` of the columns.
+/// - `marginals`: The quantile functions of the columns, one per column.
+/// - `rng`: The random number generator the draws are taken from.
+///
+/// # Returns
+///
+/// - `Ok(Array2<f64>)` of shape `(n, marginals.len())` holding the draws.
+/// - `Err(String)` if `marginals` is empty or `copula` is invalid for `marginals.len()` columns.
+///
+/// # Example
+///
+/// ```rust
+/// use statrs::distribution::{ContinuousCDF, Exp, Normal};
+/// use marketing_iv_methods::utils::random_vector_gen::{copula_generate, seeded_rng, Copula};
+///
+/// let normal = Normal::new(0.0, 1.0).unwrap();
+/// let exponential = Exp::new(2.0).unwrap();
+/// let marginals: [&dyn Fn(f64) -> f64; 2] = [&|u| normal.inverse_cdf(u), &|u| exponential.inverse_cdf(u)];
+/// let draws = copula_generate(500, &Copula::Clayton { theta: 2.0 }, &marginals, &mut seeded_rng(42)).unwrap();
+/// assert_eq!(draws.shape(), &[500, 2]);
+/// assert!(draws.column(1).iter().all(|value| *value >= 0.0));
+/// ```
+pub fn copula_generate<R>(
+    n: usize,
+    copula: &Copula,
+    marginals: &[&dyn Fn(f64) -> f64],
+    rng: &mut R,
+) -> Result<Array2<f64>, String>
+where
+    R: Rng + ?Sized,
+{
+    let dimension = marginals.len();
+    if dimension == 0 {
+        return Err("At least one marginal is required".into());
+    }
+    copula.validate(dimension)?;
+
+    let mut draws = copula.uniforms(n, dimension, rng)?;
+    for (mut column, quantile) in draws.columns_mut().into_iter().zip(marginals) {
+        column.mapv_inplace(|u| quantile(u.clamp(1e-12, 1.0 - 1e-12)));
+    }
+    Ok(draws)
+}
+
+/// Creates the random number generator used for reproducible simulations.
+///
+/// The same `seed` always gives the same stream of numbers, so any simulation driven by the
+/// returned generator produces bit-identical data when it is run again with the same seed and
+/// the same versions of the dependencies.
+///
+/// # Parameters
+///
+/// - `seed`: The seed of the generator.
+///
+/// # Returns
+///
+/// - A `StdRng` seeded with `seed`.
+///
+/// # Example
+///
+/// ```rust
+/// use ndarray_rand::rand::Rng;
+/// use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+///
+/// let first: f64 = seeded_rng(7).gen();
+/// let second: f64 = seeded_rng(7).gen();
+/// assert_eq!(first, second);
+/// ```
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// Draws a fresh seed from the operating system's entropy, to be recorded alongside a run that
+/// was not given one.
+///
+/// # Returns
+///
+/// - A random `u64` seed for `seeded_rng`.
+///
+/// # Example
+///
+/// ```rust
+/// use marketing_iv_methods::utils::random_vector_gen::{fresh_seed, seeded_rng};
+///
+/// let seed = fresh_seed();
+/// println!("Seed: {}", seed);
+/// let mut rng = seeded_rng(seed);
+/// ```
+pub fn fresh_seed() -> u64 {
+    thread_rng().gen()
 }
@@ -0,0 +1,421 @@
+use super::bayesian_iv::{RivDpDraws, RivGibbsDraws};
+use super::bootstrap::{BootstrapResult, BootstrapScheme};
+use super::control_function::FittedControlFunction;
+use super::gmm::FittedGmm;
+use super::jive::FittedJive;
+use super::linear_regression::{t_critical_value, two_sided_t_p_value, FittedIvRegression, FittedOls};
+use ndarray::{Array1, Array2, ArrayView1};
+use statrs::distribution::{ContinuousCDF, Normal};
+use std::fmt;
+
+// width of the report, as in statsmodels
+const SUMMARY_WIDTH: usize = 78;
+const SUMMARY_CONFIDENCE: f64 = 0.95;
+
+#[derive(Debug, Clone)]
+struct SummaryRow {
+    name: String,
+    estimate: f64,
+    std_error: Option<f64>,
+    statistic: Option<f64>,
+    p_value: Option<f64>,
+    interval: Option<(f64, f64)>,
+}
+
+/// A statsmodels-style table of estimates, as returned by `Summarize::summary`.
+///
+/// The report has three parts:
+/// - A title and a two-column block of fit statistics, such as the dependent variable, the
+///   number of observations or the covariance type.
+/// - One row per coefficient with the estimate, its standard error, the test statistic against
+///   zero, its two-sided p-value and a 95% interval. The test uses the t distribution with
+///   `df_resid` degrees of freedom when it is known and the standard normal otherwise, and the
+///   columns are headed `t` or `z` accordingly.
+/// - A closing rule.
+///
+/// Estimators that report no standard errors, such as JIVE or GMM, show `-` in the inference
+/// columns. Posterior and bootstrap summaries show the spread of the draws as the standard
+/// error and a percentile interval, without a test statistic.
+///
+/// # Example
+///
+/// ```
+/// use marketing_iv_methods::utils::summary::RegressionSummary;
+///
+/// let mut summary = RegressionSummary::new("OLS Regression Results", Some(98));
+/// summary.add_statistic("Dep. Variable", "y");
+/// summary.add_coefficient("x", -0.48, Some(0.02)).unwrap();
+/// println!("{}", summary);
+/// assert_eq!(summary.names(), vec!["x"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegressionSummary {
+    title: String,
+    df_resid: Option<usize>,
+    rows: Vec<SummaryRow>,
+    statistics: Vec<(String, String)>,
+}
+
+impl RegressionSummary {
+    /// Starts an empty summary. With `df_resid` the tests use the t distribution, without it the
+    /// standard normal.
+    pub fn new(title: &str, df_resid: Option<usize>) -> Self {
+        RegressionSummary { title: title.to_string(), df_resid, rows: Vec::new(), statistics: Vec::new() }
+    }
+
+    /// Adds a fit statistic to the header block.
+    pub fn add_statistic<T: fmt::Display>(&mut self, label: &str, value: T) {
+        self.statistics.push((label.to_string(), value.to_string()));
+    }
+
+    /// Adds a coefficient, with the test against zero and the 95% interval derived from its
+    /// standard error when there is one.
+    pub fn add_coefficient(&mut self, name: &str, estimate: f64, std_error: Option<f64>) -> Result<(), String> {
+        let (statistic, p_value, interval) = match std_error {
+            Some(se) => {
+                let statistic = estimate / se;
+                let (p_value, critical) = match self.df_resid {
+                    Some(df) => (two_sided_t_p_value(statistic, df), t_critical_value(SUMMARY_CONFIDENCE, df)?),
+                    None => {
+                        let normal = Normal::new(0.0, 1.0).map_err(|e| format!("Invalid normal distribution: {}", e))?;
+                        (2.0 * (1.0 - normal.cdf(statistic.abs())), normal.inverse_cdf(0.5 + SUMMARY_CONFIDENCE / 2.0))
+                    }
+                };
+                (Some(statistic), Some(p_value), Some((estimate - critical * se, estimate + critical * se)))
+            }
+            None => (None, None, None),
+        };
+        self.rows.push(SummaryRow { name: name.to_string(), estimate, std_error, statistic, p_value, interval });
+        Ok(())
+    }
+
+    /// Adds a coefficient summarised by draws, with their standard deviation and a 95% interval
+    /// but no test statistic.
+    pub fn add_interval_coefficient(&mut self, name: &str, estimate: f64, spread: Option<f64>, interval: Option<(f64, f64)>) {
+        self.rows.push(SummaryRow {
+            name: name.to_string(),
+            estimate,
+            std_error: spread,
+            statistic: None,
+            p_value: None,
+            interval,
+        });
+    }
+
+    /// The title of the report.
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The coefficient names, in the order of the table.
+    pub fn names(&self) -> Vec<&str> {
+        self.rows.iter().map(|row| row.name.as_str()).collect()
+    }
+
+    /// The estimates, in the order of the table.
+    pub fn params(&self) -> Array1<f64> {
+        self.rows.iter().map(|row| row.estimate).collect()
+    }
+
+    /// The standard errors, `None` where the estimator reports none.
+    pub fn std_errors(&self) -> Vec<Option<f64>> {
+        self.rows.iter().map(|row| row.std_error).collect()
+    }
+
+    /// The two-sided p-values of the tests against zero, `None` where there is no test.
+    pub fn p_values(&self) -> Vec<Option<f64>> {
+        self.rows.iter().map(|row| row.p_value).collect()
+    }
+
+    /// The 95% intervals, `None` where there is no interval.
+    pub fn intervals(&self) -> Vec<Option<(f64, f64)>> {
+        self.rows.iter().map(|row| row.interval).collect()
+    }
+
+    /// The fit statistics of the header block, as label and formatted value.
+    pub fn statistics(&self) -> &[(String, String)] {
+        &self.statistics
+    }
+}
+
+fn format_cell(value: Option<f64>, precision: usize) -> String {
+    match value {
+        Some(value) if value.is_finite() => format!("{:>11.*}", precision, value),
+        Some(value) => format!("{:>11}", value),
+        None => format!("{:>11}", "-"),
+    }
+}
+
+impl fmt::Display for RegressionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name_width = self.rows.iter().map(|row| row.name.len() + 1).max().unwrap_or(0).max(SUMMARY_WIDTH - 66);
+        let width = name_width + 66;
+        let column_width = (width - 4) / 2;
+
+        writeln!(f, "{}", "=".repeat(width))?;
+        writeln!(f, "{}", format!("{:^width$}", self.title, width = width).trim_end())?;
+        writeln!(f, "{}", "=".repeat(width))?;
+        for pair in self.statistics.chunks(2) {
+            let cells: Vec<String> = pair
+                .iter()
+                .map(|(label, value)| {
+                    let label = format!("{}:", label);
+                    format!("{}{:>pad$}", label, value, pad = column_width.saturating_sub(label.len()))
+                })
+                .collect();
+            writeln!(f, "{}", cells.join("    ").trim_end())?;
+        }
+
+        let (statistic, p_value) = if self.df_resid.is_some() { ("t", "P>|t|") } else { ("z", "P>|z|") };
+        let tail = (1.0 - SUMMARY_CONFIDENCE) / 2.0;
+        writeln!(f, "{}", "=".repeat(width))?;
+        writeln!(
+            f,
+            "{:<name_width$}{:>11}{:>11}{:>11}{:>11}{:>11}{:>11}",
+            "",
+            "coef",
+            "std err",
+            statistic,
+            p_value,
+            format!("[{:.3}", tail),
+            format!("{:.3}]", 1.0 - tail),
+            name_width = name_width
+        )?;
+        writeln!(f, "{}", "-".repeat(width))?;
+        for row in &self.rows {
+            writeln!(
+                f,
+                "{:<name_width$}{}{}{}{}{}{}",
+                row.name,
+                format_cell(Some(row.estimate), 4),
+                format_cell(row.std_error, 4),
+                format_cell(row.statistic, 3),
+                format_cell(row.p_value, 3),
+                format_cell(row.interval.map(|(lower, _)| lower), 4),
+                format_cell(row.interval.map(|(_, upper)| upper), 4),
+                name_width = name_width
+            )?;
+        }
+        writeln!(f, "{}", "=".repeat(width))
+    }
+}
+
+/// Renders a fitted model as a `RegressionSummary`.
+///
+/// `names` labels the slope coefficients in the order the estimator reports them: endogenous
+/// regressors first, then exogenous controls. The intercept is added as `const` in the first row
+/// when the model was fit with one, i.e. when the fit reports its standard error or, for JIVE,
+/// which reports none, when `has_intercept` is `true`.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::covariance::CovarianceType;
+/// use marketing_iv_methods::utils::linear_regression::run_regression;
+/// use marketing_iv_methods::utils::summary::Summarize;
+///
+/// let x = Array2::from_shape_vec((6, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+/// let y = Array2::from_shape_vec((6, 1), vec![2.1, 3.9, 6.2, 7.8, 10.1, 12.0]).unwrap();
+/// let fit = run_regression(&x, &y, &CovarianceType::Classical, true).unwrap();
+/// let summary = fit.summary("y", &["x"]).unwrap();
+/// assert_eq!(summary.names(), vec!["const", "x"]);
+/// println!("{}", summary);
+/// ```
+pub trait Summarize {
+    /// Builds the summary, naming the dependent variable `dependent` and the slopes `names`.
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String>;
+}
+
+fn check_names(names: &[&str], expected: usize) -> Result<(), String> {
+    if names.len() != expected {
+        return Err(format!("Expected {} coefficient names, got {}", expected, names.len()));
+    }
+    Ok(())
+}
+
+fn add_coefficients(
+    summary: &mut RegressionSummary,
+    names: &[&str],
+    params: &Array1<f64>,
+    std_errors: Option<&Array1<f64>>,
+) -> Result<(), String> {
+    for (i, name) in names.iter().enumerate() {
+        summary.add_coefficient(name, params[i], std_errors.map(|se| se[i]))?;
+    }
+    Ok(())
+}
+
+/// The mean, standard deviation and 95% percentile interval of a set of draws.
+fn describe_draws(draws: ArrayView1<f64>) -> (f64, f64, (f64, f64)) {
+    let mut sorted = draws.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let tail = (1.0 - SUMMARY_CONFIDENCE) / 2.0;
+    let quantile = |p: f64| sorted[((p * sorted.len() as f64) as usize).min(sorted.len() - 1)];
+    (draws.mean().unwrap_or(f64::NAN), draws.std(1.0), (quantile(tail), quantile(1.0 - tail)))
+}
+
+impl Summarize for FittedOls {
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String> {
+        check_names(names, self.params().len())?;
+        let n_obs = self.df_resid() + self.df_model() + self.intercept_std_error().is_some() as usize;
+        let mut summary = RegressionSummary::new("OLS Regression Results", Some(self.df_resid()));
+        summary.add_statistic("Dep. Variable", dependent);
+        summary.add_statistic("R-squared", format!("{:.4}", self.r_squared()));
+        summary.add_statistic("Model", "OLS");
+        summary.add_statistic("Adj. R-squared", format!("{:.4}", self.adj_r_squared()));
+        summary.add_statistic("No. Observations", n_obs);
+        summary.add_statistic("Df Residuals", self.df_resid());
+        summary.add_statistic("Covariance Type", self.covariance_type());
+        summary.add_statistic("Df Model", self.df_model());
+        if let Some(se) = self.intercept_std_error() {
+            summary.add_coefficient("const", self.intercept(), Some(se))?;
+        }
+        add_coefficients(&mut summary, names, self.params(), Some(self.std_errors()))?;
+        Ok(summary)
+    }
+}
+
+impl Summarize for FittedIvRegression {
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String> {
+        check_names(names, self.params().len())?;
+        let model = if self.k() == 1.0 { "2SLS" } else { "k-class" };
+        let mut summary = RegressionSummary::new("IV Regression Results", None);
+        summary.add_statistic("Dep. Variable", dependent);
+        summary.add_statistic("Model", model);
+        summary.add_statistic("k", format!("{:.4}", self.k()));
+        summary.add_statistic("Covariance Type", self.covariance_type());
+        if let Some(se) = self.intercept_std_error() {
+            summary.add_coefficient("const", self.intercept(), Some(se))?;
+        }
+        add_coefficients(&mut summary, names, self.params(), Some(self.std_errors()))?;
+        Ok(summary)
+    }
+}
+
+impl Summarize for FittedGmm {
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String> {
+        check_names(names, self.params().len())?;
+        let mut summary = RegressionSummary::new("GMM Regression Results", None);
+        summary.add_statistic("Dep. Variable", dependent);
+        summary.add_statistic("J-statistic", format!("{:.4}", self.j_statistic()));
+        summary.add_statistic("Model", "GMM");
+        summary.add_statistic("Prob (J-statistic)", format!("{:.4}", self.j_p_value()));
+        summary.add_statistic("Weight Updates", self.iterations());
+        summary.add_statistic("J df", self.j_df());
+        summary.add_statistic("Covariance Type", self.covariance_type());
+        if let Some(se) = self.intercept_std_error() {
+            summary.add_coefficient("const", self.intercept(), Some(se))?;
+        }
+        add_coefficients(&mut summary, names, self.params(), Some(self.std_errors()))?;
+        Ok(summary)
+    }
+}
+
+impl Summarize for FittedJive {
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String> {
+        check_names(names, self.params().len())?;
+        let mut summary = RegressionSummary::new("JIVE Regression Results", None);
+        summary.add_statistic("Dep. Variable", dependent);
+        summary.add_statistic("Model", format!("{:?}", self.variant()));
+        if self.has_intercept() {
+            summary.add_coefficient("const", self.intercept(), None)?;
+        }
+        add_coefficients(&mut summary, names, self.params(), None)?;
+        Ok(summary)
+    }
+}
+
+impl Summarize for FittedControlFunction {
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String> {
+        check_names(names, self.params().len())?;
+        let mut summary = RegressionSummary::new("Control Function Regression Results", None);
+        summary.add_statistic("Dep. Variable", dependent);
+        summary.add_statistic("Endogeneity Wald", format!("{:.4}", self.endogeneity_statistic()));
+        summary.add_statistic("Model", "Control function");
+        summary.add_statistic("Prob (Wald)", format!("{:.4}", self.endogeneity_p_value()));
+        summary.add_statistic("Covariance Type", self.covariance_type());
+        if let Some(se) = self.intercept_std_error() {
+            summary.add_coefficient("const", self.intercept(), Some(se))?;
+        }
+        add_coefficients(&mut summary, names, self.params(), Some(self.std_errors()))?;
+        // one first-stage residual per endogenous regressor, named after it
+        for (j, name) in names.iter().take(self.residual_params().len()).enumerate() {
+            let label = format!("{}_resid", name);
+            summary.add_coefficient(&label, self.residual_params()[j], Some(self.residual_std_errors()[j]))?;
+        }
+        Ok(summary)
+    }
+}
+
+impl Summarize for BootstrapResult {
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String> {
+        check_names(names, self.estimate().len())?;
+        let mut summary = RegressionSummary::new("Bootstrap Results", None);
+        summary.add_statistic("Dep. Variable", dependent);
+        summary.add_statistic("Replications", self.draws().nrows());
+        summary.add_statistic("Scheme", format!("{:?}", self.scheme()));
+        summary.add_statistic("Interval", "percentile");
+        // the restricted draws are centred on the null, so they give no interval for the estimate
+        let intervals = match self.scheme() {
+            BootstrapScheme::WildRestrictedEfficient { .. } => None,
+            _ => Some(self.percentile_interval(SUMMARY_CONFIDENCE)?),
+        };
+        let std_errors = self.bootstrap_std_errors();
+        for (i, name) in names.iter().enumerate() {
+            let interval = intervals.as_ref().map(|ci| (ci[[i, 0]], ci[[i, 1]]));
+            summary.add_interval_coefficient(name, self.estimate()[i], Some(std_errors[i]), interval);
+        }
+        Ok(summary)
+    }
+}
+
+/// Adds the posterior rows of `beta` and `gamma`, whose last column is the intercept when it has
+/// one more column than there are exogenous controls.
+fn add_posterior_rows(
+    summary: &mut RegressionSummary,
+    names: &[&str],
+    beta: &Array1<f64>,
+    gamma: &Array2<f64>,
+) -> Result<(), String> {
+    if names.is_empty() || !(gamma.ncols() == names.len() - 1 || gamma.ncols() == names.len()) {
+        return Err(format!("Expected {} coefficient names, got {}", gamma.ncols() + 1, names.len()));
+    }
+    if gamma.ncols() == names.len() {
+        let (mean, sd, interval) = describe_draws(gamma.column(gamma.ncols() - 1));
+        summary.add_interval_coefficient("const", mean, Some(sd), Some(interval));
+    }
+    let (mean, sd, interval) = describe_draws(beta.view());
+    summary.add_interval_coefficient(names[0], mean, Some(sd), Some(interval));
+    for (j, name) in names.iter().skip(1).enumerate() {
+        let (mean, sd, interval) = describe_draws(gamma.column(j));
+        summary.add_interval_coefficient(name, mean, Some(sd), Some(interval));
+    }
+    Ok(())
+}
+
+impl Summarize for RivGibbsDraws {
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String> {
+        let mut summary = RegressionSummary::new("Bayesian IV Posterior Summary", None);
+        summary.add_statistic("Dep. Variable", dependent);
+        summary.add_statistic("Posterior Draws", self.beta.len());
+        summary.add_statistic("Model", "rivGibbs");
+        summary.add_statistic("Interval", "95% credible");
+        add_posterior_rows(&mut summary, names, &self.beta, &self.gamma)?;
+        Ok(summary)
+    }
+}
+
+impl Summarize for RivDpDraws {
+    fn summary(&self, dependent: &str, names: &[&str]) -> Result<RegressionSummary, String> {
+        let mean_components = self.n_components.mapv(|k| k as f64).mean().unwrap_or(f64::NAN);
+        let mut summary = RegressionSummary::new("Bayesian IV Posterior Summary", None);
+        summary.add_statistic("Dep. Variable", dependent);
+        summary.add_statistic("Posterior Draws", self.beta.len());
+        summary.add_statistic("Model", "rivDP");
+        summary.add_statistic("Mean Components", format!("{:.2}", mean_components));
+        summary.add_statistic("Interval", "95% credible");
+        add_posterior_rows(&mut summary, names, &self.beta, &self.gamma)?;
+        Ok(summary)
+    }
+}
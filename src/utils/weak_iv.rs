@@ -0,0 +1,591 @@
+use super::linear_regression::iv_design;
+use super::matrix_ops::{inverse_spd, residualize};
+use ndarray::{Array1, Array2};
+use statrs::distribution::{ChiSquared, ContinuousCDF, FisherSnedecor};
+use statrs::function::gamma::ln_gamma;
+use std::fmt;
+
+/// The result of a weak-instrument-robust test of `beta = beta_0`, as returned by
+/// `run_anderson_rubin` and `run_clr`.
+///
+/// This structure holds:
+/// - `statistic`: The value of the test statistic at `beta_0`.
+/// - `p_value`: The p-value of the test, which is valid however weak the instruments are.
+#[derive(Debug, Clone, Copy)]
+pub struct WeakIvTest {
+    statistic: f64,
+    p_value: f64,
+}
+
+impl WeakIvTest {
+    /// The value of the test statistic.
+    pub fn statistic(&self) -> f64 {
+        self.statistic
+    }
+
+    /// The p-value of the test.
+    pub fn p_value(&self) -> f64 {
+        self.p_value
+    }
+}
+
+/// A confidence set for a scalar coefficient, stored as a union of disjoint closed intervals.
+///
+/// Sets obtained by inverting weak-instrument-robust tests need not be a single bounded
+/// interval: they may be empty, a union of two disjoint rays, or the whole real line. The
+/// intervals are sorted, do not overlap, and use `f64::NEG_INFINITY` and `f64::INFINITY` for
+/// unbounded ends.
+///
+/// # Example
+///
+/// ```
+/// use marketing_iv_methods::utils::weak_iv::ConfidenceSet;
+///
+/// let set = ConfidenceSet::new(vec![(1.0, f64::INFINITY), (f64::NEG_INFINITY, -1.0)]);
+/// assert!(!set.is_bounded());
+/// assert!(set.contains(2.0) && !set.contains(0.0));
+/// assert_eq!(set.to_string(), "(-inf, -1.0000] U [1.0000, inf)");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfidenceSet {
+    intervals: Vec<(f64, f64)>,
+}
+
+impl ConfidenceSet {
+    /// Builds a confidence set from a list of intervals, sorting them and merging any that
+    /// overlap. Intervals whose lower end exceeds their upper end are dropped.
+    pub fn new(intervals: Vec<(f64, f64)>) -> Self {
+        let mut intervals: Vec<(f64, f64)> = intervals.into_iter().filter(|(lo, hi)| lo <= hi).collect();
+        intervals.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        let mut merged: Vec<(f64, f64)> = Vec::with_capacity(intervals.len());
+        for (lo, hi) in intervals {
+            match merged.last_mut() {
+                Some(last) if lo <= last.1 => last.1 = last.1.max(hi),
+                _ => merged.push((lo, hi)),
+            }
+        }
+        ConfidenceSet { intervals: merged }
+    }
+
+    /// The disjoint intervals making up the set, in increasing order.
+    pub fn intervals(&self) -> &[(f64, f64)] {
+        &self.intervals
+    }
+
+    /// Whether the set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Whether the set is bounded, i.e. contains no ray. The empty set is bounded.
+    pub fn is_bounded(&self) -> bool {
+        self.intervals.iter().all(|(lo, hi)| lo.is_finite() && hi.is_finite())
+    }
+
+    /// Whether `value` lies in the set.
+    pub fn contains(&self, value: f64) -> bool {
+        self.intervals.iter().any(|(lo, hi)| *lo <= value && value <= *hi)
+    }
+}
+
+impl fmt::Display for ConfidenceSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.intervals.is_empty() {
+            return write!(f, "empty set");
+        }
+        let pieces: Vec<String> = self
+            .intervals
+            .iter()
+            .map(|(lo, hi)| {
+                let left = if lo.is_finite() { format!("[{:.4}", lo) } else { "(-inf".to_string() };
+                let right = if hi.is_finite() { format!("{:.4}]", hi) } else { "inf)".to_string() };
+                format!("{}, {}", left, right)
+            })
+            .collect();
+        write!(f, "{}", pieces.join(" U "))
+    }
+}
+
+/// The sufficient statistics of the reduced form `[y, x] = z * Pi + v`, after partialling out the
+/// exogenous controls.
+struct ReducedForm {
+    // [y, x]' P_z [y, x], with z the partialled-out instruments
+    projected: Array2<f64>,
+    // the reduced-form error covariance [y, x]' M [y, x] / (n - k - p)
+    omega: Array2<f64>,
+    omega_inv: Array2<f64>,
+    n_instruments: usize,
+    df: usize,
+}
+
+/// The statistics `Q_S`, `Q_T` and `Q_ST` of Moreira (2003) for the null direction `b` and its
+/// dual `a`.
+struct Invariants {
+    qs: f64,
+    qt: f64,
+    qst: f64,
+}
+
+impl ReducedForm {
+    fn new(
+        y: &Array2<f64>,
+        x_endog: &Array2<f64>,
+        x_exog: &Array2<f64>,
+        z: &Array2<f64>,
+        intercept: bool,
+    ) -> Result<ReducedForm, String> {
+        let design = iv_design(y, x_endog, x_exog, z, intercept)?;
+        if x_endog.ncols() != 1 {
+            return Err("Weak-instrument-robust inference requires exactly one endogenous regressor".into());
+        }
+        let n = y.nrows();
+        let n_instruments = z.ncols();
+        let n_exog = design.exog.ncols();
+        if n <= n_instruments + n_exog {
+            return Err("Weak-instrument-robust inference requires more observations than instruments".into());
+        }
+        let df = n - n_instruments - n_exog;
+
+        let mut yx = Array2::<f64>::zeros((n, 2));
+        yx.column_mut(0).assign(&y.column(0));
+        yx.column_mut(1).assign(&x_endog.column(0));
+        let yx = residualize(&design.exog, &yx)?;
+        let z = residualize(&design.exog, z)?;
+
+        let zy = z.t().dot(&yx);
+        let zz_inv = inverse_spd(&z.t().dot(&z)).map_err(|e| format!("Instrument cross-product is singular: {}", e))?;
+        let projected = zy.t().dot(&zz_inv).dot(&zy);
+        let omega = (yx.t().dot(&yx) - &projected) / df as f64;
+        let omega_inv = inverse_spd(&omega).map_err(|e| format!("Reduced-form error covariance is singular: {}", e))?;
+
+        Ok(ReducedForm { projected, omega, omega_inv, n_instruments, df })
+    }
+
+    /// Evaluates the invariants at `beta_0`, or in the limit `beta_0 -> +/- infinity` when
+    /// `beta_0` is not finite.
+    fn invariants(&self, beta_0: f64) -> Invariants {
+        let (b, a) = if beta_0.is_finite() {
+            (Array1::from(vec![1.0, -beta_0]), Array1::from(vec![beta_0, 1.0]))
+        } else {
+            (Array1::from(vec![0.0, 1.0]), Array1::from(vec![1.0, 0.0]))
+        };
+        let omega_inv_a = self.omega_inv.dot(&a);
+        let b_omega_b = b.dot(&self.omega.dot(&b));
+        let a_omega_inv_a = a.dot(&omega_inv_a);
+        Invariants {
+            qs: b.dot(&self.projected.dot(&b)) / b_omega_b,
+            qt: omega_inv_a.dot(&self.projected.dot(&omega_inv_a)) / a_omega_inv_a,
+            qst: b.dot(&self.projected.dot(&omega_inv_a)) / (b_omega_b * a_omega_inv_a).sqrt(),
+        }
+    }
+
+    fn anderson_rubin(&self, beta_0: f64) -> Result<WeakIvTest, String> {
+        let k = self.n_instruments as f64;
+        let statistic = self.invariants(beta_0).qs / k;
+        let f_dist = FisherSnedecor::new(k, self.df as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+        Ok(WeakIvTest { statistic, p_value: 1.0 - f_dist.cdf(statistic) })
+    }
+
+    fn clr(&self, beta_0: f64) -> Result<WeakIvTest, String> {
+        let Invariants { qs, qt, qst } = self.invariants(beta_0);
+        let discriminant = ((qs + qt).powi(2) - 4.0 * (qs * qt - qst * qst)).max(0.0);
+        let statistic = 0.5 * (qs - qt + discriminant.sqrt());
+        let p_value = clr_p_value(statistic, qt, self.n_instruments)?;
+        Ok(WeakIvTest { statistic, p_value })
+    }
+}
+
+/// The p-value of the CLR statistic `lr` conditional on `Q_T = qt`, with `k` instruments.
+///
+/// Uses the integral representation of Andrews, Moreira and Stock (2007),
+///
+/// `P(LR > lr | Q_T = qt) = 1 - 2 K ∫_0^1 F_k((qt + lr) / (1 + qt s^2 / lr)) (1 - s^2)^((k - 3) / 2) ds`,
+///
+/// with `F_k` the chi-squared CDF with `k` degrees of freedom and `K` the constant that makes the
+/// weight a density. Substituting `s = sin(t)` removes the endpoint singularity for `k = 2`, and
+/// the remaining smooth integral is evaluated with Simpson's rule.
+fn clr_p_value(lr: f64, qt: f64, k: usize) -> Result<f64, String> {
+    let chi2_k = ChiSquared::new(k as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+    if lr <= 0.0 {
+        return Ok(1.0);
+    }
+    if k == 1 {
+        // exactly identified: the CLR statistic is Q_S, which is chi-squared with one degree of freedom
+        return Ok(1.0 - chi2_k.cdf(lr));
+    }
+
+    let kf = k as f64;
+    let log_constant = ln_gamma(kf / 2.0) - 0.5 * std::f64::consts::PI.ln() - ln_gamma((kf - 1.0) / 2.0);
+    let integrand = |t: f64| {
+        let s = t.sin();
+        chi2_k.cdf((qt + lr) / (1.0 + qt * s * s / lr)) * t.cos().powi(k as i32 - 2)
+    };
+
+    const INTERVALS: usize = 200;
+    let h = std::f64::consts::FRAC_PI_2 / INTERVALS as f64;
+    let mut integral = integrand(0.0) + integrand(std::f64::consts::FRAC_PI_2);
+    for i in 1..INTERVALS {
+        let weight = if i % 2 == 1 { 4.0 } else { 2.0 };
+        integral += weight * integrand(i as f64 * h);
+    }
+    integral *= h / 3.0;
+
+    Ok((1.0 - 2.0 * log_constant.exp() * integral).clamp(0.0, 1.0))
+}
+
+/// Runs the Anderson-Rubin test of `beta = beta_0` for the coefficient on a single endogenous
+/// regressor.
+///
+/// Under the null, `y - x_endog * beta_0` is unrelated to the excluded instruments, so the test
+/// is the F-test of `z` in the regression of `y - x_endog * beta_0` on `[z, x_exog]`. Its size
+/// does not depend on the strength of the first stage, unlike the Wald test based on 2SLS.
+/// The errors are assumed homoskedastic.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) with a single column, the endogenous regressor.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `beta_0` - The value of the coefficient under the null.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(WeakIvTest)` with the F statistic and its p-value.
+/// * `Err(String)` if the inputs are invalid, there is not exactly one endogenous regressor, or a
+///   cross-product matrix is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::weak_iv::run_anderson_rubin;
+///
+/// let z = Array2::from_shape_vec((8, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let test = run_anderson_rubin(&y, &x, &Array2::zeros((8, 0)), &z, -0.5, true).unwrap();
+/// println!("AR = {}, p-value = {}", test.statistic(), test.p_value());
+/// ```
+pub fn run_anderson_rubin(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    beta_0: f64,
+    intercept: bool,
+) -> Result<WeakIvTest, String> {
+    ReducedForm::new(y, x_endog, x_exog, z, intercept)?.anderson_rubin(beta_0)
+}
+
+/// Runs Moreira's (2003) conditional likelihood ratio (CLR) test of `beta = beta_0` for the
+/// coefficient on a single endogenous regressor.
+///
+/// The likelihood ratio statistic is computed from the invariants `Q_S`, `Q_T` and `Q_ST` of the
+/// reduced form, and its critical value is taken from its distribution conditional on `Q_T`, the
+/// statistic that measures instrument strength. This makes the test valid however weak the
+/// instruments are, while it is close to the Wald test when they are strong. With a single
+/// instrument the statistic equals the Anderson-Rubin statistic. The reduced-form error
+/// covariance is estimated under homoskedasticity and the p-value uses the limiting chi-squared
+/// distributions.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) with a single column, the endogenous regressor.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `beta_0` - The value of the coefficient under the null.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(WeakIvTest)` with the likelihood ratio statistic and its conditional p-value.
+/// * `Err(String)` if the inputs are invalid, there is not exactly one endogenous regressor, or a
+///   cross-product matrix is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::weak_iv::run_clr;
+///
+/// let z = Array2::from_shape_vec((8, 2), vec![
+///     1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0, 7.0, 0.0, 8.0, 1.0,
+/// ]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let test = run_clr(&y, &x, &Array2::zeros((8, 0)), &z, -0.5, true).unwrap();
+/// println!("LR = {}, p-value = {}", test.statistic(), test.p_value());
+/// ```
+pub fn run_clr(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    beta_0: f64,
+    intercept: bool,
+) -> Result<WeakIvTest, String> {
+    ReducedForm::new(y, x_endog, x_exog, z, intercept)?.clr(beta_0)
+}
+
+/// Checks that `confidence` is a valid coverage level.
+fn check_confidence(confidence: f64) -> Result<(), String> {
+    if confidence > 0.0 && confidence < 1.0 {
+        Ok(())
+    } else {
+        Err("confidence must be between 0 and 1".into())
+    }
+}
+
+/// Computes the Anderson-Rubin confidence set for the coefficient on a single endogenous
+/// regressor, by inverting `run_anderson_rubin` analytically.
+///
+/// The set of `beta_0` at which the test does not reject solves a quadratic inequality in
+/// `beta_0`, so it is always one of: a bounded interval, the union of two disjoint rays, the
+/// whole real line, or the empty set. The last three occur when the instruments are weak (or,
+/// for the empty set, when the overidentifying restrictions are rejected), and are exactly the
+/// cases in which the 2SLS Wald interval is misleading.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) with a single column, the endogenous regressor.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `confidence` - The coverage of the set, between 0 and 1.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(ConfidenceSet)` with the values of `beta` that the test does not reject.
+/// * `Err(String)` if the inputs are invalid or a cross-product matrix is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::Array2;
+/// use marketing_iv_methods::utils::weak_iv::anderson_rubin_confidence_set;
+///
+/// let z = Array2::from_shape_vec((8, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let set = anderson_rubin_confidence_set(&y, &x, &Array2::zeros((8, 0)), &z, 0.95, true).unwrap();
+/// println!("AR set: {}", set);
+/// ```
+pub fn anderson_rubin_confidence_set(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    confidence: f64,
+    intercept: bool,
+) -> Result<ConfidenceSet, String> {
+    check_confidence(confidence)?;
+    let reduced_form = ReducedForm::new(y, x_endog, x_exog, z, intercept)?;
+    let k = reduced_form.n_instruments as f64;
+    let f_dist = FisherSnedecor::new(k, reduced_form.df as f64).map_err(|e| format!("Invalid degrees of freedom: {}", e))?;
+    let critical = k * f_dist.inverse_cdf(confidence);
+
+    // accept beta_0 iff b' (Y'P Y - c * Omega) b <= 0 with b = (1, -beta_0), a quadratic
+    // m11 - 2 * m12 * beta_0 + m22 * beta_0^2 <= 0
+    let m = &reduced_form.projected - &(critical * &reduced_form.omega);
+    let (m11, m12, m22) = (m[[0, 0]], m[[0, 1]], m[[1, 1]]);
+    let discriminant = m12 * m12 - m11 * m22;
+    let scale = m11.abs().max(m12.abs()).max(m22.abs());
+
+    let intervals = if m22.abs() <= 1e-12 * scale {
+        // degenerate case: the inequality is linear in beta_0
+        if m12.abs() <= 1e-12 * scale {
+            if m11 <= 0.0 { vec![(f64::NEG_INFINITY, f64::INFINITY)] } else { vec![] }
+        } else if m12 > 0.0 {
+            vec![(m11 / (2.0 * m12), f64::INFINITY)]
+        } else {
+            vec![(f64::NEG_INFINITY, m11 / (2.0 * m12))]
+        }
+    } else if discriminant < 0.0 {
+        // no real roots: the quadratic has the sign of m22 everywhere
+        if m22 < 0.0 { vec![(f64::NEG_INFINITY, f64::INFINITY)] } else { vec![] }
+    } else {
+        let root_a = (m12 - discriminant.sqrt()) / m22;
+        let root_b = (m12 + discriminant.sqrt()) / m22;
+        let (low, high) = (root_a.min(root_b), root_a.max(root_b));
+        if m22 > 0.0 {
+            vec![(low, high)]
+        } else {
+            vec![(f64::NEG_INFINITY, low), (high, f64::INFINITY)]
+        }
+    };
+
+    Ok(ConfidenceSet::new(intervals))
+}
+
+/// Finds the point where `accept` switches between `a` and `b` by bisection, given that it
+/// takes different values at the two ends.
+fn bisect_boundary<F: Fn(f64) -> bool>(accept: &F, mut a: f64, mut b: f64) -> f64 {
+    let accept_a = accept(a);
+    for _ in 0..60 {
+        let mid = 0.5 * (a + b);
+        if accept(mid) == accept_a {
+            a = mid;
+        } else {
+            b = mid;
+        }
+    }
+    0.5 * (a + b)
+}
+
+/// Walks outward from `start` in steps that double each time, looking for a point where `accept`
+/// equals `target`. Returns the last point with the opposite value and the point found, or `None`
+/// if no such point is found before the steps overflow.
+fn search_outward<F: Fn(f64) -> bool>(accept: &F, start: f64, step: f64, direction: f64, target: bool) -> Option<(f64, f64)> {
+    let mut previous = start;
+    let mut step = step;
+    for _ in 0..60 {
+        let candidate = start + direction * step;
+        if accept(candidate) == target {
+            return Some((previous, candidate));
+        }
+        previous = candidate;
+        step *= 2.0;
+    }
+    None
+}
+
+/// Computes Moreira's conditional likelihood ratio confidence set for the coefficient on a single
+/// endogenous regressor, by inverting `run_clr` over a grid.
+///
+/// The test is evaluated at every point of `grid`, and each switch between acceptance and
+/// rejection is located by bisection. Outside the grid, the test is also evaluated in the limit
+/// `beta_0 -> +/- infinity`: when the set reaches the edge of the grid it is extended outward in
+/// doubling steps until the test rejects, and is unbounded if it never does. When the test
+/// accepts in the limit, the outer ray beyond a rejection, whether at the edge of the grid or
+/// found by that search, is located the same way. A component lying strictly between two
+/// neighbouring grid points can be missed, and so can a rejection region narrower than the
+/// doubling steps outside the grid, so the grid should be fine enough around the estimate and
+/// wide enough to cover the region where the test may reject.
+///
+/// # Arguments
+///
+/// * `y` - A 2D array (`Array2<f64>`) with a single column, representing the dependent variable.
+/// * `x_endog` - A 2D array (`Array2<f64>`) with a single column, the endogenous regressor.
+/// * `x_exog` - A 2D array (`Array2<f64>`) of exogenous controls, possibly with zero columns.
+/// * `z` - A 2D array (`Array2<f64>`) of excluded instruments.
+/// * `grid` - A strictly increasing set of at least two candidate values for `beta`.
+/// * `confidence` - The coverage of the set, between 0 and 1.
+/// * `intercept` - A boolean indicating whether to include an intercept term in the model.
+///
+/// # Returns
+///
+/// * `Ok(ConfidenceSet)` with the values of `beta` that the test does not reject.
+/// * `Err(String)` if the inputs are invalid, the grid is too short or not increasing, or a
+///   cross-product matrix is singular.
+///
+/// # Example
+///
+/// ```
+/// use ndarray::{Array1, Array2};
+/// use marketing_iv_methods::utils::weak_iv::clr_confidence_set;
+///
+/// let z = Array2::from_shape_vec((8, 2), vec![
+///     1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0, 7.0, 0.0, 8.0, 1.0,
+/// ]).unwrap();
+/// let u = Array2::from_shape_vec((8, 1), vec![0.1, -0.2, 0.0, 0.2, -0.1, 0.05, -0.05, 0.1]).unwrap();
+/// let e = Array2::from_shape_vec((8, 1), vec![0.3, 0.1, -0.2, -0.1, 0.0, 0.2, -0.3, 0.1]).unwrap();
+/// let x = &z.column(0).insert_axis(ndarray::Axis(1)) + &u;
+/// let y = -0.5 * &x + &u + &e;
+/// let grid = Array1::linspace(-2.0, 1.0, 61);
+/// let set = clr_confidence_set(&y, &x, &Array2::zeros((8, 0)), &z, &grid, 0.95, true).unwrap();
+/// println!("CLR set: {}", set);
+/// ```
+pub fn clr_confidence_set(
+    y: &Array2<f64>,
+    x_endog: &Array2<f64>,
+    x_exog: &Array2<f64>,
+    z: &Array2<f64>,
+    grid: &Array1<f64>,
+    confidence: f64,
+    intercept: bool,
+) -> Result<ConfidenceSet, String> {
+    check_confidence(confidence)?;
+    if grid.len() < 2 || grid.windows(2).into_iter().any(|w| w[1] <= w[0]) {
+        return Err("grid must contain at least two strictly increasing values".into());
+    }
+    let reduced_form = ReducedForm::new(y, x_endog, x_exog, z, intercept)?;
+    // the conditional p-value only fails for invalid degrees of freedom, ruled out above
+    let accept = |beta_0: f64| {
+        reduced_form
+            .clr(beta_0)
+            .map(|test| test.p_value > 1.0 - confidence)
+            .unwrap_or(false)
+    };
+
+    let accepted: Vec<bool> = grid.iter().map(|b| accept(*b)).collect();
+    let accept_at_infinity = accept(f64::INFINITY);
+    let first = grid[0];
+    let last = grid[grid.len() - 1];
+    let step = last - first;
+
+    let mut intervals = Vec::new();
+    // extend a component that reaches the edge of the grid until the test rejects, returning its
+    // end and, when the test accepts in the limit, where the outer ray beyond the rejection starts
+    let extend = |edge: f64, direction: f64| match search_outward(&accept, edge, step, direction, false) {
+        Some((inside, outside)) => {
+            let ray = if accept_at_infinity {
+                search_outward(&accept, outside, step, direction, true)
+                    .map(|(rejected, accepted)| bisect_boundary(&accept, accepted, rejected))
+            } else {
+                None
+            };
+            (bisect_boundary(&accept, inside, outside), ray)
+        }
+        None => (direction * f64::INFINITY, None),
+    };
+
+    let mut start = if accepted[0] {
+        let (end, ray) = extend(first, -1.0);
+        if let Some(ray) = ray {
+            intervals.push((f64::NEG_INFINITY, ray));
+        }
+        Some(end)
+    } else {
+        if accept_at_infinity {
+            if let Some((outside, inside)) = search_outward(&accept, first, step, -1.0, true) {
+                intervals.push((f64::NEG_INFINITY, bisect_boundary(&accept, inside, outside)));
+            }
+        }
+        None
+    };
+
+    for i in 1..grid.len() {
+        match (accepted[i - 1], accepted[i]) {
+            (true, false) => {
+                let end = bisect_boundary(&accept, grid[i - 1], grid[i]);
+                intervals.push((start.take().unwrap_or(grid[i - 1]), end));
+            }
+            (false, true) => start = Some(bisect_boundary(&accept, grid[i - 1], grid[i])),
+            _ => {}
+        }
+    }
+
+    if let Some(start) = start {
+        let (end, ray) = extend(last, 1.0);
+        intervals.push((start, end));
+        if let Some(ray) = ray {
+            intervals.push((ray, f64::INFINITY));
+        }
+    } else if accept_at_infinity {
+        if let Some((outside, inside)) = search_outward(&accept, last, step, 1.0, true) {
+            intervals.push((bisect_boundary(&accept, inside, outside), f64::INFINITY));
+        }
+    }
+
+    Ok(ConfidenceSet::new(intervals))
+}
@@ -0,0 +1,116 @@
+use marketing_iv_methods::utils::bayesian_iv::{run_riv_dp, run_riv_gibbs, RivDpPrior, RivGibbsPrior};
+use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
+use ndarray::{Array1, Array2};
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand_distr::{Normal, Uniform};
+
+mod common;
+use common::IvDesign;
+
+// Fixture: x = z + v + e_x and y = -0.5x + 2v + e_y, so the errors of the two equations are
+// positively correlated through the omitted v
+fn design(n: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+    let data = IvDesign::new(n).alpha_y(2.0).generate(rng);
+    (data.y, data.x, data.z)
+}
+
+#[test]
+fn test_draw_shapes() {
+    let mut rng = seeded_rng(1);
+    let (y, x, z) = design(100, &mut rng);
+    let w = random_vector_generate(100, Normal::new(0.0, 1.0).unwrap(), &mut rng);
+    let draws = run_riv_gibbs(&y, &x, &z, &w, None, 50, 10, true, &mut rng).unwrap();
+    assert_eq!(draws.beta.len(), 50);
+    assert_eq!(draws.gamma.shape(), &[50, 2]);
+    assert_eq!(draws.delta.shape(), &[50, 3]);
+    assert_eq!(draws.sigma.shape(), &[50, 2, 2]);
+}
+
+#[test]
+fn test_same_seed_gives_identical_draws() {
+    let (y, x, z) = design(100, &mut seeded_rng(1));
+    let none = Array2::zeros((100, 0));
+    let first = run_riv_gibbs(&y, &x, &z, &none, None, 50, 10, true, &mut seeded_rng(3)).unwrap();
+    let second = run_riv_gibbs(&y, &x, &z, &none, None, 50, 10, true, &mut seeded_rng(3)).unwrap();
+    assert_eq!(first.beta, second.beta);
+    assert_eq!(first.sigma, second.sigma);
+    let first = run_riv_dp(&y, &x, &z, &none, None, 20, 5, &mut seeded_rng(4)).unwrap();
+    let second = run_riv_dp(&y, &x, &z, &none, None, 20, 5, &mut seeded_rng(4)).unwrap();
+    assert_eq!(first.beta, second.beta);
+    assert_eq!(first.n_components, second.n_components);
+}
+
+#[test]
+fn test_posterior_centres_on_beta() {
+    let mut rng = seeded_rng(2);
+    let (y, x, z) = design(1000, &mut rng);
+    let draws = run_riv_gibbs(&y, &x, &z, &Array2::zeros((1000, 0)), None, 1000, 200, false, &mut rng).unwrap();
+    let posterior_mean = draws.beta.mean().unwrap();
+    assert!((posterior_mean + 0.5).abs() < 0.2, "Expected posterior mean close to -0.5, got {}", posterior_mean);
+
+    // cov(e_x, e_y) = cov(v + e_x, 2v + e_y) = 2
+    let sigma_12 = draws.sigma.slice(ndarray::s![.., 0, 1]).mean().unwrap();
+    assert!((sigma_12 - 2.0).abs() < 0.5, "Expected error covariance close to 2, got {}", sigma_12);
+}
+
+#[test]
+fn test_tight_prior_dominates() {
+    let mut rng = seeded_rng(3);
+    let (y, x, z) = design(50, &mut rng);
+    let mut prior = RivGibbsPrior::default_for(1, 1);
+    prior.mbg = Array1::from(vec![3.0]);
+    prior.abg = Array2::from_elem((1, 1), 1e8);
+    let draws = run_riv_gibbs(&y, &x, &z, &Array2::zeros((50, 0)), Some(&prior), 200, 50, false, &mut rng).unwrap();
+    let posterior_mean = draws.beta.mean().unwrap();
+    assert!((posterior_mean - 3.0).abs() < 1e-2, "Expected posterior mean close to the prior mean 3, got {}", posterior_mean);
+}
+
+#[test]
+fn test_errors_on_prior_dimension() {
+    let mut rng = seeded_rng(4);
+    let (y, x, z) = design(50, &mut rng);
+    let prior = RivGibbsPrior::default_for(1, 1);
+    let result = run_riv_gibbs(&y, &x, &z, &Array2::zeros((50, 0)), Some(&prior), 10, 0, true, &mut rng);
+    assert_eq!(result.unwrap_err(), "The prior on delta must have dimension 2");
+}
+
+// Fixture: the design above, with e_y drawn from a two-component normal mixture
+fn bimodal_design(n: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+    let (y, x, z) = design(n, rng);
+    let shift = random_vector_generate(n, Uniform::new(0.0, 1.0), rng).mapv(|u| if u < 0.5 { 4.0 } else { -4.0 });
+    (&y + &shift, x, z)
+}
+
+#[test]
+fn test_dp_draw_shapes() {
+    let mut rng = seeded_rng(5);
+    let (y, x, z) = design(100, &mut rng);
+    let w = random_vector_generate(100, Normal::new(0.0, 1.0).unwrap(), &mut rng);
+    let draws = run_riv_dp(&y, &x, &z, &w, None, 50, 10, &mut rng).unwrap();
+    assert_eq!(draws.beta.len(), 50);
+    assert_eq!(draws.gamma.shape(), &[50, 1]);
+    assert_eq!(draws.delta.shape(), &[50, 2]);
+    assert_eq!(draws.n_components.len(), 50);
+    assert!(draws.alpha.iter().all(|a| *a > 0.0));
+}
+
+#[test]
+fn test_dp_finds_mixture_components() {
+    let mut rng = seeded_rng(6);
+    let (y, x, z) = bimodal_design(400, &mut rng);
+    let draws = run_riv_dp(&y, &x, &z, &Array2::zeros((400, 0)), None, 300, 100, &mut rng).unwrap();
+    let posterior_mean = draws.beta.mean().unwrap();
+    assert!((posterior_mean + 0.5).abs() < 0.3, "Expected posterior mean close to -0.5, got {}", posterior_mean);
+    let min_components = draws.n_components.iter().min().unwrap();
+    assert!(*min_components >= 2, "Expected at least two components, got {}", min_components);
+}
+
+#[test]
+fn test_dp_errors_on_invalid_prior() {
+    let mut rng = seeded_rng(7);
+    let (y, x, z) = design(50, &mut rng);
+    let mut prior = RivDpPrior::default_for(1, 1);
+    prior.alpha_rate = 0.0;
+    let result = run_riv_dp(&y, &x, &z, &Array2::zeros((50, 0)), Some(&prior), 10, 0, &mut rng);
+    assert_eq!(result.unwrap_err(), "a, alpha_shape and alpha_rate must be positive");
+}
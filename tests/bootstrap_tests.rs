@@ -0,0 +1,164 @@
+use marketing_iv_methods::utils::bootstrap::{run_bootstrap, BootstrapFit, BootstrapScheme, WildWeights};
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::jive::{run_jive, JiveVariant};
+use marketing_iv_methods::utils::linear_regression::{run_2sls, run_regression};
+use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+use ndarray::{Array2, Axis, concatenate};
+use ndarray_rand::rand::rngs::StdRng;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: one endogenous regressor with a strong instrument and heteroskedastic errors
+fn iv_design(n: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+    let data = IvDesign::new(n).heteroskedastic().intercept(1.0).generate(rng);
+    (data.y, data.x, data.z)
+}
+
+fn tsls(y: &Array2<f64>, x: &Array2<f64>, w: &Array2<f64>, z: &Array2<f64>) -> Result<BootstrapFit, String> {
+    run_2sls(y, x, w, z, &CovarianceType::HC1, true).map(|fit| BootstrapFit::from(&fit))
+}
+
+fn ols(y: &Array2<f64>, _: &Array2<f64>, w: &Array2<f64>, _: &Array2<f64>) -> Result<BootstrapFit, String> {
+    run_regression(w, y, &CovarianceType::HC1, true).map(|fit| BootstrapFit::from(&fit))
+}
+
+#[test]
+fn test_pairs_bootstrap_matches_robust_standard_error() {
+    let mut rng = seeded_rng(1);
+    let (y, x, z) = iv_design(300, &mut rng);
+    let regressors = concatenate(Axis(1), &[x.view(), z.view()]).unwrap();
+    let none = Array2::zeros((300, 0));
+    let result = run_bootstrap(&y, &none, &regressors, &none, ols, &BootstrapScheme::Pairs, 499, true, &mut rng).unwrap();
+    let robust = run_regression(&regressors, &y, &CovarianceType::HC1, true).unwrap();
+
+    let ratio = &result.bootstrap_std_errors() / robust.std_errors();
+    assert!(ratio.iter().all(|r| (r - 1.0).abs() < 0.25));
+}
+
+#[test]
+fn test_exact_fit_is_reproduced_by_residual_schemes() {
+    let mut rng = seeded_rng(2);
+    // without structural or first-stage errors every bootstrap sample equals the original one
+    let z = Array2::from_shape_fn((20, 1), |(i, _)| (i % 6) as f64);
+    let w = Array2::from_shape_fn((20, 1), |(i, _)| (i % 4) as f64);
+    let x = 2.0 * &z + &w;
+    let y = -0.5 * &x + 0.8 * &w + 1.0;
+    for scheme in [BootstrapScheme::Residual, BootstrapScheme::Wild(WildWeights::Mammen)] {
+        let result = run_bootstrap(&y, &x, &w, &z, tsls, &scheme, 20, true, &mut rng).unwrap();
+        for row in result.draws().outer_iter() {
+            assert!((row[0] + 0.5).abs() < 1e-8 && (row[1] - 0.8).abs() < 1e-8);
+        }
+    }
+}
+
+#[test]
+fn test_bootstrap_t_errors_on_exact_fit() {
+    let mut rng = seeded_rng(8);
+    let z = Array2::from_shape_fn((20, 1), |(i, _)| (i % 6) as f64);
+    let w = Array2::from_shape_fn((20, 1), |(i, _)| (i % 4) as f64);
+    let x = 2.0 * &z + &w;
+    let y = -0.5 * &x + 0.8 * &w + 1.0;
+
+    // the 2SLS standard errors of an exact fit are zero up to rounding, so bootstrap-t may
+    // fail but must not panic
+    for scheme in [BootstrapScheme::Residual, BootstrapScheme::Wild(WildWeights::Rademacher)] {
+        let result = run_bootstrap(&y, &x, &w, &z, tsls, &scheme, 20, true, &mut rng).unwrap();
+        if let Ok(interval) = result.bootstrap_t_interval(0.95) {
+            assert!(interval.iter().all(|bound| bound.is_finite()));
+        }
+        let _ = result.bootstrap_t_p_value(0, -0.5);
+    }
+
+    // an estimator whose standard errors are exactly zero
+    let zero_se = |y: &Array2<f64>, x: &Array2<f64>, w: &Array2<f64>, z: &Array2<f64>| {
+        tsls(y, x, w, z).map(|fit| BootstrapFit { std_errors: Some(fit.params.mapv(|_| 0.0)), ..fit })
+    };
+    let result = run_bootstrap(&y, &x, &w, &z, zero_se, &BootstrapScheme::Residual, 20, true, &mut rng).unwrap();
+    assert!(result.bootstrap_t_interval(0.95).unwrap_err().contains("bootstrap-t inference is undefined"));
+    assert!(result.bootstrap_t_p_value(0, -0.5).unwrap_err().contains("bootstrap-t inference is undefined"));
+    assert!(result.percentile_interval(0.95).is_ok());
+}
+
+#[test]
+fn test_intervals_contain_estimate() {
+    let mut rng = seeded_rng(3);
+    let (y, x, z) = iv_design(150, &mut rng);
+    let no_controls = Array2::zeros((150, 0));
+    for scheme in [BootstrapScheme::Pairs, BootstrapScheme::Residual, BootstrapScheme::Wild(WildWeights::Rademacher)] {
+        let result = run_bootstrap(&y, &x, &no_controls, &z, tsls, &scheme, 299, true, &mut rng).unwrap();
+        let estimate = result.estimate()[0];
+        for interval in [
+            result.percentile_interval(0.95).unwrap(),
+            result.bca_interval(0.95).unwrap(),
+            result.bootstrap_t_interval(0.95).unwrap(),
+        ] {
+            assert!(interval[[0, 0]] < estimate && estimate < interval[[0, 1]]);
+        }
+    }
+}
+
+#[test]
+fn test_wild_restricted_efficient_bootstrap_rejects_false_null() {
+    let mut rng = seeded_rng(4);
+    let (y, x, z) = iv_design(200, &mut rng);
+    let no_controls = Array2::zeros((200, 0));
+    let scheme = BootstrapScheme::WildRestrictedEfficient { weights: WildWeights::Rademacher, null_value: 1.0 };
+    let result = run_bootstrap(&y, &x, &no_controls, &z, tsls, &scheme, 199, true, &mut rng).unwrap();
+    assert!(result.bootstrap_t_p_value(0, 1.0).unwrap() < 0.05);
+    assert!(result.bootstrap_t_interval(0.95).is_ok());
+
+    // the restricted draws only speak to the imposed null
+    assert!(result.percentile_interval(0.95).is_err());
+    assert!(result.bca_interval(0.95).is_err());
+    assert!(result.bootstrap_t_p_value(0, -0.5).is_err());
+}
+
+#[test]
+fn test_wild_restricted_efficient_bootstrap_size() {
+    let mut rng = seeded_rng(5);
+    let replications = 100;
+    let mut rejections = 0;
+    for _ in 0..replications {
+        let (y, x, z) = iv_design(100, &mut rng);
+        let no_controls = Array2::zeros((100, 0));
+        let scheme = BootstrapScheme::WildRestrictedEfficient { weights: WildWeights::Rademacher, null_value: -0.5 };
+        let result = run_bootstrap(&y, &x, &no_controls, &z, tsls, &scheme, 99, true, &mut rng).unwrap();
+        rejections += (result.bootstrap_t_p_value(0, -0.5).unwrap() < 0.05) as usize;
+    }
+    assert!(rejections <= 15);
+}
+
+#[test]
+fn test_same_seed_gives_identical_bootstrap() {
+    let (y, x, z) = iv_design(100, &mut seeded_rng(7));
+    let no_controls = Array2::zeros((100, 0));
+    let scheme = BootstrapScheme::Wild(WildWeights::Mammen);
+    let first = run_bootstrap(&y, &x, &no_controls, &z, tsls, &scheme, 49, true, &mut seeded_rng(8)).unwrap();
+    let second = run_bootstrap(&y, &x, &no_controls, &z, tsls, &scheme, 49, true, &mut seeded_rng(8)).unwrap();
+    assert_eq!(first.bootstrap_std_errors(), second.bootstrap_std_errors());
+    assert_eq!(first.percentile_interval(0.9).unwrap(), second.percentile_interval(0.9).unwrap());
+}
+
+#[test]
+fn test_bootstrap_errors_on_invalid_requests() {
+    let mut rng = seeded_rng(6);
+    let (y, x, z) = iv_design(50, &mut rng);
+    let no_controls = Array2::zeros((50, 0));
+
+    // JIVE reports no standard errors, so only percentile and BCa intervals are available
+    let jive = |y: &Array2<f64>, x: &Array2<f64>, w: &Array2<f64>, z: &Array2<f64>| {
+        run_jive(y, x, w, z, JiveVariant::Jive1, true).map(|fit| BootstrapFit::from(&fit))
+    };
+    let result = run_bootstrap(&y, &x, &no_controls, &z, jive, &BootstrapScheme::Pairs, 49, true, &mut rng).unwrap();
+    assert!(result.percentile_interval(0.9).is_ok());
+    assert!(result.bootstrap_t_interval(0.9).is_err());
+    assert!(result.percentile_interval(1.5).is_err());
+
+    let two_endog = concatenate(Axis(1), &[x.view(), (&x * &x).view()]).unwrap();
+    let two_instruments = concatenate(Axis(1), &[z.view(), (&z * &z).view()]).unwrap();
+    let wre = BootstrapScheme::WildRestrictedEfficient { weights: WildWeights::Mammen, null_value: 0.0 };
+    assert!(run_bootstrap(&y, &two_endog, &no_controls, &two_instruments, tsls, &wre, 49, true, &mut rng).is_err());
+    assert!(run_bootstrap(&y, &x, &no_controls, &z, tsls, &BootstrapScheme::Pairs, 1, true, &mut rng).is_err());
+    assert!(run_bootstrap(&y, &x, &z, &z, ols, &BootstrapScheme::Pairs, 49, true, &mut rng).is_err());
+}
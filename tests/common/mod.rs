@@ -0,0 +1,137 @@
+// Shared fixtures for the integration tests. Each test crate uses a different subset.
+#![allow(dead_code)]
+
+use marketing_iv_methods::utils::random_vector_gen::random_vector_generate;
+use ndarray::{Array1, Array2, Axis};
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand_distr::Normal;
+
+/// The simulated data of an `IvDesign`: the outcome `y`, the endogenous regressor `x`, the
+/// exogenous control `w` and the instruments `z`.
+pub struct IvData {
+    pub y: Array2<f64>,
+    pub x: Array2<f64>,
+    pub w: Array2<f64>,
+    pub z: Array2<f64>,
+}
+
+/// The confounder design shared by the IV tests,
+///
+/// `x = z * pi + alpha_x * v + w_x * w + sigma_ex * e_x`,
+/// `y = beta * x + alpha_y * v + w_y * w + z * gamma + s * e_y + intercept`,
+///
+/// where the confounder `v`, the control `w`, the instruments `z` and the errors `e_x` and `e_y`
+/// are independent standard normals, and `s = 1 + |z_1|` when the errors are heteroskedastic and
+/// `1` otherwise. There are as many instruments as entries in `pi`. The draws are made in the
+/// order `v`, `z`, `w`, `e_x`, `e_y`, so a seed fixes the data whatever the coefficients.
+#[derive(Debug, Clone)]
+pub struct IvDesign {
+    n: usize,
+    pi: Vec<f64>,
+    alpha_x: f64,
+    alpha_y: f64,
+    beta: f64,
+    w_x: f64,
+    w_y: f64,
+    gamma: Option<Vec<f64>>,
+    sigma_ex: f64,
+    heteroskedastic: bool,
+    intercept: f64,
+}
+
+impl IvDesign {
+    /// A design with `n` observations, one instrument with `pi = 1`, `alpha_x = alpha_y = 1`,
+    /// `beta = -0.5`, unit-variance homoskedastic errors and no control, direct effect or
+    /// intercept.
+    pub fn new(n: usize) -> Self {
+        IvDesign {
+            n,
+            pi: vec![1.0],
+            alpha_x: 1.0,
+            alpha_y: 1.0,
+            beta: -0.5,
+            w_x: 0.0,
+            w_y: 0.0,
+            gamma: None,
+            sigma_ex: 1.0,
+            heteroskedastic: false,
+            intercept: 0.0,
+        }
+    }
+
+    pub fn pi(mut self, pi: Vec<f64>) -> Self {
+        self.pi = pi;
+        self
+    }
+
+    pub fn alpha_x(mut self, alpha_x: f64) -> Self {
+        self.alpha_x = alpha_x;
+        self
+    }
+
+    pub fn alpha_y(mut self, alpha_y: f64) -> Self {
+        self.alpha_y = alpha_y;
+        self
+    }
+
+    pub fn beta(mut self, beta: f64) -> Self {
+        self.beta = beta;
+        self
+    }
+
+    pub fn w_x(mut self, w_x: f64) -> Self {
+        self.w_x = w_x;
+        self
+    }
+
+    pub fn w_y(mut self, w_y: f64) -> Self {
+        self.w_y = w_y;
+        self
+    }
+
+    /// The direct effects of the instruments on `y`, one per instrument.
+    pub fn gamma(mut self, gamma: Vec<f64>) -> Self {
+        self.gamma = Some(gamma);
+        self
+    }
+
+    pub fn sigma_ex(mut self, sigma_ex: f64) -> Self {
+        self.sigma_ex = sigma_ex;
+        self
+    }
+
+    /// Scales `e_y` by `1 + |z_1|`.
+    pub fn heteroskedastic(mut self) -> Self {
+        self.heteroskedastic = true;
+        self
+    }
+
+    pub fn intercept(mut self, intercept: f64) -> Self {
+        self.intercept = intercept;
+        self
+    }
+
+    pub fn generate(&self, rng: &mut StdRng) -> IvData {
+        let n = self.n;
+        let k = self.pi.len();
+        let dist = Normal::new(0.0, 1.0).unwrap();
+        let v = random_vector_generate(n, dist, rng);
+        let z = random_vector_generate(n * k, dist, rng).into_shape((n, k)).unwrap();
+        let w = random_vector_generate(n, dist, rng);
+        let e_x = random_vector_generate(n, dist, rng);
+        let e_y = random_vector_generate(n, dist, rng);
+
+        let pi = Array1::from(self.pi.clone()).insert_axis(Axis(1));
+        let x = z.dot(&pi) + self.alpha_x * &v + self.w_x * &w + self.sigma_ex * &e_x;
+        let scale = if self.heteroskedastic {
+            z.column(0).mapv(|z1| 1.0 + z1.abs()).insert_axis(Axis(1))
+        } else {
+            Array2::ones((n, 1))
+        };
+        let mut y = self.beta * &x + self.alpha_y * &v + self.w_y * &w + &scale * &e_y + self.intercept;
+        if let Some(gamma) = &self.gamma {
+            y = y + z.dot(&Array1::from(gamma.clone()).insert_axis(Axis(1)));
+        }
+        IvData { y, x, w, z }
+    }
+}
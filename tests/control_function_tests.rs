@@ -0,0 +1,101 @@
+use marketing_iv_methods::utils::control_function::run_control_function;
+use marketing_iv_methods::utils::covariance::{CovarianceType, HacKernel};
+use marketing_iv_methods::utils::linear_regression::run_2sls;
+use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
+use ndarray::{Array1, Array2};
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand_distr::Normal;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: x = 2v + z + e_x and y = -0.5x + alpha_y * v + e_y, with v omitted from the model
+fn design(n: usize, alpha_y: f64, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>, Array2<f64>) {
+    let data = IvDesign::new(n).alpha_x(2.0).alpha_y(alpha_y).w_y(0.3).generate(rng);
+    (data.y, data.x, data.w, data.z)
+}
+
+#[test]
+fn test_control_function_matches_2sls() {
+    let mut rng = seeded_rng(1);
+    let (y, x, w, z) = design(500, 3.0, &mut rng);
+    let cf_fit = run_control_function(&y, &x, &w, &z, &CovarianceType::HC0, true).unwrap();
+    let tsls_fit = run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, true).unwrap();
+    for (cf, tsls) in cf_fit.params().iter().zip(tsls_fit.params().iter()) {
+        assert!((cf - tsls).abs() < 1e-8, "Expected control function = 2SLS, got {} and {}", cf, tsls);
+    }
+    assert!((cf_fit.intercept() - tsls_fit.intercept()).abs() < 1e-8);
+}
+
+#[test]
+fn test_control_function_corrected_std_error() {
+    let mut rng = seeded_rng(2);
+    // the structural error 3v + e_y has variance 10 and the instrument has unit variance and
+    // coefficient, so the asymptotic standard error of beta is sqrt(10 / n)
+    let n = 20000;
+    let (y, x, w, z) = design(n, 3.0, &mut rng);
+    let fit = run_control_function(&y, &x, &w, &z, &CovarianceType::HC0, true).unwrap();
+    let expected = (10.0 / n as f64).sqrt();
+    assert!((fit.std_errors()[0] / expected - 1.0).abs() < 0.15, "Expected standard error close to {}, got {}", expected, fit.std_errors()[0]);
+}
+
+#[test]
+fn test_endogeneity_test_rejects() {
+    let mut rng = seeded_rng(3);
+    let (y, x, w, z) = design(5000, 3.0, &mut rng);
+    let fit = run_control_function(&y, &x, &w, &z, &CovarianceType::HC0, true).unwrap();
+    assert!(fit.residual_params()[0] > 0.0, "Expected positive residual coefficient, got {}", fit.residual_params()[0]);
+    assert!(fit.endogeneity_p_value() < 0.01, "Expected rejection, got p-value {}", fit.endogeneity_p_value());
+    let t = fit.residual_params()[0] / fit.residual_std_errors()[0];
+    assert!((t * t - fit.endogeneity_statistic()).abs() < 1e-6);
+}
+
+#[test]
+fn test_control_function_covariance_types() {
+    let mut rng = seeded_rng(5);
+    let n = 2000;
+    let (y, x, w, z) = design(n, 3.0, &mut rng);
+    let fit = |covariance: CovarianceType| run_control_function(&y, &x, &w, &z, &covariance, true).unwrap();
+    let hc0 = fit(CovarianceType::HC0);
+    let hc1 = fit(CovarianceType::HC1);
+
+    // the errors are homoskedastic, so every estimator agrees with the robust one
+    for other in [fit(CovarianceType::Classical), fit(CovarianceType::HC2), fit(CovarianceType::HC3)] {
+        let ratio = other.std_errors()[0] / hc0.std_errors()[0];
+        assert!((ratio - 1.0).abs() < 0.1, "Expected standard errors close to HC0, got ratio {}", ratio);
+    }
+    assert!(fit(CovarianceType::HC3).std_errors()[0] > fit(CovarianceType::HC2).std_errors()[0]);
+    assert!(hc1.std_errors()[0] > hc0.std_errors()[0]);
+
+    // one observation per cluster, or a Bartlett kernel that gives no weight to the first lag,
+    // reproduces HC1
+    let singletons = fit(CovarianceType::Cluster(Array1::from_iter(0..n)));
+    let no_lags = fit(CovarianceType::Hac { kernel: HacKernel::Bartlett, bandwidth: Some(1.0) });
+    for other in [singletons, no_lags] {
+        assert!((other.std_errors()[0] - hc1.std_errors()[0]).abs() < 1e-10);
+        assert!((other.residual_std_errors()[0] - hc1.residual_std_errors()[0]).abs() < 1e-10);
+        assert!((other.intercept_std_error().unwrap() - hc1.intercept_std_error().unwrap()).abs() < 1e-10);
+    }
+}
+
+#[test]
+fn test_control_function_cluster_std_errors_grow_with_cluster_shocks() {
+    let mut rng = seeded_rng(6);
+    let n = 2000;
+    let (y, x, w, z) = design(n, 3.0, &mut rng);
+    // a shared shock in each cluster of 20 consecutive observations
+    let stores = Array1::from_iter((0..n).map(|i| i / 20));
+    let shocks = random_vector_generate(n / 20, Normal::new(0.0, 3.0).unwrap(), &mut rng);
+    let y = &y + &Array2::from_shape_fn((n, 1), |(i, _)| shocks[[i / 20, 0]]);
+    let hc1 = run_control_function(&y, &x, &w, &z, &CovarianceType::HC1, true).unwrap();
+    let clustered = run_control_function(&y, &x, &w, &z, &CovarianceType::Cluster(stores), true).unwrap();
+    assert!(clustered.intercept_std_error().unwrap() > 2.0 * hc1.intercept_std_error().unwrap());
+}
+
+#[test]
+fn test_control_function_errors_when_under_identified() {
+    let mut rng = seeded_rng(4);
+    let (y, x, w, _) = design(20, 3.0, &mut rng);
+    let result = run_control_function(&y, &x, &w, &Array2::zeros((20, 0)), &CovarianceType::HC0, true);
+    assert_eq!(result.unwrap_err(), "Model is under-identified: 0 instruments for 1 endogenous regressors");
+}
@@ -0,0 +1,270 @@
+use marketing_iv_methods::utils::covariance::{CovarianceType, HacKernel};
+use marketing_iv_methods::utils::linear_regression::{run_2sls, run_regression};
+use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
+use ndarray::{Array1, Array2, Axis, concatenate};
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand_distr::Normal;
+
+// Fixture: y = 1 + 2x + e with the standard deviation of e proportional to |x|
+fn heteroskedastic_design(n: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>) {
+    let dist = Normal::new(0.0, 1.0).unwrap();
+    let x = random_vector_generate(n, dist, rng);
+    let e = random_vector_generate(n, dist, rng);
+    let y = 1.0 + 2.0 * &x + 2.0 * &x.mapv(f64::abs) * &e;
+    (y, x)
+}
+
+// Fixture: `n_clusters` stores with `per_cluster` observations each, where both x and the error
+// have a store-level component
+fn clustered_design(n_clusters: usize, per_cluster: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array1<usize>) {
+    let dist = Normal::new(0.0, 1.0).unwrap();
+    let n = n_clusters * per_cluster;
+    let ids = Array1::from_shape_fn(n, |i| i / per_cluster);
+    let x_store = random_vector_generate(n_clusters, dist, rng);
+    let e_store = random_vector_generate(n_clusters, dist, rng);
+    let x = &random_vector_generate(n, dist, rng) * 0.5 + &Array2::from_shape_fn((n, 1), |(i, _)| x_store[[ids[i], 0]]);
+    let e = random_vector_generate(n, dist, rng) + Array2::from_shape_fn((n, 1), |(i, _)| e_store[[ids[i], 0]]);
+    let y = 1.0 + 2.0 * &x + e;
+    (y, x, ids)
+}
+
+// Fixture: y = 2x + u where x and u are both AR(1) with autoregressive coefficient `rho`
+fn autocorrelated_design(n: usize, rho: f64, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>) {
+    let dist = Normal::new(0.0, 1.0).unwrap();
+    let shocks = random_vector_generate(2 * n, dist, rng);
+    let mut x = Array2::<f64>::zeros((n, 1));
+    let mut u = Array2::<f64>::zeros((n, 1));
+    for i in 1..n {
+        x[[i, 0]] = rho * x[[i - 1, 0]] + shocks[[i, 0]];
+        u[[i, 0]] = rho * u[[i - 1, 0]] + shocks[[n + i, 0]];
+    }
+    let y = 2.0 * &x + u;
+    (y, x)
+}
+
+#[test]
+fn test_covariance_type_parses_case_insensitively() {
+    assert_eq!("classical".parse::<CovarianceType>().unwrap(), CovarianceType::Classical);
+    assert_eq!("HC0".parse::<CovarianceType>().unwrap(), CovarianceType::HC0);
+    assert_eq!("hc2".parse::<CovarianceType>().unwrap(), CovarianceType::HC2);
+    assert_eq!(
+        "HAC-QS".parse::<CovarianceType>().unwrap(),
+        CovarianceType::Hac { kernel: HacKernel::QuadraticSpectral, bandwidth: None }
+    );
+    assert!("hc4".parse::<CovarianceType>().is_err());
+}
+
+#[test]
+fn test_hc0_matches_closed_form() {
+    let x = Array2::from_shape_vec((6, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let y = Array2::from_shape_vec((6, 1), vec![2.1, 3.9, 6.2, 7.8, 10.1, 12.0]).unwrap();
+    let hc0 = run_regression(&x, &y, &CovarianceType::HC0, true).unwrap();
+    let hc1 = run_regression(&x, &y, &CovarianceType::HC1, true).unwrap();
+
+    // simple-regression formula: var(b) = sum((x_i - xbar)^2 e_i^2) / Sxx^2
+    let centered = x.column(0).mapv(|v| v - 3.5);
+    let sxx = centered.dot(&centered);
+    let meat = (&centered * &centered * hc0.residuals() * hc0.residuals()).sum();
+    assert!((hc0.std_errors()[0] - meat.sqrt() / sxx).abs() < 1e-10);
+    assert_eq!(hc0.covariance_type(), &CovarianceType::HC0);
+
+    // HC1 rescales HC0 by n / (n - p) with p = 2
+    assert!((hc1.std_errors()[0] - hc0.std_errors()[0] * 1.5f64.sqrt()).abs() < 1e-10);
+    assert!((hc1.intercept_std_error().unwrap() - hc0.intercept_std_error().unwrap() * 1.5f64.sqrt()).abs() < 1e-10);
+}
+
+#[test]
+fn test_leverage_adjusted_errors_are_ordered() {
+    let mut rng = seeded_rng(1);
+    let (y, x) = heteroskedastic_design(50, &mut rng);
+    let ses: Vec<f64> = [CovarianceType::HC0, CovarianceType::HC2, CovarianceType::HC3]
+        .iter()
+        .map(|covariance| run_regression(&x, &y, covariance, true).unwrap().std_errors()[0])
+        .collect();
+    assert!(ses[0] <= ses[1] && ses[1] <= ses[2]);
+}
+
+#[test]
+fn test_robust_errors_track_sampling_variability() {
+    let mut rng = seeded_rng(2);
+    let replications = 300;
+    let mut estimates = vec![];
+    let mut classical = vec![];
+    let mut robust = vec![];
+    for _ in 0..replications {
+        let (y, x) = heteroskedastic_design(400, &mut rng);
+        let classical_fit = run_regression(&x, &y, &CovarianceType::Classical, true).unwrap();
+        estimates.push(classical_fit.params()[0]);
+        classical.push(classical_fit.std_errors()[0]);
+        robust.push(run_regression(&x, &y, &CovarianceType::HC1, true).unwrap().std_errors()[0]);
+    }
+    let mean = estimates.iter().sum::<f64>() / replications as f64;
+    let sd = (estimates.iter().map(|b| (b - mean).powi(2)).sum::<f64>() / (replications - 1) as f64).sqrt();
+    let mean_classical = classical.iter().sum::<f64>() / replications as f64;
+    let mean_robust = robust.iter().sum::<f64>() / replications as f64;
+
+    // the error variance grows with x^2, so the classical SE understates the spread by about sqrt(3)
+    assert!((mean_robust / sd - 1.0).abs() < 0.2);
+    assert!(mean_classical / sd < 0.75);
+}
+
+#[test]
+fn test_just_identified_iv_with_exogenous_regressor_matches_ols() {
+    let mut rng = seeded_rng(3);
+    let (y, x) = heteroskedastic_design(100, &mut rng);
+    let w = random_vector_generate(100, Normal::new(0.0, 1.0).unwrap(), &mut rng);
+    let ols_x = concatenate(Axis(1), &[x.view(), w.view()]).unwrap();
+    for covariance in [CovarianceType::Classical, CovarianceType::HC0, CovarianceType::HC1, CovarianceType::HC2, CovarianceType::HC3] {
+        let iv_fit = run_2sls(&y, &x, &w, &x, &covariance, true).unwrap();
+        let ols_fit = run_regression(&ols_x, &y, &covariance, true).unwrap();
+        assert!((iv_fit.std_errors() - ols_fit.std_errors()).iter().all(|d| d.abs() < 1e-8));
+        assert!((iv_fit.intercept_std_error().unwrap() - ols_fit.intercept_std_error().unwrap()).abs() < 1e-8);
+        assert_eq!(iv_fit.covariance_type(), &covariance);
+    }
+}
+
+#[test]
+fn test_hc3_errors_on_leverage_one() {
+    // the dummy singles out the last observation, which it then fits exactly
+    let x = Array2::from_shape_vec((5, 2), vec![1.0, 0.0, 2.0, 0.0, 3.0, 0.0, 4.0, 0.0, 5.0, 1.0]).unwrap();
+    let y = Array2::from_shape_vec((5, 1), vec![1.0, 2.2, 2.9, 4.1, 7.0]).unwrap();
+    assert!(run_regression(&x, &y, &CovarianceType::HC0, false).is_ok());
+    assert!(run_regression(&x, &y, &CovarianceType::HC3, false).is_err());
+}
+
+#[test]
+fn test_singleton_clusters_reproduce_hc1() {
+    let mut rng = seeded_rng(4);
+    let (y, x) = heteroskedastic_design(60, &mut rng);
+    let ids = Array1::from_iter(0..60);
+    let hc1 = run_regression(&x, &y, &CovarianceType::HC1, true).unwrap();
+    let cluster = run_regression(&x, &y, &CovarianceType::Cluster(ids), true).unwrap();
+    assert!((cluster.std_errors()[0] - hc1.std_errors()[0]).abs() < 1e-10);
+    assert!((cluster.intercept_std_error().unwrap() - hc1.intercept_std_error().unwrap()).abs() < 1e-10);
+}
+
+#[test]
+fn test_cluster_errors_exceed_hc1_under_within_cluster_correlation() {
+    let mut rng = seeded_rng(5);
+    let (y, x, ids) = clustered_design(40, 25, &mut rng);
+    let hc1 = run_regression(&x, &y, &CovarianceType::HC1, true).unwrap();
+    let cluster = run_regression(&x, &y, &CovarianceType::Cluster(ids), true).unwrap();
+    // with 25 observations per store the design effect is roughly 1 + 24 * 0.8 * 0.5
+    assert!(cluster.std_errors()[0] > 2.0 * hc1.std_errors()[0]);
+}
+
+#[test]
+fn test_two_way_cluster_with_singleton_second_dimension_is_one_way() {
+    let mut rng = seeded_rng(6);
+    let (y, x, ids) = clustered_design(10, 8, &mut rng);
+    let one_way = run_regression(&x, &y, &CovarianceType::Cluster(ids.clone()), true).unwrap();
+    let two_way = run_regression(&x, &y, &CovarianceType::TwoWayCluster(ids, Array1::from_iter(0..80)), true).unwrap();
+    assert!((two_way.std_errors()[0] - one_way.std_errors()[0]).abs() < 1e-10);
+}
+
+#[test]
+fn test_two_way_cluster_by_store_and_week() {
+    let mut rng = seeded_rng(7);
+    let (y, x, stores) = clustered_design(12, 10, &mut rng);
+    let weeks = Array1::from_shape_fn(120, |i| i % 10);
+    let two_way = run_regression(&x, &y, &CovarianceType::TwoWayCluster(stores.clone(), weeks.clone()), true).unwrap();
+    let by_store = run_regression(&x, &y, &CovarianceType::Cluster(stores), true).unwrap();
+    let by_week = run_regression(&x, &y, &CovarianceType::Cluster(weeks), true).unwrap();
+
+    // store and week are crossed, so each intersection is a single observation and V_12 is HC1
+    let hc1 = run_regression(&x, &y, &CovarianceType::HC1, true).unwrap();
+    let expected = by_store.std_errors()[0].powi(2) + by_week.std_errors()[0].powi(2) - hc1.std_errors()[0].powi(2);
+    assert!((two_way.std_errors()[0].powi(2) - expected).abs() < 1e-10);
+}
+
+#[test]
+fn test_clustered_iv_with_exogenous_regressor_matches_ols() {
+    let mut rng = seeded_rng(8);
+    let (y, x, ids) = clustered_design(15, 6, &mut rng);
+    let w = random_vector_generate(90, Normal::new(0.0, 1.0).unwrap(), &mut rng);
+    let covariance = CovarianceType::Cluster(ids);
+    let iv_fit = run_2sls(&y, &x, &w, &x, &covariance, true).unwrap();
+    let ols_fit = run_regression(&concatenate(Axis(1), &[x.view(), w.view()]).unwrap(), &y, &covariance, true).unwrap();
+    assert!((iv_fit.std_errors() - ols_fit.std_errors()).iter().all(|d| d.abs() < 1e-8));
+}
+
+#[test]
+fn test_cluster_errors_on_invalid_identifiers() {
+    let mut rng = seeded_rng(9);
+    let (y, x, _) = clustered_design(5, 4, &mut rng);
+    assert!(run_regression(&x, &y, &CovarianceType::Cluster(Array1::zeros(20)), true).is_err());
+    assert!(run_regression(&x, &y, &CovarianceType::Cluster(Array1::zeros(19)), true).is_err());
+    assert!(run_regression(&x, &y, &CovarianceType::TwoWayCluster(Array1::from_iter(0..20), Array1::zeros(20)), true).is_err());
+}
+
+#[test]
+fn test_newey_west_matches_closed_form() {
+    let x = Array2::from_shape_vec((6, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let y = Array2::from_shape_vec((6, 1), vec![1.1, 1.9, 3.2, 3.8, 5.1, 6.0]).unwrap();
+    let covariance = CovarianceType::Hac { kernel: HacKernel::Bartlett, bandwidth: Some(2.0) };
+    let fit = run_regression(&x, &y, &covariance, false).unwrap();
+
+    // one lag with weight 1/2: var(b) = n / (n - 1) * (sum s_i^2 + sum s_i s_{i-1}) / Sxx^2
+    let scores = &x.column(0) * fit.residuals();
+    let lagged: f64 = (1..6).map(|i| scores[i] * scores[i - 1]).sum();
+    let meat = 6.0 / 5.0 * (scores.dot(&scores) + lagged);
+    assert!((fit.std_errors()[0] - meat.sqrt() / 91.0).abs() < 1e-10);
+}
+
+#[test]
+fn test_hac_without_lags_reproduces_hc1() {
+    let mut rng = seeded_rng(10);
+    let (y, x) = autocorrelated_design(80, 0.5, &mut rng);
+    let hc1 = run_regression(&x, &y, &CovarianceType::HC1, true).unwrap();
+    for kernel in [HacKernel::Bartlett, HacKernel::Parzen] {
+        let hac = run_regression(&x, &y, &CovarianceType::Hac { kernel, bandwidth: Some(1.0) }, true).unwrap();
+        assert!((hac.std_errors()[0] - hc1.std_errors()[0]).abs() < 1e-10);
+    }
+    let invalid = CovarianceType::Hac { kernel: HacKernel::Bartlett, bandwidth: Some(0.0) };
+    assert!(run_regression(&x, &y, &invalid, true).is_err());
+}
+
+#[test]
+fn test_hac_errors_track_sampling_variability_under_autocorrelation() {
+    let mut rng = seeded_rng(11);
+    let replications = 200;
+    let mut estimates = vec![];
+    let mut standard_errors = vec![vec![]; 4];
+    let covariances = [
+        CovarianceType::HC1,
+        CovarianceType::Hac { kernel: HacKernel::Bartlett, bandwidth: None },
+        CovarianceType::Hac { kernel: HacKernel::Parzen, bandwidth: None },
+        CovarianceType::Hac { kernel: HacKernel::QuadraticSpectral, bandwidth: None },
+    ];
+    for _ in 0..replications {
+        let (y, x) = autocorrelated_design(300, 0.7, &mut rng);
+        for (j, covariance) in covariances.iter().enumerate() {
+            let fit = run_regression(&x, &y, covariance, false).unwrap();
+            if j == 0 {
+                estimates.push(fit.params()[0]);
+            }
+            standard_errors[j].push(fit.std_errors()[0]);
+        }
+    }
+    let mean = estimates.iter().sum::<f64>() / replications as f64;
+    let sd = (estimates.iter().map(|b| (b - mean).powi(2)).sum::<f64>() / (replications - 1) as f64).sqrt();
+    let ratios: Vec<f64> = standard_errors.iter().map(|se| se.iter().sum::<f64>() / replications as f64 / sd).collect();
+
+    // the long-run variance of x * u is about (1 + 0.49) / (1 - 0.49) times its variance, which
+    // HC1 misses and the HAC estimators largely recover
+    assert!(ratios[0] < 0.75);
+    for ratio in &ratios[1..] {
+        assert!(*ratio > ratios[0] + 0.15 && *ratio > 0.7 && *ratio < 1.25);
+    }
+}
+
+#[test]
+fn test_hac_iv_with_exogenous_regressor_matches_ols() {
+    let mut rng = seeded_rng(12);
+    let (y, x) = autocorrelated_design(120, 0.6, &mut rng);
+    let w = random_vector_generate(120, Normal::new(0.0, 1.0).unwrap(), &mut rng);
+    let covariance = CovarianceType::Hac { kernel: HacKernel::Parzen, bandwidth: None };
+    let iv_fit = run_2sls(&y, &x, &w, &x, &covariance, true).unwrap();
+    let ols_fit = run_regression(&concatenate(Axis(1), &[x.view(), w.view()]).unwrap(), &y, &covariance, true).unwrap();
+    assert!((iv_fit.std_errors() - ols_fit.std_errors()).iter().all(|d| d.abs() < 1e-8));
+}
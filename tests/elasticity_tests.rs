@@ -0,0 +1,164 @@
+use marketing_iv_methods::simple_example::run_regressions::{run_elasticity_regressions, run_yxv_regression};
+use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::elasticity::{elasticity_at_means, elasticity_per_observation, DemandFit, DemandSpecification};
+use marketing_iv_methods::utils::linear_regression::{run_2sls, run_regression};
+use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
+use ndarray::{concatenate, Array1, Array2, Axis};
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand_distr::Normal;
+
+// Fixture: linear demand in price and an exogenous control, around a price of 10 and a quantity of 40
+fn demand_design(n: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>) {
+    let dist = Normal::new(0.0, 1.0).unwrap();
+    let price = random_vector_generate(n, dist, rng).mapv(|p| 10.0 + p);
+    let control = random_vector_generate(n, dist, rng);
+    let e = random_vector_generate(n, dist, rng);
+    let y = 40.0 - 2.0 * &price + 0.5 * &control + e;
+    (concatenate(Axis(1), &[price.view(), control.view()]).unwrap(), y)
+}
+
+// The linear elasticity at a point as a function of (slopes, intercept)
+fn linear_elasticity(params: &Array1<f64>, intercept: f64, point: &Array1<f64>) -> f64 {
+    params[0] * point[0] / (intercept + point.dot(params))
+}
+
+#[test]
+fn test_log_log_elasticity_is_the_coefficient() {
+    let mut rng = seeded_rng(1);
+    let (x, y) = demand_design(200, &mut rng);
+    let log_x = concatenate(Axis(1), &[x.column(0).mapv(f64::ln).insert_axis(Axis(1)).view(), x.column(1).insert_axis(Axis(1))]).unwrap();
+    let log_y = y.mapv(f64::ln);
+    let fit = run_regression(&log_x, &log_y, &CovarianceType::HC1, true).unwrap();
+    let elasticity = elasticity_at_means(&DemandFit::from(&fit), &log_x, 0, DemandSpecification::LogLog).unwrap();
+    assert_eq!(elasticity.estimates().len(), 1);
+    assert!((elasticity.estimates()[0] - fit.params()[0]).abs() < 1e-12);
+    assert!((elasticity.std_errors()[0] - fit.std_errors()[0]).abs() < 1e-12);
+    assert_eq!(elasticity.specification(), DemandSpecification::LogLog);
+}
+
+#[test]
+fn test_linear_elasticity_at_means_matches_formula() {
+    let mut rng = seeded_rng(2);
+    let (x, y) = demand_design(200, &mut rng);
+    let fit = run_regression(&x, &y, &CovarianceType::Classical, true).unwrap();
+    let elasticity = elasticity_at_means(&DemandFit::from(&fit), &x, 0, DemandSpecification::Linear).unwrap();
+    let means = x.mean_axis(Axis(0)).unwrap();
+    let expected = linear_elasticity(fit.params(), fit.intercept(), &means);
+    assert!((elasticity.estimates()[0] - expected).abs() < 1e-12);
+    // with an intercept the fitted demand at the means is the mean demand
+    let expected_mean = fit.params()[0] * means[0] / y.mean().unwrap();
+    assert!((elasticity.estimates()[0] - expected_mean).abs() < 1e-9);
+    assert!(elasticity.estimates()[0] < 0.0);
+}
+
+#[test]
+fn test_delta_method_matches_numerical_gradient() {
+    let mut rng = seeded_rng(3);
+    let (x, y) = demand_design(200, &mut rng);
+    let fit = run_regression(&x, &y, &CovarianceType::HC3, true).unwrap();
+    let elasticity = elasticity_at_means(&DemandFit::from(&fit), &x, 0, DemandSpecification::Linear).unwrap();
+    let means = x.mean_axis(Axis(0)).unwrap();
+
+    // central differences in (slopes, intercept), the order of the covariance
+    let h = 1e-6;
+    let mut gradient = Array1::zeros(3);
+    for j in 0..3 {
+        let shifted = |step: f64| {
+            let mut params = fit.params().clone();
+            let mut intercept = fit.intercept();
+            if j < 2 {
+                params[j] += step;
+            } else {
+                intercept += step;
+            }
+            linear_elasticity(&params, intercept, &means)
+        };
+        gradient[j] = (shifted(h) - shifted(-h)) / (2.0 * h);
+    }
+    let se = gradient.dot(&fit.covariance().dot(&gradient)).sqrt();
+    assert!((elasticity.std_errors()[0] - se).abs() < 1e-6 * se);
+}
+
+#[test]
+fn test_elasticity_per_observation() {
+    let mut rng = seeded_rng(4);
+    let (x, y) = demand_design(50, &mut rng);
+    let fit = run_regression(&x, &y, &CovarianceType::HC1, true).unwrap();
+    let demand = DemandFit::from(&fit);
+    let elasticities = elasticity_per_observation(&demand, &x, 0, DemandSpecification::Linear).unwrap();
+    assert_eq!(elasticities.estimates().len(), 50);
+    assert_eq!(elasticities.std_errors().len(), 50);
+    for (i, row) in x.outer_iter().enumerate() {
+        let expected = linear_elasticity(fit.params(), fit.intercept(), &row.to_owned());
+        assert!((elasticities.estimates()[i] - expected).abs() < 1e-12);
+        assert!(elasticities.std_errors()[i] > 0.0);
+    }
+
+    let constant = elasticity_per_observation(&demand, &x, 0, DemandSpecification::LogLog).unwrap();
+    assert!(constant.estimates().iter().all(|&e| e == fit.params()[0]));
+}
+
+#[test]
+fn test_conf_int_brackets_the_estimate() {
+    let mut rng = seeded_rng(5);
+    let (x, y) = demand_design(200, &mut rng);
+    let fit = run_regression(&x, &y, &CovarianceType::HC1, true).unwrap();
+    let elasticity = elasticity_at_means(&DemandFit::from(&fit), &x, 0, DemandSpecification::Linear).unwrap();
+    let intervals = elasticity.conf_int(0.95).unwrap();
+    let (estimate, se) = (elasticity.estimates()[0], elasticity.std_errors()[0]);
+    assert!((intervals[[0, 0]] - (estimate - 1.959964 * se)).abs() < 1e-5);
+    assert!((intervals[[0, 1]] - (estimate + 1.959964 * se)).abs() < 1e-5);
+    assert!(elasticity.conf_int(1.0).is_err());
+}
+
+#[test]
+fn test_elasticity_errors() {
+    let mut rng = seeded_rng(6);
+    let (x, y) = demand_design(50, &mut rng);
+    let fit = run_regression(&x, &y, &CovarianceType::Classical, true).unwrap();
+    let demand = DemandFit::from(&fit);
+    let one_column = x.column(0).insert_axis(Axis(1)).to_owned();
+    assert!(elasticity_at_means(&demand, &one_column, 0, DemandSpecification::Linear).is_err());
+    assert!(elasticity_at_means(&demand, &x, 2, DemandSpecification::Linear).is_err());
+
+    let mut bad_covariance = demand.clone();
+    bad_covariance.covariance = Array2::eye(4);
+    assert!(elasticity_at_means(&bad_covariance, &x, 0, DemandSpecification::Linear).is_err());
+
+    let zero_demand = DemandFit { params: Array1::from(vec![-1.0, 0.0]), intercept: 0.0, covariance: Array2::eye(2) };
+    let origin = Array2::zeros((1, 2));
+    assert!(elasticity_per_observation(&zero_demand, &origin, 0, DemandSpecification::Linear).is_err());
+}
+
+#[test]
+fn test_fit_covariance_matches_std_errors() {
+    let mut rng = seeded_rng(7);
+    let (x, y) = demand_design(200, &mut rng);
+    let none = Array2::zeros((200, 0));
+    let z = x.column(1).insert_axis(Axis(1)).to_owned() + x.column(0).insert_axis(Axis(1));
+    let price = x.column(0).insert_axis(Axis(1)).to_owned();
+    let fit = run_2sls(&y, &price, &none, &z, &CovarianceType::HC1, true).unwrap();
+    assert_eq!(fit.covariance().dim(), (2, 2));
+    assert!((fit.covariance()[[0, 0]].sqrt() - fit.std_errors()[0]).abs() < 1e-12);
+    let ols = run_regression(&x, &y, &CovarianceType::HC1, true).unwrap();
+    assert_eq!(ols.covariance().dim(), (3, 3));
+    assert!((ols.covariance()[[1, 1]].sqrt() - ols.std_errors()[1]).abs() < 1e-12);
+}
+
+#[test]
+fn test_iv_elasticity_recovers_the_truth() {
+    let mut rng = seeded_rng(8);
+    let params = SimulationParams::builder().n(5000).alpha_y(1.0).alpha_x(2.0).sigma_ex(0.5).pi(vec![1.0]).build().unwrap();
+    let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::HC1, &mut rng).unwrap();
+    for (specification, price_level, quantity_level) in
+        [(DemandSpecification::Linear, 10.0, 20.0), (DemandSpecification::LogLog, 10f64.ln(), 20f64.ln())]
+    {
+        let (ols, iv, truth, implied_bias) =
+            run_elasticity_regressions(&generated_data, -0.5, specification, price_level, quantity_level, &CovarianceType::HC1).unwrap();
+        assert!((iv.estimates()[0] - truth).abs() < 4.0 * iv.std_errors()[0] + 0.01);
+        // the OLS elasticity is off by about the bias implied by the OLS coefficient bias
+        assert!((ols.estimates()[0] - truth - implied_bias).abs() < 0.1 * implied_bias.abs() + 0.05);
+        assert!(implied_bias > 0.0);
+    }
+}
@@ -0,0 +1,115 @@
+use marketing_iv_methods::utils::error_distribution::{error_vector_generate, ErrorDistribution};
+use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
+use ndarray::Array2;
+use ndarray_rand::rand_distr::Normal;
+
+fn shapes() -> Vec<ErrorDistribution> {
+    vec![
+        ErrorDistribution::Normal,
+        ErrorDistribution::StudentT { df: 5.0 },
+        ErrorDistribution::SkewNormal { shape: 4.0 },
+        ErrorDistribution::LogNormal { log_sd: 0.5 },
+        ErrorDistribution::Laplace,
+        ErrorDistribution::NormalMixture { weights: vec![0.7, 0.3], means: vec![-1.0, 2.0], std_devs: vec![0.5, 1.5] },
+    ]
+}
+
+// Fixture: the sample mean, standard deviation and skewness of a column of draws
+fn moments(draws: &Array2<f64>) -> (f64, f64, f64) {
+    let column = draws.column(0);
+    let mean = column.mean().unwrap();
+    let sd = column.std(1.0);
+    let skewness = column.iter().map(|d| ((d - mean) / sd).powi(3)).sum::<f64>() / column.len() as f64;
+    (mean, sd, skewness)
+}
+
+#[test]
+fn test_every_shape_is_standardized() {
+    let mut rng = seeded_rng(1);
+    for distribution in shapes() {
+        let draws = error_vector_generate(400000, &distribution, 2.0, &mut rng).unwrap();
+        let (mean, sd, _) = moments(&draws);
+        assert!(mean.abs() < 2e-2, "Expected mean 0 for {} but got {}", distribution, mean);
+        assert!((sd - 2.0).abs() < 3e-2, "Expected standard deviation 2 for {} but got {}", distribution, sd);
+    }
+}
+
+#[test]
+fn test_skewed_shapes_have_the_right_sign() {
+    let mut rng = seeded_rng(2);
+    let right = error_vector_generate(200000, &ErrorDistribution::SkewNormal { shape: 4.0 }, 1.0, &mut rng).unwrap();
+    let left = error_vector_generate(200000, &ErrorDistribution::SkewNormal { shape: -4.0 }, 1.0, &mut rng).unwrap();
+    let log_normal = error_vector_generate(200000, &ErrorDistribution::LogNormal { log_sd: 0.5 }, 1.0, &mut rng).unwrap();
+    let symmetric = error_vector_generate(200000, &ErrorDistribution::Laplace, 1.0, &mut rng).unwrap();
+    assert!(moments(&right).2 > 0.5);
+    assert!(moments(&left).2 < -0.5);
+    assert!(moments(&log_normal).2 > 1.0);
+    assert!(moments(&symmetric).2.abs() < 0.1);
+}
+
+#[test]
+fn test_heavy_tails_have_excess_kurtosis() {
+    let mut rng = seeded_rng(3);
+    for (distribution, expected) in [(ErrorDistribution::Laplace, 6.0), (ErrorDistribution::StudentT { df: 8.0 }, 4.5)] {
+        let draws = error_vector_generate(400000, &distribution, 1.0, &mut rng).unwrap();
+        let (mean, sd, _) = moments(&draws);
+        let kurtosis = draws.iter().map(|d| ((d - mean) / sd).powi(4)).sum::<f64>() / draws.len() as f64;
+        assert!((kurtosis - expected).abs() < 0.3, "Expected kurtosis {} for {} but got {}", expected, distribution, kurtosis);
+    }
+}
+
+#[test]
+fn test_normal_shape_matches_normal_draws() {
+    let shaped = error_vector_generate(100, &ErrorDistribution::Normal, 1.5, &mut seeded_rng(4)).unwrap();
+    let normal = random_vector_generate(100, Normal::new(0.0, 1.5).unwrap(), &mut seeded_rng(4));
+    assert!(shaped.iter().zip(normal.iter()).all(|(a, b)| (a - b).abs() < 1e-12));
+}
+
+#[test]
+fn test_invalid_distributions() {
+    let invalid = [
+        (ErrorDistribution::StudentT { df: 2.0 }, "greater than 2"),
+        (ErrorDistribution::SkewNormal { shape: f64::NAN }, "shape must be finite"),
+        (ErrorDistribution::LogNormal { log_sd: 0.0 }, "log_sd must be positive"),
+        (ErrorDistribution::NormalMixture { weights: vec![], means: vec![], std_devs: vec![] }, "at least one component"),
+        (ErrorDistribution::NormalMixture { weights: vec![1.0], means: vec![0.0, 1.0], std_devs: vec![1.0] }, "as many means"),
+        (ErrorDistribution::NormalMixture { weights: vec![0.5, 0.6], means: vec![0.0, 1.0], std_devs: vec![1.0, 1.0] }, "sum to one"),
+        (ErrorDistribution::NormalMixture { weights: vec![0.5, 0.5], means: vec![0.0, 1.0], std_devs: vec![1.0, 0.0] }, "standard deviations must be positive"),
+    ];
+    for (distribution, expected) in invalid {
+        let message = distribution.validate().unwrap_err();
+        assert!(message.contains(expected), "Expected '{}' in '{}'", expected, message);
+        assert!(error_vector_generate(10, &distribution, 1.0, &mut seeded_rng(5)).is_err());
+    }
+}
+
+#[test]
+fn test_errors_on_invalid_sigma() {
+    let mut rng = seeded_rng(6);
+    let result = error_vector_generate(10, &ErrorDistribution::Laplace, -1.0, &mut rng);
+    assert!(result.unwrap_err().contains("standard deviation must be positive"));
+}
+
+#[test]
+fn test_quantile_inverts_cdf() {
+    for distribution in shapes() {
+        let standardized = distribution.standardized().unwrap();
+        for p in [0.001, 0.1, 0.5, 0.75, 0.999] {
+            let x = standardized.quantile(p);
+            assert!((standardized.cdf(x) - p).abs() < 1e-8, "Expected cdf {} for {} but got {}", p, distribution, standardized.cdf(x));
+        }
+    }
+}
+
+#[test]
+fn test_cdf_matches_draws() {
+    let mut rng = seeded_rng(7);
+    for distribution in shapes() {
+        let standardized = distribution.standardized().unwrap();
+        let draws = error_vector_generate(100000, &distribution, 1.0, &mut rng).unwrap();
+        for x in [-1.0, 0.0, 0.5, 2.0] {
+            let share = draws.iter().filter(|d| **d <= x).count() as f64 / draws.len() as f64;
+            assert!((share - standardized.cdf(x)).abs() < 1e-2, "Expected cdf {} at {} for {} but got {}", standardized.cdf(x), x, distribution, share);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::first_stage::{
+    run_first_stage_diagnostics, stock_yogo_bias_critical_value, stock_yogo_size_critical_value,
+};
+use marketing_iv_methods::utils::linear_regression::run_regression;
+use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
+use ndarray::{concatenate, s, Array2, Axis};
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand_distr::Normal;
+
+// Fixture: one endogenous regressor on `n_instruments` instruments with heteroskedastic errors
+fn first_stage_design(n: usize, n_instruments: usize, strength: f64, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+    let dist = Normal::new(0.0, 1.0).unwrap();
+    let columns: Vec<Array2<f64>> = (0..n_instruments).map(|_| random_vector_generate(n, dist, rng)).collect();
+    let views: Vec<_> = columns.iter().map(|column| column.view()).collect();
+    let z = concatenate(Axis(1), &views).unwrap();
+    let w = random_vector_generate(n, dist, rng);
+    let e = random_vector_generate(n, dist, rng);
+    let x = strength * z.sum_axis(Axis(1)).insert_axis(Axis(1)) + 0.5 * &w + &e * &z.column(0).mapv(|v| 1.0 + v.abs()).insert_axis(Axis(1));
+    (x, w, z)
+}
+
+#[test]
+fn test_cragg_donald_equals_first_stage_f_with_one_regressor() {
+    let mut rng = seeded_rng(1);
+    let (x, w, z) = first_stage_design(300, 3, 0.2, &mut rng);
+    let report = run_first_stage_diagnostics(&x, &w, &z, true).unwrap();
+    assert!((report.cragg_donald_f() - report.f_statistics()[0]).abs() < 1e-8 * report.f_statistics()[0]);
+    assert_eq!(report.f_df(), (3, 300 - 5));
+    assert!(report.partial_r_squared()[0] > 0.0 && report.partial_r_squared()[0] < 1.0);
+}
+
+#[test]
+fn test_kleibergen_paap_equals_robust_wald_with_one_instrument() {
+    let mut rng = seeded_rng(2);
+    let (x, w, z) = first_stage_design(300, 1, 0.2, &mut rng);
+    let report = run_first_stage_diagnostics(&x, &w, &z, true).unwrap();
+
+    // with a single instrument the rank test is the squared HC1 t statistic of the first stage
+    let regressors = concatenate(Axis(1), &[z.view(), w.view()]).unwrap();
+    let fit = run_regression(&regressors, &x, &CovarianceType::HC1, true).unwrap();
+    let wald = (fit.params()[0] / fit.std_errors()[0]).powi(2);
+    assert!((report.kleibergen_paap_f() - wald).abs() < 1e-6 * wald);
+}
+
+#[test]
+fn test_effective_f_with_one_instrument() {
+    let mut rng = seeded_rng(3);
+    let (x, w, z) = first_stage_design(400, 1, 0.3, &mut rng);
+    let report = run_first_stage_diagnostics(&x, &w, &z, true).unwrap();
+    let effective_f = report.effective_f().unwrap();
+    assert!((effective_f - report.kleibergen_paap_f()).abs() < 1e-8 * effective_f);
+    assert!((report.effective_f_critical_value().unwrap() - 23.109).abs() < 0.01);
+}
+
+#[test]
+fn test_weak_design_has_small_statistics() {
+    let mut rng = seeded_rng(4);
+    let (x, w, z) = first_stage_design(200, 4, 0.0, &mut rng);
+    let report = run_first_stage_diagnostics(&x, &w, &z, false).unwrap();
+    assert!(report.f_statistics()[0] < stock_yogo_bias_critical_value(1, 4, 0.10).unwrap());
+    assert!(report.effective_f().unwrap() < report.effective_f_critical_value().unwrap());
+    assert!(format!("{}", report).contains("maximal relative bias: 5%: 16.85"));
+}
+
+#[test]
+fn test_two_endogenous_regressors() {
+    let mut rng = seeded_rng(5);
+    let (x, w, z) = first_stage_design(300, 4, 0.5, &mut rng);
+    let noise = random_vector_generate(300, Normal::new(0.0, 1.0).unwrap(), &mut rng);
+    let second = &z.slice(s![.., 1..2]) - &z.slice(s![.., 2..3]) + &noise;
+    let x_two = concatenate(Axis(1), &[x.view(), second.view()]).unwrap();
+    let report = run_first_stage_diagnostics(&x_two, &w, &z, true).unwrap();
+    assert_eq!(report.n_endog(), 2);
+    assert!(report.effective_f().is_none());
+    assert!(report.cragg_donald_f() <= report.f_statistics().iter().cloned().fold(f64::INFINITY, f64::min) + 1e-8);
+    assert!(report.kleibergen_paap_f() > 0.0);
+}
+
+#[test]
+fn test_stock_yogo_lookup() {
+    assert_eq!(stock_yogo_bias_critical_value(1, 10, 0.05).unwrap(), 20.74);
+    assert_eq!(stock_yogo_bias_critical_value(2, 4, 0.30).unwrap(), 4.73);
+    assert_eq!(stock_yogo_size_critical_value(1, 2, 0.25).unwrap(), 7.25);
+    assert_eq!(stock_yogo_size_critical_value(2, 3, 0.15).unwrap(), 8.18);
+    assert!(stock_yogo_bias_critical_value(1, 2, 0.10).is_err());
+    assert!(stock_yogo_size_critical_value(1, 3, 0.05).is_err());
+}
+
+#[test]
+fn test_first_stage_errors() {
+    let mut rng = seeded_rng(6);
+    let (x, w, z) = first_stage_design(50, 1, 0.5, &mut rng);
+    let x_two = concatenate(Axis(1), &[x.view(), w.view()]).unwrap();
+    assert!(run_first_stage_diagnostics(&x_two, &Array2::zeros((50, 0)), &z, true).is_err());
+    assert!(run_first_stage_diagnostics(&Array2::zeros((50, 0)), &w, &z, true).is_err());
+    assert!(run_first_stage_diagnostics(&x, &w.slice(s![..40, ..]).to_owned(), &z, true).is_err());
+}
@@ -0,0 +1,156 @@
+use marketing_iv_methods::utils::covariance::{CovarianceType, HacKernel};
+use marketing_iv_methods::utils::gmm::{run_gmm, GmmWeighting};
+use marketing_iv_methods::utils::linear_regression::run_2sls;
+use marketing_iv_methods::utils::overid::{run_overid_test, OveridStatistic};
+use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+use ndarray::{Array1, Array2, Axis};
+use ndarray_rand::rand::rngs::StdRng;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: over-identified design with three instruments and heteroskedastic errors
+fn heteroskedastic_design(n: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+    let design = IvDesign::new(n).pi(vec![1.0, 0.5, 0.5]).alpha_x(2.0).alpha_y(1.5);
+    let data = design.heteroskedastic().intercept(1.0).generate(rng);
+    (data.y, data.x, data.z)
+}
+
+#[test]
+fn test_one_step_matches_2sls() {
+    let mut rng = seeded_rng(1);
+    let (y, x, z) = heteroskedastic_design(500, &mut rng);
+    let controls = Array2::zeros((500, 0));
+    let gmm_fit = run_gmm(&y, &x, &controls, &z, GmmWeighting::OneStep, &CovarianceType::HC0, true).unwrap();
+    let tsls_fit = run_2sls(&y, &x, &controls, &z, &CovarianceType::Classical, true).unwrap();
+    assert!((gmm_fit.params()[0] - tsls_fit.params()[0]).abs() < 1e-8);
+    assert!((gmm_fit.intercept() - tsls_fit.intercept()).abs() < 1e-8);
+    assert_eq!(gmm_fit.iterations(), 0);
+}
+
+#[test]
+fn test_one_step_std_errors_match_robust_2sls() {
+    let mut rng = seeded_rng(9);
+    let (y, x, z) = heteroskedastic_design(500, &mut rng);
+    let controls = Array2::zeros((500, 0));
+    for covariance in [CovarianceType::HC0, CovarianceType::HC1] {
+        let gmm_fit = run_gmm(&y, &x, &controls, &z, GmmWeighting::OneStep, &covariance, true).unwrap();
+        let tsls_fit = run_2sls(&y, &x, &controls, &z, &covariance, true).unwrap();
+        assert!((gmm_fit.std_errors()[0] - tsls_fit.std_errors()[0]).abs() < 1e-10);
+        assert!((gmm_fit.intercept_std_error().unwrap() - tsls_fit.intercept_std_error().unwrap()).abs() < 1e-10);
+        assert_eq!(gmm_fit.covariance().shape(), &[2, 2]);
+    }
+
+    // without an intercept there is no intercept standard error
+    let two_step = run_gmm(&y, &x, &controls, &z, GmmWeighting::TwoStep, &CovarianceType::HC0, false).unwrap();
+    assert!(two_step.std_errors()[0] > 0.0 && two_step.intercept_std_error().is_none());
+}
+
+#[test]
+fn test_two_step_recovers_beta() {
+    let mut rng = seeded_rng(2);
+    let (y, x, z) = heteroskedastic_design(50000, &mut rng);
+    let fit = run_gmm(&y, &x, &Array2::zeros((50000, 0)), &z, GmmWeighting::TwoStep, &CovarianceType::HC0, true).unwrap();
+    assert!((fit.params()[0] + 0.5).abs() < 1e-1, "Expected beta close to -0.5, got {}", fit.params()[0]);
+    assert!((fit.intercept() - 1.0).abs() < 1e-1, "Expected intercept close to 1.0, got {}", fit.intercept());
+    assert_eq!(fit.j_df(), 2);
+    assert_eq!(fit.weighting_matrix().shape(), &[4, 4]);
+    assert!(fit.j_p_value() > 0.0 && fit.j_p_value() <= 1.0);
+}
+
+#[test]
+fn test_iterated_converges() {
+    let mut rng = seeded_rng(3);
+    let (y, x, z) = heteroskedastic_design(2000, &mut rng);
+    let weighting = GmmWeighting::Iterated { max_iter: 100, tol: 1e-10 };
+    let fit = run_gmm(&y, &x, &Array2::zeros((2000, 0)), &z, weighting, &CovarianceType::HC0, true).unwrap();
+    assert!(fit.iterations() > 1 && fit.iterations() < 100, "Expected convergence, got {} iterations", fit.iterations());
+}
+
+#[test]
+fn test_iterated_rejects_zero_iterations() {
+    let mut rng = seeded_rng(3);
+    let (y, x, z) = heteroskedastic_design(200, &mut rng);
+    let controls = Array2::zeros((200, 0));
+    let weighting = GmmWeighting::Iterated { max_iter: 0, tol: 1e-10 };
+    assert!(run_gmm(&y, &x, &controls, &z, weighting, &CovarianceType::HC0, true).is_err());
+
+    // a single update is two-step GMM
+    let weighting = GmmWeighting::Iterated { max_iter: 1, tol: 1e-10 };
+    let iterated = run_gmm(&y, &x, &controls, &z, weighting, &CovarianceType::HC0, true).unwrap();
+    let two_step = run_gmm(&y, &x, &controls, &z, GmmWeighting::TwoStep, &CovarianceType::HC0, true).unwrap();
+    assert_eq!(iterated.iterations(), 1);
+    assert!((iterated.params()[0] - two_step.params()[0]).abs() < 1e-12);
+    assert!((iterated.j_statistic() - two_step.j_statistic()).abs() < 1e-10);
+}
+
+#[test]
+fn test_cue_close_to_two_step() {
+    let mut rng = seeded_rng(4);
+    let (y, x, z) = heteroskedastic_design(2000, &mut rng);
+    let controls = Array2::zeros((2000, 0));
+    let two_step = run_gmm(&y, &x, &controls, &z, GmmWeighting::TwoStep, &CovarianceType::HC0, true).unwrap();
+    let cue = run_gmm(&y, &x, &controls, &z, GmmWeighting::ContinuouslyUpdated, &CovarianceType::HC0, true).unwrap();
+    assert!((cue.params()[0] - two_step.params()[0]).abs() < 0.1, "Expected CUE close to two-step, got {} and {}", cue.params()[0], two_step.params()[0]);
+    assert!(cue.j_statistic() >= 0.0);
+    assert_eq!(cue.iterations(), 0);
+}
+
+#[test]
+fn test_j_detects_invalid_instrument() {
+    let mut rng = seeded_rng(5);
+    let (y, x, z) = heteroskedastic_design(5000, &mut rng);
+    // make the third instrument enter y directly
+    let y_invalid = &y + &(2.0 * &z.column(2).insert_axis(Axis(1)));
+    let fit = run_gmm(&y_invalid, &x, &Array2::zeros((5000, 0)), &z, GmmWeighting::TwoStep, &CovarianceType::HC0, true).unwrap();
+    assert!(fit.j_p_value() < 0.01, "Expected J to reject, got p-value {}", fit.j_p_value());
+}
+
+#[test]
+fn test_exactly_identified_has_no_j_p_value() {
+    let mut rng = seeded_rng(6);
+    let (y, x, z) = heteroskedastic_design(500, &mut rng);
+    let z1 = z.column(0).insert_axis(Axis(1)).to_owned();
+    let fit = run_gmm(&y, &x, &Array2::zeros((500, 0)), &z1, GmmWeighting::TwoStep, &CovarianceType::HC0, true).unwrap();
+    assert_eq!(fit.j_df(), 0);
+    assert!(fit.j_statistic().abs() < 1e-8);
+    assert!(fit.j_p_value().is_nan());
+}
+
+#[test]
+fn test_classical_moment_covariance_gives_2sls_and_sargan() {
+    let mut rng = seeded_rng(7);
+    let (y, x, z) = heteroskedastic_design(500, &mut rng);
+    let controls = Array2::zeros((500, 0));
+    let fit = run_gmm(&y, &x, &controls, &z, GmmWeighting::TwoStep, &CovarianceType::Classical, true).unwrap();
+    let tsls_fit = run_2sls(&y, &x, &controls, &z, &CovarianceType::Classical, true).unwrap();
+    assert!((fit.params()[0] - tsls_fit.params()[0]).abs() < 1e-8);
+    let sargan = run_overid_test(&y, &x, &controls, &z, OveridStatistic::Sargan, true).unwrap();
+    assert!((fit.j_statistic() - sargan.statistic()).abs() < 1e-8);
+    assert_eq!(fit.covariance_type(), &CovarianceType::Classical);
+}
+
+#[test]
+fn test_cluster_and_hac_moment_covariances() {
+    let mut rng = seeded_rng(8);
+    let n = 500;
+    let (y, x, z) = heteroskedastic_design(n, &mut rng);
+    let controls = Array2::zeros((n, 0));
+    let fit = |covariance: CovarianceType| run_gmm(&y, &x, &controls, &z, GmmWeighting::TwoStep, &covariance, true).unwrap();
+    let hc0 = fit(CovarianceType::HC0);
+
+    // one observation per cluster, or a Bartlett kernel with no weight on the first lag, scales
+    // the HC0 moment covariance by n / (n - p), which leaves the estimate unchanged and shrinks J
+    let scale = (n - 2) as f64 / n as f64;
+    let singletons = fit(CovarianceType::Cluster(Array1::from_iter(0..n)));
+    let no_lags = fit(CovarianceType::Hac { kernel: HacKernel::Bartlett, bandwidth: Some(1.0) });
+    for other in [singletons, no_lags] {
+        assert!((other.params()[0] - hc0.params()[0]).abs() < 1e-10);
+        assert!((other.j_statistic() - scale * hc0.j_statistic()).abs() < 1e-8);
+    }
+
+    let automatic = fit(CovarianceType::Hac { kernel: HacKernel::QuadraticSpectral, bandwidth: None });
+    assert!((automatic.params()[0] + 0.5).abs() < 0.2, "Expected beta close to -0.5, got {}", automatic.params()[0]);
+    let result = run_gmm(&y, &x, &controls, &z, GmmWeighting::TwoStep, &CovarianceType::Cluster(Array1::zeros(n)), true);
+    assert!(result.is_err());
+}
@@ -0,0 +1,100 @@
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::hausman::{run_hausman, HausmanMethod};
+use marketing_iv_methods::utils::linear_regression::{run_2sls, run_regression};
+use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+use ndarray::{concatenate, Array2, Axis};
+use ndarray_rand::rand::rngs::StdRng;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: x loads on the confounder v with weight `alpha_x`, and the errors of y are heteroskedastic
+fn hausman_design(n: usize, alpha_x: f64, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>, Array2<f64>) {
+    let design = IvDesign::new(n).alpha_x(alpha_x).w_x(0.5).w_y(0.8);
+    let data = design.heteroskedastic().intercept(1.0).generate(rng);
+    (data.y, data.x, data.w, data.z)
+}
+
+#[test]
+fn test_hausman_rejects_endogenous_regressor() {
+    let mut rng = seeded_rng(1);
+    let (y, x, w, z) = hausman_design(2000, 1.0, &mut rng);
+    for method in [HausmanMethod::Regression, HausmanMethod::Contrast] {
+        for covariance in [CovarianceType::Classical, CovarianceType::HC1, CovarianceType::HC3] {
+            let test = run_hausman(&y, &x, &w, &z, method, &covariance, true).unwrap();
+            assert!(test.p_value() < 0.01);
+            assert_eq!(test.df(), 1);
+            assert_eq!(test.method(), method);
+        }
+    }
+}
+
+#[test]
+fn test_difference_is_2sls_minus_ols() {
+    let mut rng = seeded_rng(2);
+    let (y, x, w, z) = hausman_design(200, 1.0, &mut rng);
+    let test = run_hausman(&y, &x, &w, &z, HausmanMethod::Contrast, &CovarianceType::HC1, true).unwrap();
+    let tsls = run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, true).unwrap();
+    let ols = run_regression(&concatenate(Axis(1), &[x.view(), w.view()]).unwrap(), &y, &CovarianceType::Classical, true).unwrap();
+    assert!((test.difference()[0] - (tsls.params()[0] - ols.params()[0])).abs() < 1e-10);
+}
+
+#[test]
+fn test_classical_contrast_matches_durbin_form() {
+    let mut rng = seeded_rng(3);
+    // one regressor, no controls, no intercept: H = d^2 / (s^2 (1 / x'P_Z x - 1 / x'x))
+    let (y, x, _, z) = hausman_design(300, 0.5, &mut rng);
+    let none = Array2::zeros((300, 0));
+    let test = run_hausman(&y, &x, &none, &z, HausmanMethod::Contrast, &CovarianceType::Classical, false).unwrap();
+
+    let (x, y, z) = (x.column(0), y.column(0), z.column(0));
+    let xx = x.dot(&x);
+    let xpx = x.dot(&z).powi(2) / z.dot(&z);
+    let b_ols = x.dot(&y) / xx;
+    let b_iv = z.dot(&y) / z.dot(&x);
+    let resid = &y - &(b_ols * &x);
+    let s2 = resid.dot(&resid) / 299.0;
+    let expected = (b_iv - b_ols).powi(2) / (s2 * (1.0 / xpx - 1.0 / xx));
+    assert!((test.statistic() - expected).abs() < 1e-8 * expected);
+}
+
+#[test]
+fn test_regression_form_is_squared_t_statistic() {
+    let mut rng = seeded_rng(4);
+    let (y, x, w, z) = hausman_design(300, 0.5, &mut rng);
+    let test = run_hausman(&y, &x, &w, &z, HausmanMethod::Regression, &CovarianceType::HC1, true).unwrap();
+
+    let first_stage_x = concatenate(Axis(1), &[z.view(), w.view()]).unwrap();
+    let first_stage = run_regression(&first_stage_x, &x, &CovarianceType::Classical, true).unwrap();
+    let v_hat = first_stage.residuals().clone().insert_axis(Axis(1));
+    let augmented = concatenate(Axis(1), &[x.view(), w.view(), v_hat.view()]).unwrap();
+    let fit = run_regression(&augmented, &y, &CovarianceType::HC1, true).unwrap();
+    let t = fit.params()[2] / fit.std_errors()[2];
+    assert!((test.statistic() - t * t).abs() < 1e-6 * t * t);
+}
+
+#[test]
+fn test_robust_hausman_size_under_exogeneity() {
+    let mut rng = seeded_rng(5);
+    let replications = 200;
+    let mut rejections = [0; 2];
+    for _ in 0..replications {
+        let (y, x, w, z) = hausman_design(200, 0.0, &mut rng);
+        for (i, method) in [HausmanMethod::Regression, HausmanMethod::Contrast].iter().enumerate() {
+            let test = run_hausman(&y, &x, &w, &z, *method, &CovarianceType::HC1, true).unwrap();
+            rejections[i] += (test.p_value() < 0.05) as usize;
+        }
+    }
+    assert!(rejections.iter().all(|r| *r <= 22));
+}
+
+#[test]
+fn test_hausman_errors() {
+    let mut rng = seeded_rng(6);
+    let (y, x, w, z) = hausman_design(50, 1.0, &mut rng);
+    let none = Array2::zeros((50, 0));
+    let two = concatenate(Axis(1), &[x.view(), w.view()]).unwrap();
+    assert!(run_hausman(&y, &two, &none, &z, HausmanMethod::Contrast, &CovarianceType::Classical, true).is_err());
+    assert!(run_hausman(&y, &none, &w, &z, HausmanMethod::Regression, &CovarianceType::Classical, true).is_err());
+    assert!(run_hausman(&y, &x, &w, &none, HausmanMethod::Regression, &CovarianceType::HC0, true).is_err());
+}
@@ -0,0 +1,85 @@
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::jive::{run_jive, JiveVariant};
+use marketing_iv_methods::utils::linear_regression::run_2sls;
+use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+use ndarray::Array2;
+use ndarray_rand::rand::rngs::StdRng;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: one endogenous regressor, a control w and `n_instruments` weak instruments, only the
+// first of which moves x
+fn many_instrument_design(n: usize, n_instruments: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>, Array2<f64>) {
+    let mut pi = vec![0.0; n_instruments];
+    pi[0] = 0.5;
+    let data = IvDesign::new(n).pi(pi).w_x(0.5).w_y(0.8).alpha_y(2.0).intercept(1.0).generate(rng);
+    (data.y, data.x, data.w, data.z)
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let len = values.len();
+    (values[(len - 1) / 2] + values[len / 2]) / 2.0
+}
+
+#[test]
+fn test_jive_exact_fit() {
+    let z = Array2::from_shape_vec((6, 2), vec![1.0, 0.0, 2.0, 1.0, 3.0, 0.0, 4.0, 1.0, 5.0, 0.0, 6.0, 1.0]).unwrap();
+    let x = Array2::from_shape_vec((6, 1), vec![1.5, 1.0, 3.5, 4.0, 4.5, 6.5]).unwrap();
+    let y = -0.5 * &x + 2.0;
+    // UJIVE's instrument is only orthogonal to the constant in expectation, so it is left out
+    for variant in [JiveVariant::Jive1, JiveVariant::Jive2] {
+        let fit = run_jive(&y, &x, &Array2::zeros((6, 0)), &z, variant, true).unwrap();
+        assert!((fit.params()[0] + 0.5).abs() < 1e-10, "Expected beta = -0.5 for {:?}, got {}", variant, fit.params()[0]);
+        assert!((fit.intercept() - 2.0).abs() < 1e-10, "Expected intercept = 2.0 for {:?}, got {}", variant, fit.intercept());
+        assert_eq!(fit.variant(), variant);
+    }
+}
+
+#[test]
+fn test_ujive_equals_jive1_without_controls() {
+    let mut rng = seeded_rng(1);
+    let (y, x, _, z) = many_instrument_design(100, 5, &mut rng);
+    let jive1 = run_jive(&y, &x, &Array2::zeros((100, 0)), &z, JiveVariant::Jive1, false).unwrap();
+    let ujive = run_jive(&y, &x, &Array2::zeros((100, 0)), &z, JiveVariant::Ujive, false).unwrap();
+    assert!((jive1.params()[0] - ujive.params()[0]).abs() < 1e-10, "Expected UJIVE = JIVE1, got {} and {}", ujive.params()[0], jive1.params()[0]);
+}
+
+#[test]
+fn test_jive_recovers_control_coefficient() {
+    let mut rng = seeded_rng(2);
+    let (y, x, w, z) = many_instrument_design(20000, 3, &mut rng);
+    for variant in [JiveVariant::Jive1, JiveVariant::Jive2, JiveVariant::Ujive] {
+        let fit = run_jive(&y, &x, &w, &z, variant, true).unwrap();
+        assert!((fit.params()[0] + 0.5).abs() < 1e-1, "Expected beta close to -0.5 for {:?}, got {}", variant, fit.params()[0]);
+        assert!((fit.params()[1] - 0.8).abs() < 1e-1, "Expected control close to 0.8 for {:?}, got {}", variant, fit.params()[1]);
+        assert!((fit.intercept() - 1.0).abs() < 1e-1, "Expected intercept close to 1.0 for {:?}, got {}", variant, fit.intercept());
+    }
+}
+
+#[test]
+fn test_ujive_less_biased_than_2sls_with_many_instruments() {
+    let mut rng = seeded_rng(3);
+    let replications = 100;
+    let mut tsls = vec![];
+    let mut ujive = vec![];
+    for _ in 0..replications {
+        let (y, x, w, z) = many_instrument_design(200, 30, &mut rng);
+        tsls.push(run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, true).unwrap().params()[0]);
+        ujive.push(run_jive(&y, &x, &w, &z, JiveVariant::Ujive, true).unwrap().params()[0]);
+    }
+    let tsls_bias = (median(&mut tsls) + 0.5).abs();
+    let ujive_bias = (median(&mut ujive) + 0.5).abs();
+    assert!(ujive_bias < tsls_bias, "Expected UJIVE median bias {} below 2SLS median bias {}", ujive_bias, tsls_bias);
+}
+
+#[test]
+fn test_jive_errors_on_leverage_one() {
+    let mut rng = seeded_rng(4);
+    // a dummy instrument that is switched on for a single observation fits it exactly
+    let (y, x, _, _) = many_instrument_design(20, 1, &mut rng);
+    let z = Array2::from_shape_fn((20, 2), |(i, j)| if j == 0 { i as f64 } else if i == 0 { 1.0 } else { 0.0 });
+    let result = run_jive(&y, &x, &Array2::zeros((20, 0)), &z, JiveVariant::Jive1, true);
+    assert_eq!(result.unwrap_err(), "An observation has leverage one, so its leave-one-out fit is undefined");
+}
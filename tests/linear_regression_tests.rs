@@ -0,0 +1,158 @@
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::linear_regression::{run_2sls, run_fuller, run_k_class, run_liml, run_regression};
+use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
+use ndarray::{Array2, Axis, concatenate};
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand_distr::Normal;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: endogenous design where v drives both x and y, and z only moves x
+fn endogenous_design(n: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>, Array2<f64>) {
+    let data = IvDesign::new(n).alpha_x(2.0).alpha_y(3.0).w_y(0.8).intercept(1.0).generate(rng);
+    (data.y, data.x, data.w, data.z)
+}
+
+#[test]
+fn test_ols_matches_closed_form() {
+    let x = Array2::from_shape_vec((6, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+    let y = Array2::from_shape_vec((6, 1), vec![2.1, 3.9, 6.2, 7.8, 10.1, 12.0]).unwrap();
+    let fit = run_regression(&x, &y, &CovarianceType::Classical, true).unwrap();
+
+    // simple-regression formulas: b = Sxy / Sxx, se(b) = sqrt(s^2 / Sxx), se(a) = sqrt(s^2 (1/n + xbar^2 / Sxx))
+    assert!((fit.params()[0] - 1.9914285714285715).abs() < 1e-10);
+    assert!((fit.intercept() - 0.04666666666666597).abs() < 1e-10);
+    assert!((fit.std_errors()[0] - 0.039105647938376814).abs() < 1e-10);
+    assert!((fit.intercept_std_error().unwrap() - 0.1522946184001616).abs() < 1e-10);
+    assert!((fit.r_squared() - 0.9984599311490572).abs() < 1e-10);
+    assert!((fit.adj_r_squared() - 0.9980749139363214).abs() < 1e-10);
+    assert_eq!((fit.df_model(), fit.df_resid()), (1, 4));
+
+    // the 97.5% quantile of the t distribution with 4 degrees of freedom is 2.7764451
+    let ci = fit.conf_int(0.95).unwrap();
+    assert!((ci[[0, 1]] - ci[[0, 0]] - 2.0 * 2.7764451051977987 * 0.039105647938376814).abs() < 1e-8);
+    let (lower, upper) = fit.intercept_conf_int(0.95).unwrap().unwrap();
+    assert!(lower < 0.0 && upper > 0.0);
+    assert!(fit.p_values()[0] < 1e-5 && fit.intercept_p_value().unwrap() > 0.5);
+    assert!(fit.residuals().sum().abs() < 1e-10);
+}
+
+#[test]
+fn test_ols_without_intercept_uses_uncentered_r_squared() {
+    let x = Array2::from_shape_vec((4, 1), vec![1.0, 2.0, 3.0, 4.0]).unwrap();
+    let y = Array2::from_shape_vec((4, 1), vec![1.0, 2.0, 2.0, 5.0]).unwrap();
+    let fit = run_regression(&x, &y, &CovarianceType::Classical, false).unwrap();
+    let ssr = fit.residuals().dot(fit.residuals());
+    assert!((fit.r_squared() - (1.0 - ssr / 34.0)).abs() < 1e-10);
+    assert_eq!(fit.intercept(), 0.0);
+    assert!(fit.intercept_std_error().is_none() && fit.intercept_p_value().is_none());
+}
+
+#[test]
+fn test_ols_errors_on_rank_deficient_design() {
+    let mut rng = seeded_rng(1);
+    let (y, x, _, _) = endogenous_design(20, &mut rng);
+    let collinear = concatenate(Axis(1), &[x.view(), (2.0 * &x).view()]).unwrap();
+    let result = run_regression(&collinear, &y, &CovarianceType::Classical, true);
+    assert_eq!(result.unwrap_err(), "The design matrix is rank deficient");
+}
+
+#[test]
+fn test_2sls_exact_fit() {
+    let z = Array2::from_shape_vec((5, 1), vec![1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+    let x = Array2::from_shape_vec((5, 1), vec![1.5, 1.0, 3.5, 4.0, 4.5]).unwrap();
+    let y = -0.5 * &x + 2.0;
+    let fit = run_2sls(&y, &x, &Array2::zeros((5, 0)), &z, &CovarianceType::Classical, true).unwrap();
+    assert!((fit.params()[0] + 0.5).abs() < 1e-10, "Expected beta = -0.5, got {}", fit.params()[0]);
+    assert!((fit.intercept() - 2.0).abs() < 1e-10, "Expected intercept = 2.0, got {}", fit.intercept());
+}
+
+#[test]
+fn test_2sls_matches_ols_when_instrumenting_with_itself() {
+    let mut rng = seeded_rng(2);
+    let (y, x, w, _) = endogenous_design(200, &mut rng);
+    let iv_fit = run_2sls(&y, &x, &w, &x, &CovarianceType::Classical, true).unwrap();
+    let ols_fit = run_regression(&concatenate(Axis(1), &[x.view(), w.view()]).unwrap(), &y, &CovarianceType::Classical, true).unwrap();
+    for (iv, ols) in iv_fit.params().iter().zip(ols_fit.params().iter()) {
+        assert!((iv - ols).abs() < 1e-8, "Expected IV = OLS, got {} and {}", iv, ols);
+    }
+    assert!((iv_fit.intercept() - ols_fit.intercept()).abs() < 1e-8);
+}
+
+#[test]
+fn test_2sls_removes_endogeneity_bias() {
+    let mut rng = seeded_rng(3);
+    let (y, x, w, z) = endogenous_design(100000, &mut rng);
+    let iv_fit = run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, true).unwrap();
+    let ols_fit = run_regression(&concatenate(Axis(1), &[x.view(), w.view()]).unwrap(), &y, &CovarianceType::Classical, true).unwrap();
+
+    assert!((iv_fit.params()[0] + 0.5).abs() < 1e-1, "Expected 2SLS close to -0.5, got {}", iv_fit.params()[0]);
+    assert!((iv_fit.params()[1] - 0.8).abs() < 1e-1, "Expected control close to 0.8, got {}", iv_fit.params()[1]);
+    assert!((ols_fit.params()[0] + 0.5).abs() > 0.5, "Expected OLS to be biased, got {}", ols_fit.params()[0]);
+}
+
+#[test]
+fn test_2sls_errors_when_under_identified() {
+    let mut rng = seeded_rng(4);
+    let (y, x, w, _) = endogenous_design(20, &mut rng);
+    let x2 = concatenate(Axis(1), &[x.view(), w.view()]).unwrap();
+    let z = Array2::ones((20, 1));
+    let result = run_2sls(&y, &x2, &Array2::zeros((20, 0)), &z, &CovarianceType::Classical, false);
+    assert_eq!(result.unwrap_err(), "Model is under-identified: 1 instruments for 2 endogenous regressors");
+}
+
+#[test]
+fn test_2sls_errors_on_mismatched_rows() {
+    let mut rng = seeded_rng(5);
+    let (y, x, w, _) = endogenous_design(20, &mut rng);
+    let z = Array2::ones((10, 1));
+    let result = run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, true);
+    assert!(result.is_err(), "Expected error for mismatched rows, but got Ok");
+}
+
+#[test]
+fn test_k_class_nests_ols_and_2sls() {
+    let mut rng = seeded_rng(6);
+    let (y, x, w, z) = endogenous_design(500, &mut rng);
+    let ols_fit = run_regression(&concatenate(Axis(1), &[x.view(), w.view()]).unwrap(), &y, &CovarianceType::Classical, true).unwrap();
+    let k0_fit = run_k_class(&y, &x, &w, &z, 0.0, &CovarianceType::Classical, true).unwrap();
+    let tsls_fit = run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, true).unwrap();
+    let k1_fit = run_k_class(&y, &x, &w, &z, 1.0, &CovarianceType::Classical, true).unwrap();
+    for (k0, ols) in k0_fit.params().iter().zip(ols_fit.params().iter()) {
+        assert!((k0 - ols).abs() < 1e-8, "Expected k = 0 to equal OLS, got {} and {}", k0, ols);
+    }
+    assert_eq!(k1_fit.params(), tsls_fit.params());
+}
+
+#[test]
+fn test_liml_equals_2sls_when_just_identified() {
+    let mut rng = seeded_rng(7);
+    let (y, x, w, z) = endogenous_design(500, &mut rng);
+    let liml_fit = run_liml(&y, &x, &w, &z, &CovarianceType::Classical, true).unwrap();
+    let tsls_fit = run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, true).unwrap();
+    assert!((liml_fit.k() - 1.0).abs() < 1e-8, "Expected kappa = 1, got {}", liml_fit.k());
+    assert!((liml_fit.params()[0] - tsls_fit.params()[0]).abs() < 1e-6);
+}
+
+#[test]
+fn test_liml_and_fuller_kappa_over_identified() {
+    let mut rng = seeded_rng(8);
+    let (y, x, w, z) = endogenous_design(500, &mut rng);
+    let z2 = concatenate(Axis(1), &[z.view(), random_vector_generate(500, Normal::new(0.0, 1.0).unwrap(), &mut rng).view()]).unwrap();
+    let liml_fit = run_liml(&y, &x, &w, &z2, &CovarianceType::Classical, true).unwrap();
+    let fuller_fit = run_fuller(&y, &x, &w, &z2, 1.0, &CovarianceType::Classical, true).unwrap();
+    assert!(liml_fit.k() >= 1.0, "Expected kappa >= 1, got {}", liml_fit.k());
+    // n = 500 observations and L = 4 instruments (two excluded, one control, one intercept)
+    assert!((liml_fit.k() - fuller_fit.k() - 1.0 / 496.0).abs() < 1e-10);
+}
+
+#[test]
+fn test_fuller_errors_on_invalid_alpha() {
+    let mut rng = seeded_rng(9);
+    let (y, x, w, z) = endogenous_design(20, &mut rng);
+    for alpha in [-1.0, f64::NAN, f64::INFINITY] {
+        let result = run_fuller(&y, &x, &w, &z, alpha, &CovarianceType::Classical, true);
+        assert_eq!(result.unwrap_err(), "alpha must be non-negative and finite");
+    }
+}
@@ -0,0 +1,94 @@
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::gmm::{run_gmm, GmmWeighting};
+use marketing_iv_methods::utils::linear_regression::{run_2sls, run_regression};
+use marketing_iv_methods::utils::overid::{run_difference_in_sargan, run_overid_test, OveridStatistic};
+use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+use ndarray::{Array2, Axis};
+use ndarray_rand::rand::rngs::StdRng;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: x on three instruments, which enter y directly with weights `gamma`
+fn overid_design(n: usize, gamma: [f64; 3], rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+    let data = IvDesign::new(n).pi(vec![1.0; 3]).gamma(gamma.to_vec()).heteroskedastic().generate(rng);
+    (data.y, data.x, data.z)
+}
+
+#[test]
+fn test_hansen_j_matches_two_step_gmm() {
+    let mut rng = seeded_rng(1);
+    let (y, x, z) = overid_design(300, [0.0, 0.0, 0.2], &mut rng);
+    let none = Array2::zeros((300, 0));
+    let test = run_overid_test(&y, &x, &none, &z, OveridStatistic::HansenJ, true).unwrap();
+    let gmm = run_gmm(&y, &x, &none, &z, GmmWeighting::TwoStep, &CovarianceType::HC0, true).unwrap();
+    assert!((test.statistic() - gmm.j_statistic()).abs() < 1e-8 * gmm.j_statistic());
+    assert_eq!(test.df(), 2);
+    assert_eq!(test.kind(), OveridStatistic::HansenJ);
+}
+
+#[test]
+fn test_sargan_is_n_r_squared() {
+    let mut rng = seeded_rng(2);
+    let (y, x, z) = overid_design(300, [0.0, 0.0, 0.2], &mut rng);
+    let none = Array2::zeros((300, 0));
+    let test = run_overid_test(&y, &x, &none, &z, OveridStatistic::Sargan, true).unwrap();
+
+    let tsls = run_2sls(&y, &x, &none, &z, &CovarianceType::Classical, true).unwrap();
+    let resid = (&y - &(tsls.params()[0] * &x)).mapv(|e| e - tsls.intercept());
+    let auxiliary = run_regression(&z, &resid, &CovarianceType::Classical, true).unwrap();
+    let total = resid.iter().map(|e| e * e).sum::<f64>();
+    let unexplained = auxiliary.residuals().dot(auxiliary.residuals());
+    let expected = 300.0 * (total - unexplained) / total;
+    assert!((test.statistic() - expected).abs() < 1e-8 * expected);
+}
+
+#[test]
+fn test_difference_with_exactly_identified_restriction_equals_full_statistic() {
+    let mut rng = seeded_rng(3);
+    let (y, x, z) = overid_design(300, [0.0, 0.0, 0.2], &mut rng);
+    let none = Array2::zeros((300, 0));
+    for kind in [OveridStatistic::Sargan, OveridStatistic::HansenJ] {
+        let full = run_overid_test(&y, &x, &none, &z, kind, true).unwrap();
+        let difference = run_difference_in_sargan(&y, &x, &none, &z, &[1, 2], kind, true).unwrap();
+        assert!((difference.statistic() - full.statistic()).abs() < 1e-8 * full.statistic());
+        assert_eq!(difference.df(), 2);
+        let single = run_difference_in_sargan(&y, &x, &none, &z, &[2], kind, true).unwrap();
+        assert!(single.statistic() >= 0.0 && single.statistic() <= full.statistic() + 1e-10);
+    }
+}
+
+#[test]
+fn test_overid_power_depends_on_the_pattern_of_invalidity() {
+    let mut rng = seeded_rng(4);
+    let replications = 100;
+    let none = Array2::zeros((500, 0));
+    let mut rejections = [0; 3];
+    for _ in 0..replications {
+        // one invalid instrument is detected, instruments invalid in proportion to pi are not
+        let (y, x, z) = overid_design(500, [0.0, 0.0, 0.5], &mut rng);
+        let hansen = run_overid_test(&y, &x, &none, &z, OveridStatistic::HansenJ, true).unwrap();
+        let difference = run_difference_in_sargan(&y, &x, &none, &z, &[2], OveridStatistic::HansenJ, true).unwrap();
+        rejections[0] += (hansen.p_value() < 0.05) as usize;
+        rejections[1] += (difference.p_value() < 0.05) as usize;
+
+        let (y, x, z) = overid_design(500, [0.5, 0.5, 0.5], &mut rng);
+        let hansen = run_overid_test(&y, &x, &none, &z, OveridStatistic::HansenJ, true).unwrap();
+        rejections[2] += (hansen.p_value() < 0.05) as usize;
+    }
+    assert!(rejections[0] > 80 && rejections[1] > 80);
+    assert!(rejections[2] <= 15);
+}
+
+#[test]
+fn test_overid_errors() {
+    let mut rng = seeded_rng(5);
+    let (y, x, z) = overid_design(50, [0.0; 3], &mut rng);
+    let none = Array2::zeros((50, 0));
+    let one = z.column(0).to_owned().insert_axis(Axis(1));
+    assert!(run_overid_test(&y, &x, &none, &one, OveridStatistic::Sargan, true).is_err());
+    assert!(run_difference_in_sargan(&y, &x, &none, &z, &[], OveridStatistic::Sargan, true).is_err());
+    assert!(run_difference_in_sargan(&y, &x, &none, &z, &[3], OveridStatistic::Sargan, true).is_err());
+    assert!(run_difference_in_sargan(&y, &x, &none, &z, &[1, 1], OveridStatistic::Sargan, true).is_err());
+    assert!(run_difference_in_sargan(&y, &x, &none, &z, &[0, 1, 2], OveridStatistic::HansenJ, true).is_err());
+}
@@ -0,0 +1,93 @@
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::linear_regression::run_2sls;
+use marketing_iv_methods::utils::plausibly_exogenous::{gamma_box_grid, run_ltz, run_uci};
+use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+use ndarray::{Array1, Array2};
+use ndarray_rand::rand::rngs::StdRng;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: endogenous design where the instrument z also has a direct effect `gamma` on y
+fn leaky_design(n: usize, gamma: f64, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>, Array2<f64>) {
+    let design = IvDesign::new(n).alpha_x(2.0).alpha_y(3.0).w_x(0.5).w_y(0.8);
+    let data = design.gamma(vec![gamma]).intercept(1.0).generate(rng);
+    (data.y, data.x, data.w, data.z)
+}
+
+#[test]
+fn test_gamma_box_grid_values() {
+    let grid = gamma_box_grid(&[-1.0, 0.0], &[1.0, 0.5], 3).unwrap();
+    assert_eq!(grid.shape(), &[9, 2]);
+    assert_eq!(grid.row(0).to_vec(), vec![-1.0, 0.0]);
+    assert_eq!(grid.row(1).to_vec(), vec![0.0, 0.0]);
+    assert_eq!(grid.row(8).to_vec(), vec![1.0, 0.5]);
+}
+
+#[test]
+fn test_gamma_box_grid_errors_on_unordered_bounds() {
+    let result = gamma_box_grid(&[1.0], &[-1.0], 5);
+    assert_eq!(result.unwrap_err(), "Each lower bound must not exceed its upper bound");
+}
+
+#[test]
+fn test_gamma_box_grid_errors_on_oversized_grid() {
+    let result = gamma_box_grid(&[-1.0; 40], &[1.0; 40], 10);
+    assert_eq!(result.unwrap_err(), "A grid of 10 points in each of 40 dimensions is too large");
+    // the point count fits in a usize but the values would not fit in memory
+    let result = gamma_box_grid(&[0.0; 6], &[1.0; 6], 1000);
+    assert_eq!(result.unwrap_err(), "A grid of 1000 points in each of 6 dimensions is too large");
+}
+
+#[test]
+fn test_zero_support_and_zero_prior_give_2sls_interval() {
+    let mut rng = seeded_rng(1);
+    let (y, x, w, z) = leaky_design(500, 0.0, &mut rng);
+    let support = gamma_box_grid(&[0.0], &[0.0], 2).unwrap();
+    let uci = run_uci(&y, &x, &w, &z, &support, 0.95, true).unwrap();
+    let ltz = run_ltz(&y, &x, &w, &z, &Array1::zeros(1), &Array2::zeros((1, 1)), 0.95, true).unwrap();
+    let tsls = run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, true).unwrap();
+
+    assert!((uci.lower()[0] - ltz.lower()[0]).abs() < 1e-10);
+    assert!((uci.upper()[0] - ltz.upper()[0]).abs() < 1e-10);
+    let centre = (uci.lower()[0] + uci.upper()[0]) / 2.0;
+    assert!((centre - tsls.params()[0]).abs() < 1e-10, "Expected the interval to be centred on 2SLS, got {} and {}", centre, tsls.params()[0]);
+    assert_eq!(uci.lower().len(), 1);
+}
+
+#[test]
+fn test_bounds_widen_with_support() {
+    let mut rng = seeded_rng(2);
+    let (y, x, w, z) = leaky_design(500, 0.0, &mut rng);
+    let mut previous_width = 0.0;
+    for delta in [0.0, 0.1, 0.3] {
+        let support = gamma_box_grid(&[-delta], &[delta], 11).unwrap();
+        let uci = run_uci(&y, &x, &w, &z, &support, 0.95, true).unwrap();
+        let ltz = run_ltz(&y, &x, &w, &z, &Array1::zeros(1), &Array2::from_elem((1, 1), delta * delta / 3.0), 0.95, true).unwrap();
+        let width = uci.upper()[0] - uci.lower()[0];
+        assert!(width > previous_width, "Expected wider UCI bounds at delta = {}", delta);
+        assert!(ltz.upper()[0] - ltz.lower()[0] <= width + 1e-10, "Expected LTZ bounds inside UCI bounds at delta = {}", delta);
+        previous_width = width;
+    }
+}
+
+#[test]
+fn test_ltz_prior_mean_corrects_direct_effect() {
+    let mut rng = seeded_rng(3);
+    let (y, x, w, z) = leaky_design(20000, 0.3, &mut rng);
+    let naive = run_ltz(&y, &x, &w, &z, &Array1::zeros(1), &Array2::zeros((1, 1)), 0.95, true).unwrap();
+    let informed = run_ltz(&y, &x, &w, &z, &Array1::from_elem(1, 0.3), &Array2::from_elem((1, 1), 0.01), 0.95, true).unwrap();
+
+    assert!(naive.lower()[0] > -0.5, "Expected the exclusion-restricted interval to miss beta, got lower bound {}", naive.lower()[0]);
+    assert!(informed.lower()[0] < -0.5 && informed.upper()[0] > -0.5, "Expected the informed interval to cover beta, got [{}, {}]", informed.lower()[0], informed.upper()[0]);
+    assert_eq!(informed.confidence(), 0.95);
+}
+
+#[test]
+fn test_uci_errors_on_support_dimension() {
+    let mut rng = seeded_rng(4);
+    let (y, x, w, z) = leaky_design(50, 0.0, &mut rng);
+    let support = gamma_box_grid(&[-0.1, -0.1], &[0.1, 0.1], 3).unwrap();
+    let result = run_uci(&y, &x, &w, &z, &support, 0.95, true);
+    assert_eq!(result.unwrap_err(), "gamma_support has 2 columns but there are 1 instruments");
+}
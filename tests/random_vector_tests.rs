@@ -1,24 +1,30 @@
-use marketing_iv_methods::utils::random_vector_gen::random_vector_generate;
+use marketing_iv_methods::utils::random_vector_gen::{copula_generate, multivariate_normal_generate, random_vector_generate, seeded_rng, Copula};
+use ndarray::{array, Array1, Array2, Axis};
+use statrs::distribution::{ContinuousCDF, Exp, Normal as NormalCdf};
+use std::f64::consts::PI;
 use ndarray_rand::rand_distr::{Normal, Uniform};
 
 #[test]
 fn test_vector_size_normal() {
+    let mut rng = seeded_rng(1);
     let dist = Normal::new(0.0, 1.0).unwrap();
-    let vec = random_vector_generate(5, dist);
+    let vec = random_vector_generate(5, dist, &mut rng);
     assert_eq!(vec.shape(), &[5, 1])
 }
 
 #[test]
 fn test_vector_size_uniform() {
+    let mut rng = seeded_rng(2);
     let dist = Uniform::new(0.0, 1.0);
-    let vec = random_vector_generate(5, dist);
+    let vec = random_vector_generate(5, dist, &mut rng);
     assert_eq!(vec.shape(), &[5, 1])
 }
 
 #[test]
 fn test_vector_stats_normal() {
+    let mut rng = seeded_rng(3);
     let dist = Normal::new(0.0, 9.0).unwrap();
-    let vec = random_vector_generate(1000000, dist);
+    let vec = random_vector_generate(1000000, dist, &mut rng);
     let binding = vec.view();
     let flattened = binding.to_shape(vec.len()).unwrap();
 
@@ -35,8 +41,9 @@ fn test_vector_stats_normal() {
 
 #[test]
 fn test_vector_stats_uniform() {
+    let mut rng = seeded_rng(4);
     let dist = Uniform::new(0.0, 9.0);
-    let vec = random_vector_generate(1000000, dist);
+    let vec = random_vector_generate(1000000, dist, &mut rng);
     let binding = vec.view();
     let flattened = binding.to_shape(vec.len()).unwrap();
 
@@ -49,4 +56,156 @@ fn test_vector_stats_uniform() {
     // compare to population mean and standard deviation
     assert!((mean - 4.5).abs() < 1e-1, "Expected values to be close, but got mean = {}", mean);
     assert!((std - 2.59).abs() < 1e-1, "Expected values to be close, but got standard deviation = {}", std);    
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_same_seed_gives_identical_vectors() {
+    let dist = Normal::new(0.0, 1.0).unwrap();
+    let first = random_vector_generate(100, dist, &mut seeded_rng(11));
+    let second = random_vector_generate(100, dist, &mut seeded_rng(11));
+    assert_eq!(first, second);
+
+    // draws continue the stream rather than restarting it
+    let mut rng = seeded_rng(11);
+    let head = random_vector_generate(50, dist, &mut rng);
+    let tail = random_vector_generate(50, dist, &mut rng);
+    assert_eq!(head.column(0), first.slice(ndarray::s![..50, 0]));
+    assert_eq!(tail.column(0), first.slice(ndarray::s![50.., 0]));
+}
+
+#[test]
+fn test_multivariate_normal_moments() {
+    let mut rng = seeded_rng(12);
+    let mean = array![1.0, -2.0, 0.5];
+    let covariance = array![[4.0, 1.2, -0.6], [1.2, 1.0, 0.3], [-0.6, 0.3, 2.0]];
+    let draws = multivariate_normal_generate(200000, &mean, &covariance, &mut rng).unwrap();
+    assert_eq!(draws.shape(), &[200000, 3]);
+
+    let sample_mean = draws.mean_axis(Axis(0)).unwrap();
+    let centered = &draws - &sample_mean;
+    let sample_covariance = centered.t().dot(&centered) / (draws.nrows() - 1) as f64;
+    for j in 0..3 {
+        assert!((sample_mean[j] - mean[j]).abs() < 2e-2, "Expected mean {} but got {}", mean[j], sample_mean[j]);
+        for k in 0..3 {
+            assert!(
+                (sample_covariance[[j, k]] - covariance[[j, k]]).abs() < 5e-2,
+                "Expected covariance {} but got {} at ({}, {})", covariance[[j, k]], sample_covariance[[j, k]], j, k
+            );
+        }
+    }
+}
+
+#[test]
+fn test_multivariate_normal_errors() {
+    let mut rng = seeded_rng(13);
+    let mean: Array1<f64> = Array1::zeros(2);
+
+    let result = multivariate_normal_generate(10, &mean, &array![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], &mut rng);
+    assert!(result.unwrap_err().contains("must be 2 by 2"));
+
+    let result = multivariate_normal_generate(10, &mean, &array![[1.0, 0.5], [0.2, 1.0]], &mut rng);
+    assert!(result.unwrap_err().contains("symmetric"));
+
+    let result = multivariate_normal_generate(10, &mean, &array![[1.0, 2.0], [2.0, 1.0]], &mut rng);
+    assert!(result.unwrap_err().contains("positive definite"));
+}
+
+// Fixture: Kendall's tau between two columns
+fn kendall_tau(draws: &Array2<f64>, a: usize, b: usize) -> f64 {
+    let n = draws.nrows();
+    let mut concordance = 0.0;
+    for i in 0..n {
+        for j in (i + 1)..n {
+            concordance += ((draws[[i, a]] - draws[[j, a]]) * (draws[[i, b]] - draws[[j, b]])).signum();
+        }
+    }
+    concordance / (n * (n - 1) / 2) as f64
+}
+
+// Fixture: the share of rows where both columns are below their `q` quantile
+fn joint_lower_tail(draws: &Array2<f64>, q: f64) -> f64 {
+    let threshold = |j: usize| {
+        let mut column = draws.column(j).to_vec();
+        column.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        column[(q * column.len() as f64) as usize]
+    };
+    let (t0, t1) = (threshold(0), threshold(1));
+    draws.rows().into_iter().filter(|row| row[0] < t0 && row[1] < t1).count() as f64 / draws.nrows() as f64
+}
+
+#[test]
+fn test_copulas_match_kendall_tau() {
+    let mut rng = seeded_rng(14);
+    let uniform = |u: f64| u;
+    let marginals: [&dyn Fn(f64) -> f64; 3] = [&uniform, &uniform, &uniform];
+    let r = 0.6;
+    let correlation = array![[1.0, r, r], [r, 1.0, r], [r, r, 1.0]];
+    let gaussian_tau = 2.0 / PI * f64::asin(r);
+    let copulas = [
+        (Copula::Gaussian { correlation: correlation.clone() }, gaussian_tau),
+        (Copula::StudentT { correlation, df: 4.0 }, gaussian_tau),
+        (Copula::Clayton { theta: 2.0 }, 0.5),
+        (Copula::Gumbel { theta: 1.5 }, 1.0 - 1.0 / 1.5),
+        (Copula::Gumbel { theta: 1.0 }, 0.0),
+    ];
+    for (copula, expected) in copulas {
+        let draws = copula_generate(2000, &copula, &marginals, &mut rng).unwrap();
+        assert!(draws.iter().all(|u| *u > 0.0 && *u < 1.0));
+        for column in draws.columns() {
+            assert!((column.mean().unwrap() - 0.5).abs() < 2e-2, "Expected uniform marginals for {:?}", copula);
+        }
+        for (a, b) in [(0, 1), (0, 2), (1, 2)] {
+            let tau = kendall_tau(&draws, a, b);
+            assert!((tau - expected).abs() < 4e-2, "Expected tau {} for {:?} but got {}", expected, copula, tau);
+        }
+    }
+}
+
+#[test]
+fn test_copula_tail_dependence() {
+    let mut rng = seeded_rng(15);
+    let uniform = |u: f64| u;
+    let marginals: [&dyn Fn(f64) -> f64; 2] = [&uniform, &uniform];
+    let clayton = copula_generate(100000, &Copula::Clayton { theta: 2.0 }, &marginals, &mut rng).unwrap();
+    let gumbel = copula_generate(100000, &Copula::Gumbel { theta: 2.0 }, &marginals, &mut rng).unwrap();
+
+    // Clayton clusters in the lower tail, Gumbel in the upper tail, at the same Kendall's tau
+    let flipped = gumbel.mapv(|u| 1.0 - u);
+    assert!(joint_lower_tail(&clayton, 0.02) > 1.5 * joint_lower_tail(&gumbel, 0.02));
+    assert!(joint_lower_tail(&flipped, 0.02) > 1.5 * joint_lower_tail(&clayton.mapv(|u| 1.0 - u), 0.02));
+}
+
+#[test]
+fn test_copula_applies_the_marginals() {
+    let mut rng = seeded_rng(16);
+    let normal = NormalCdf::new(3.0, 2.0).unwrap();
+    let exponential = Exp::new(0.5).unwrap();
+    let marginals: [&dyn Fn(f64) -> f64; 2] = [&|u| normal.inverse_cdf(u), &|u| exponential.inverse_cdf(u)];
+    let draws = copula_generate(100000, &Copula::Clayton { theta: 1.0 }, &marginals, &mut rng).unwrap();
+    let (first, second) = (draws.column(0), draws.column(1));
+    assert!((first.mean().unwrap() - 3.0).abs() < 3e-2);
+    assert!((first.std(1.0) - 2.0).abs() < 3e-2);
+    assert!((second.mean().unwrap() - 2.0).abs() < 3e-2);
+    assert!(second.iter().all(|value| *value >= 0.0));
+}
+
+#[test]
+fn test_copula_errors() {
+    let mut rng = seeded_rng(17);
+    let uniform = |u: f64| u;
+    let marginals: [&dyn Fn(f64) -> f64; 2] = [&uniform, &uniform];
+    let invalid = [
+        (Copula::Gaussian { correlation: Array2::eye(3) }, "must be 2 by 2"),
+        (Copula::Gaussian { correlation: array![[2.0, 0.0], [0.0, 1.0]] }, "unit diagonal"),
+        (Copula::Gaussian { correlation: array![[1.0, 0.5], [0.4, 1.0]] }, "symmetric"),
+        (Copula::StudentT { correlation: array![[1.0, 1.0], [1.0, 1.0]], df: 3.0 }, "positive definite"),
+        (Copula::StudentT { correlation: Array2::eye(2), df: 0.0 }, "degrees of freedom"),
+        (Copula::Clayton { theta: -1.0 }, "Clayton theta"),
+        (Copula::Gumbel { theta: 0.5 }, "Gumbel theta"),
+    ];
+    for (copula, expected) in invalid {
+        let message = copula_generate(10, &copula, &marginals, &mut rng).unwrap_err();
+        assert!(message.contains(expected), "Expected '{}' in '{}'", expected, message);
+    }
+    assert!(copula_generate(10, &Copula::Clayton { theta: 1.0 }, &[], &mut rng).is_err());
+}
@@ -1,105 +1,411 @@
-use marketing_iv_methods::simple_example::generate_vector_data::{ind_var_generate, dep_var_generate, IndVars, DepVars};
+use marketing_iv_methods::simple_example::generate_vector_data::{ind_var_generate, dep_var_generate, joint_var_generate, IndVars, DepVars};
+use marketing_iv_methods::simple_example::run_regressions::{run_yxv_regression, run_other_regressions, run_iv_regression, run_k_class_regressions, run_jive_regressions};
+use marketing_iv_methods::simple_example::simulation_params::SimulationParams;
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::error_distribution::ErrorDistribution;
+use marketing_iv_methods::utils::random_vector_gen::{seeded_rng, Copula};
+use ndarray::Axis;
+use ndarray_rand::rand::rngs::StdRng;
 
-fn valid_input() -> (usize, f64, f64, f64) {
-    (10, 2.5, 1.0, 0.5)
+const NORMAL: ErrorDistribution = ErrorDistribution::Normal;
+
+fn valid_input() -> (usize, f64, Vec<f64>, f64, f64) {
+    (10, 2.5, vec![1.0, 0.5], 1.0, 0.5)
 }
 
-fn generate_valid_data() -> IndVars {
-    let (n, alpha_x, sigma_a, sigma_ex) = valid_input();
-    ind_var_generate(n, alpha_x, sigma_a, sigma_ex).unwrap()
+fn generate_valid_data(rng: &mut StdRng) -> IndVars {
+    let (n, alpha_x, pi, sigma_a, sigma_ex) = valid_input();
+    ind_var_generate(n, alpha_x, &pi, sigma_a, sigma_ex, &NORMAL, &NORMAL, rng).unwrap()
 }
 
-fn valid_input_y() -> (f64, f64, f64, IndVars) {
-    let (n, alpha_x, sigma_a, sigma_ex) = valid_input();
-    let ind_vars = ind_var_generate(n, alpha_x, sigma_a, sigma_ex).unwrap();
+fn valid_input_y(rng: &mut StdRng) -> (f64, f64, f64, IndVars) {
+    let (n, alpha_x, pi, sigma_a, sigma_ex) = valid_input();
+    let ind_vars = ind_var_generate(n, alpha_x, &pi, sigma_a, sigma_ex, &NORMAL, &NORMAL, rng).unwrap();
     (0.5, 1.5, 1.0, ind_vars)
 }
 
-fn generate_valid_data_y() -> DepVars {
-    let (beta, alpha_y, sigma_ey, ind_vars) = valid_input_y();
-    dep_var_generate(beta, alpha_y, sigma_ey, ind_vars).unwrap()
+fn generate_valid_data_y(rng: &mut StdRng) -> DepVars {
+    let (beta, alpha_y, sigma_ey, ind_vars) = valid_input_y(rng);
+    dep_var_generate(beta, alpha_y, sigma_ey, &NORMAL, ind_vars, rng).unwrap()
 }
 
 // Fixture: returns input with negative sigma_a
-fn input_with_negative_sigma_a() -> (usize, f64, f64, f64) {
-    let (n, alpha_x, _, sigma_ex) = valid_input();
-    (n, alpha_x, -1.0, sigma_ex)
+fn input_with_negative_sigma_a() -> (usize, f64, Vec<f64>, f64, f64) {
+    let (n, alpha_x, pi, _, sigma_ex) = valid_input();
+    (n, alpha_x, pi, -1.0, sigma_ex)
 }
 
 // Fixture: returns input with negative sigma_ex
-fn input_with_negative_sigma_ex() -> (usize, f64, f64, f64) {
-    let (n, alpha_x, sigma_a, _) = valid_input();
-    (n, alpha_x, sigma_a, -0.5)
+fn input_with_negative_sigma_ex() -> (usize, f64, Vec<f64>, f64, f64) {
+    let (n, alpha_x, pi, sigma_a, _) = valid_input();
+    (n, alpha_x, pi, sigma_a, -0.5)
 }
 
 //Fixture: retuns input with negative sigma_ey
-fn input_with_negative_sigma_ey() -> (f64, f64, f64, IndVars) {
-    let (beta, alpha_y, _, ind_vars) = valid_input_y();
+fn input_with_negative_sigma_ey(rng: &mut StdRng) -> (f64, f64, f64, IndVars) {
+    let (beta, alpha_y, _, ind_vars) = valid_input_y(rng);
     (beta, alpha_y, -1.0, ind_vars)
 }
 
 #[test]
 fn test_errors_on_negative_sigma_a() {
-    let (n, alpha_x, sigma_a, sigma_ex) = input_with_negative_sigma_a();
-    let result = ind_var_generate(n, alpha_x, sigma_a, sigma_ex);
+    let mut rng = seeded_rng(1);
+    let (n, alpha_x, pi, sigma_a, sigma_ex) = input_with_negative_sigma_a();
+    let result = ind_var_generate(n, alpha_x, &pi, sigma_a, sigma_ex, &NORMAL, &NORMAL, &mut rng);
     assert!(result.is_err(), "Expected error for negative sigma_a, but got Ok");
     assert_eq!(result.unwrap_err(), "sigma_a must be positive");
 }
 
 #[test]
 fn test_errors_on_negative_sigma_ex() {
-    let (n, alpha_x, sigma_a, sigma_ex) = input_with_negative_sigma_ex();
-    let result = ind_var_generate(n, alpha_x, sigma_a, sigma_ex);
+    let mut rng = seeded_rng(2);
+    let (n, alpha_x, pi, sigma_a, sigma_ex) = input_with_negative_sigma_ex();
+    let result = ind_var_generate(n, alpha_x, &pi, sigma_a, sigma_ex, &NORMAL, &NORMAL, &mut rng);
     assert!(result.is_err(), "Expected error for negative sigma_ex, but got Ok");
     assert_eq!(result.unwrap_err(), "sigma_ex must be positive");
 }
 
 #[test]
 fn test_errors_on_negative_sigma_ey() {
-    let (beta, alpha_y, sigma_ey, ind_vars) = input_with_negative_sigma_ey();
-    let result = dep_var_generate(beta, alpha_y, sigma_ey, ind_vars);
+    let mut rng = seeded_rng(3);
+    let (beta, alpha_y, sigma_ey, ind_vars) = input_with_negative_sigma_ey(&mut rng);
+    let result = dep_var_generate(beta, alpha_y, sigma_ey, &NORMAL, ind_vars, &mut rng);
     assert!(result.is_err(), "Expected error for negative sigma_ex, but got Ok");
     assert_eq!(result.unwrap_err(), "sigma_ey must be positive");
 }
 
 #[test]
 fn test_x_shape_matches_v() {
-    let data = generate_valid_data();
+    let mut rng = seeded_rng(4);
+    let data = generate_valid_data(&mut rng);
     assert_eq!(data.x.shape(), data.v.shape());
 }
 
 #[test]
 fn test_y_shape_matches_v() {
-    let data = generate_valid_data_y();
+    let mut rng = seeded_rng(5);
+    let data = generate_valid_data_y(&mut rng);
     assert_eq!(data.y.shape(), data.ind_vars.v.shape());
 }
 
 #[test]
-fn test_x_value() {
-    let data = generate_valid_data();
+fn test_z_shape_matches_pi() {
+    let mut rng = seeded_rng(6);
+    let data = generate_valid_data(&mut rng);
+    assert_eq!(data.z.shape(), &[10, 2]);
+}
+
+#[test]
+fn test_no_instruments_when_pi_empty() {
+    let mut rng = seeded_rng(7);
+    let data = ind_var_generate(10, 2.5, &[], 1.0, 0.5, &NORMAL, &NORMAL, &mut rng).unwrap();
+    assert_eq!(data.z.shape(), &[10, 0]);
     let x_expected = &data.v * data.alpha_x + &data.e_x;
     assert_eq!(data.x, x_expected);
 }
 
+#[test]
+fn test_x_value() {
+    let mut rng = seeded_rng(8);
+    let data = generate_valid_data(&mut rng);
+    let x_expected = &data.v * data.alpha_x + &data.z.column(0).insert_axis(Axis(1)) * data.pi[0] + &data.z.column(1).insert_axis(Axis(1)) * data.pi[1] + &data.e_x;
+    for (x, expected) in data.x.iter().zip(x_expected.iter()) {
+        assert!((x - expected).abs() < 1e-12, "Expected x = {}, got {}", expected, x);
+    }
+}
+
 #[test]
 fn test_ind_var_values() {
-    let data = generate_valid_data();
+    let mut rng = seeded_rng(9);
+    let data = generate_valid_data(&mut rng);
     assert_eq!(data.alpha_x, 2.5);
+    assert_eq!(data.pi, vec![1.0, 0.5]);
     assert_eq!(data.sigma_a, 1.0);
     assert_eq!(data.sigma_ex, 0.5);
 }
 
 #[test]
 fn test_y_value() {
-    let data = generate_valid_data_y();
+    let mut rng = seeded_rng(10);
+    let data = generate_valid_data_y(&mut rng);
     let y_expected = &data.ind_vars.x * data.beta + &data.ind_vars.v * data.alpha_y + &data.e_y;
     assert_eq!(data.y, y_expected);
 }
 
 #[test]
 fn test_dep_var_values() {
-    let data = generate_valid_data_y();
+    let mut rng = seeded_rng(11);
+    let data = generate_valid_data_y(&mut rng);
     assert_eq!(data.beta, 0.5);
     assert_eq!(data.alpha_y, 1.5);
     assert_eq!(data.sigma_ey, 1.0);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_iv_regression_recovers_beta() {
+    let mut rng = seeded_rng(12);
+    let params = SimulationParams::builder().n(100000).pi(vec![1.0]).build().unwrap();
+    let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+    let iv_model = run_iv_regression(&generated_data, &CovarianceType::Classical, false).unwrap();
+    let (biased_model, _, bias) = run_other_regressions(&generated_data, &CovarianceType::Classical, false).unwrap();
+
+    assert!((iv_model.params()[0] + 0.5).abs() < 1e-1, "Expected IV estimate close to -0.5, got {}", iv_model.params()[0]);
+    assert!((biased_model.params()[0] + 0.5 - bias).abs() < 1e-1, "Expected OLS bias close to {}, got {}", bias, biased_model.params()[0] + 0.5);
+}
+
+#[test]
+fn test_iv_regression_errors_without_instruments() {
+    let mut rng = seeded_rng(13);
+    let params = SimulationParams::builder().n(100).pi(vec![]).build().unwrap();
+    let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+    let result = run_iv_regression(&generated_data, &CovarianceType::Classical, false);
+    assert_eq!(result.unwrap_err(), "The generated data contain no instruments");
+}
+
+#[test]
+fn test_k_class_regressions_order() {
+    let mut rng = seeded_rng(14);
+    let params = SimulationParams::builder().n(500).pi(vec![0.5, 0.5, 0.5]).build().unwrap();
+    let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+    let (tsls_model, liml_model, fuller_model) = run_k_class_regressions(&generated_data, &CovarianceType::Classical, false).unwrap();
+    assert_eq!(tsls_model.k(), 1.0);
+    assert!(liml_model.k() >= 1.0, "Expected LIML kappa >= 1, got {}", liml_model.k());
+    assert!(fuller_model.k() < liml_model.k(), "Expected Fuller k below LIML kappa");
+}
+
+#[test]
+fn test_jive_regressions_errors_without_instruments() {
+    let mut rng = seeded_rng(15);
+    let params = SimulationParams::builder().n(100).pi(vec![]).build().unwrap();
+    let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+    let result = run_jive_regressions(&generated_data, false);
+    assert_eq!(result.unwrap_err(), "The generated data contain no instruments");
+}
+
+#[test]
+fn test_simulation_params_defaults_match_main() {
+    let params = SimulationParams::default();
+    assert_eq!(params.n(), 10000);
+    assert_eq!(params.beta(), -0.5);
+    assert_eq!(params.alpha_y(), 4.5);
+    assert_eq!(params.alpha_x(), 2.5);
+    assert_eq!((params.sigma_a(), params.sigma_ex(), params.sigma_ey()), (1.0, 1.0, 1.0));
+    assert!(!params.intercept());
+    assert!(params.pi().is_empty());
+    assert_eq!(SimulationParams::builder().build().unwrap(), params);
+}
+
+#[test]
+fn test_simulation_params_builder_sets_named_fields() {
+    let params = SimulationParams::builder().alpha_y(1.5).alpha_x(0.5).intercept(true).build().unwrap();
+    assert_eq!(params.alpha_y(), 1.5);
+    assert_eq!(params.alpha_x(), 0.5);
+    assert!(params.intercept());
+
+    let variant = params.to_builder().n(50).build().unwrap();
+    assert_eq!(variant.n(), 50);
+    assert_eq!(variant.alpha_y(), 1.5);
+    assert!(variant.intercept());
+}
+
+#[test]
+fn test_simulation_params_reports_every_invalid_field() {
+    let message = SimulationParams::builder()
+        .n(0)
+        .alpha_x(f64::NAN)
+        .sigma_a(0.0)
+        .sigma_ey(-1.0)
+        .pi(vec![1.0, f64::INFINITY])
+        .build()
+        .unwrap_err();
+    for expected in ["n must be positive", "alpha_x must be finite", "sigma_a must be positive", "sigma_ey must be positive", "pi[1] must be finite"] {
+        assert!(message.contains(expected), "Expected '{}' in '{}'", expected, message);
+    }
+    assert!(!message.contains("sigma_ex"));
+}
+
+#[test]
+fn test_run_yxv_regression_uses_params() {
+    let mut rng = seeded_rng(16);
+    let params = SimulationParams::builder().n(50).pi(vec![0.5, 0.5]).intercept(true).build().unwrap();
+    let (model, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+    assert_eq!(generated_data.y.nrows(), 50);
+    assert_eq!(generated_data.z.ncols(), 2);
+    assert_eq!(generated_data.alpha_y, 4.5);
+    assert_eq!(model.covariance().nrows(), 3);
+}
+
+#[test]
+fn test_same_seed_gives_identical_data() {
+    let params = SimulationParams::builder().n(200).pi(vec![0.5, 0.5]).build().unwrap();
+    let (first_model, first) = run_yxv_regression(&params, &CovarianceType::HC1, &mut seeded_rng(2024)).unwrap();
+    let (second_model, second) = run_yxv_regression(&params, &CovarianceType::HC1, &mut seeded_rng(2024)).unwrap();
+    assert_eq!(first.y, second.y);
+    assert_eq!(first.x, second.x);
+    assert_eq!(first.z, second.z);
+    assert_eq!(first_model.params(), second_model.params());
+
+    let (_, other) = run_yxv_regression(&params, &CovarianceType::HC1, &mut seeded_rng(2025)).unwrap();
+    assert_ne!(first.y, other.y);
+}
+
+// Fixture: the sample correlation of the two error terms
+fn error_correlation(dep_vars: &DepVars) -> f64 {
+    let e_x = dep_vars.ind_vars.e_x.column(0);
+    let e_y = dep_vars.e_y.column(0);
+    let (mean_x, mean_y) = (e_x.mean().unwrap(), e_y.mean().unwrap());
+    let cov = e_x.iter().zip(e_y.iter()).map(|(a, b)| (a - mean_x) * (b - mean_y)).sum::<f64>();
+    let var_x = e_x.iter().map(|a| (a - mean_x).powi(2)).sum::<f64>();
+    let var_y = e_y.iter().map(|b| (b - mean_y).powi(2)).sum::<f64>();
+    cov / (var_x * var_y).sqrt()
+}
+
+#[test]
+fn test_joint_var_generate_error_correlation() {
+    let mut rng = seeded_rng(17);
+    let params = SimulationParams::builder().n(100000).sigma_ex(0.5).sigma_ey(2.0).rho(0.6).build().unwrap();
+    let dep_vars = joint_var_generate(&params, &mut rng).unwrap();
+    assert_eq!(dep_vars.rho, 0.6);
+    assert_eq!(dep_vars.y.nrows(), 100000);
+    let correlation = error_correlation(&dep_vars);
+    assert!((correlation - 0.6).abs() < 1e-2, "Expected correlation 0.6 but got {}", correlation);
+    let sd_ey = dep_vars.e_y.std(1.0);
+    assert!((sd_ey - 2.0).abs() < 2e-2, "Expected standard deviation 2.0 but got {}", sd_ey);
+
+    // y and x are still built from the drawn errors
+    let fitted = dep_vars.beta * &dep_vars.ind_vars.x + dep_vars.alpha_y * &dep_vars.ind_vars.v + &dep_vars.e_y;
+    assert!(dep_vars.y.iter().zip(fitted.iter()).all(|(a, b)| (a - b).abs() < 1e-12));
+}
+
+#[test]
+fn test_joint_var_generate_uncorrelated_by_default() {
+    let mut rng = seeded_rng(18);
+    let params = SimulationParams::builder().n(100000).build().unwrap();
+    let dep_vars = joint_var_generate(&params, &mut rng).unwrap();
+    let correlation = error_correlation(&dep_vars);
+    assert!(correlation.abs() < 1e-2, "Expected no correlation but got {}", correlation);
+}
+
+#[test]
+fn test_ols_bias_formula_with_correlated_errors() {
+    let mut rng = seeded_rng(19);
+    let params = SimulationParams::builder().n(200000).alpha_y(0.0).rho(0.5).pi(vec![1.0]).build().unwrap();
+    let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::Classical, &mut rng).unwrap();
+    let (ols_model, _, bias) = run_other_regressions(&generated_data, &CovarianceType::Classical, false).unwrap();
+    let observed = ols_model.params()[0] - params.beta();
+    assert!(bias > 0.0);
+    assert!((observed - bias).abs() < 1e-2, "Expected bias {} but got {}", bias, observed);
+
+    // 2SLS is unaffected by the correlated errors
+    let iv_model = run_iv_regression(&generated_data, &CovarianceType::Classical, false).unwrap();
+    assert!((iv_model.params()[0] - params.beta()).abs() < 2e-2);
+}
+
+#[test]
+fn test_simulation_params_rejects_perfect_correlation() {
+    let message = SimulationParams::builder().rho(1.0).build().unwrap_err();
+    assert!(message.contains("rho must be strictly between -1 and 1"));
+    assert!(SimulationParams::builder().rho(-0.99).build().is_ok());
+}
+
+#[test]
+fn test_generators_use_the_given_distributions() {
+    let mut rng = seeded_rng(20);
+    let heavy = ErrorDistribution::StudentT { df: 4.0 };
+    let skewed = ErrorDistribution::SkewNormal { shape: 3.0 };
+    let ind_vars = ind_var_generate(20, 2.5, &[1.0], 1.0, 0.5, &heavy, &NORMAL, &mut rng).unwrap();
+    assert_eq!(ind_vars.v_distribution, heavy);
+    assert_eq!(ind_vars.ex_distribution, NORMAL);
+    let dep_vars = dep_var_generate(-0.5, 1.5, 1.0, &skewed, ind_vars, &mut rng).unwrap();
+    assert_eq!(dep_vars.ey_distribution, skewed);
+
+    let invalid = ErrorDistribution::LogNormal { log_sd: -1.0 };
+    let result = ind_var_generate(20, 2.5, &[1.0], 1.0, 0.5, &NORMAL, &invalid, &mut rng);
+    assert!(result.unwrap_err().contains("Invalid distribution of e_x"));
+}
+
+#[test]
+fn test_simulation_params_distributions() {
+    let laplace = ErrorDistribution::Laplace;
+    let params = SimulationParams::builder().distribution(laplace.clone()).build().unwrap();
+    assert_eq!((params.v_distribution(), params.ex_distribution(), params.ey_distribution()), (&laplace, &laplace, &laplace));
+
+    let message = SimulationParams::builder().ey_distribution(laplace).rho(0.3).build().unwrap_err();
+    assert!(message.contains("rho must be zero unless e_x and e_y are normal"));
+    let message = SimulationParams::builder().v_distribution(ErrorDistribution::StudentT { df: 1.0 }).build().unwrap_err();
+    assert!(message.contains("the distribution of v is invalid"));
+}
+
+#[test]
+fn test_iv_regression_recovers_beta_with_heavy_tailed_errors() {
+    let mut rng = seeded_rng(21);
+    for distribution in [ErrorDistribution::StudentT { df: 3.0 }, ErrorDistribution::LogNormal { log_sd: 1.0 }] {
+        let params = SimulationParams::builder().n(100000).pi(vec![1.0]).distribution(distribution.clone()).build().unwrap();
+        let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::HC1, &mut rng).unwrap();
+        let iv_model = run_iv_regression(&generated_data, &CovarianceType::HC1, false).unwrap();
+        assert!((iv_model.params()[0] - params.beta()).abs() < 0.1, "2SLS estimate {} under {}", iv_model.params()[0], distribution);
+
+        // the bias formula depends only on the variances, so it still holds
+        let (ols_model, _, bias) = run_other_regressions(&generated_data, &CovarianceType::HC1, false).unwrap();
+        assert!((ols_model.params()[0] - params.beta() - bias).abs() < 5e-2);
+    }
+}
+
+#[test]
+fn test_joint_var_generate_with_copula() {
+    let mut rng = seeded_rng(22);
+    let copula = Copula::Clayton { theta: 2.0 };
+    let params = SimulationParams::builder()
+        .n(20000)
+        .sigma_a(2.0)
+        .v_distribution(ErrorDistribution::Laplace)
+        .ey_distribution(ErrorDistribution::LogNormal { log_sd: 0.5 })
+        .copula(copula.clone())
+        .build()
+        .unwrap();
+    let dep_vars = joint_var_generate(&params, &mut rng).unwrap();
+    assert_eq!(dep_vars.copula, Some(copula));
+
+    // the marginals keep their standard deviations, and the log-normal its lower bound
+    assert!((dep_vars.ind_vars.v.std(1.0) - 2.0).abs() < 5e-2);
+    assert!((dep_vars.e_y.std(1.0) - 1.0).abs() < 5e-2);
+    let shape = ErrorDistribution::LogNormal { log_sd: 0.5 }.standardized().unwrap();
+    assert!(dep_vars.e_y.iter().all(|e| *e > shape.quantile(1e-12) - 1e-9));
+
+    // the confounder now moves with both errors
+    let v = dep_vars.ind_vars.v.column(0);
+    for errors in [&dep_vars.ind_vars.e_x, &dep_vars.e_y] {
+        let e = errors.column(0);
+        let covariance = v.iter().zip(e.iter()).map(|(a, b)| a * b).sum::<f64>() / v.len() as f64;
+        assert!(covariance > 0.3, "Expected dependent unobservables but got covariance {}", covariance);
+    }
+}
+
+#[test]
+fn test_simulation_params_copula() {
+    let params = SimulationParams::builder().copula(Copula::Gumbel { theta: 2.0 }).build().unwrap();
+    assert_eq!(params.copula(), Some(&Copula::Gumbel { theta: 2.0 }));
+    assert_eq!(SimulationParams::default().copula(), None);
+
+    let message = SimulationParams::builder().copula(Copula::Gaussian { correlation: ndarray::Array2::eye(2) }).rho(0.5).build().unwrap_err();
+    assert!(message.contains("the copula is invalid: The copula correlation must be 3 by 3"));
+    assert!(message.contains("rho must be zero when a copula is set"));
+}
+
+#[test]
+fn test_iv_regression_recovers_beta_with_copula_dependence() {
+    let mut rng = seeded_rng(23);
+    let params = SimulationParams::builder()
+        .n(50000)
+        .pi(vec![1.0])
+        .alpha_x(0.5)
+        .distribution(ErrorDistribution::StudentT { df: 5.0 })
+        .copula(Copula::Gumbel { theta: 3.0 })
+        .build()
+        .unwrap();
+    let (_, generated_data) = run_yxv_regression(&params, &CovarianceType::HC1, &mut rng).unwrap();
+    let iv_model = run_iv_regression(&generated_data, &CovarianceType::HC1, false).unwrap();
+    assert!((iv_model.params()[0] - params.beta()).abs() < 0.05, "2SLS estimate {}", iv_model.params()[0]);
+    let (ols_model, _, _) = run_other_regressions(&generated_data, &CovarianceType::HC1, false).unwrap();
+    assert!(ols_model.params()[0] - params.beta() > 0.5);
+}
@@ -0,0 +1,133 @@
+use marketing_iv_methods::utils::bayesian_iv::RivGibbsDraws;
+use marketing_iv_methods::utils::control_function::run_control_function;
+use marketing_iv_methods::utils::covariance::{CovarianceType, HacKernel};
+use marketing_iv_methods::utils::gmm::{run_gmm, GmmWeighting};
+use marketing_iv_methods::utils::jive::{run_jive, JiveVariant};
+use marketing_iv_methods::utils::linear_regression::{run_2sls, run_regression};
+use marketing_iv_methods::utils::random_vector_gen::seeded_rng;
+use marketing_iv_methods::utils::summary::{RegressionSummary, Summarize};
+use ndarray::{array, concatenate, Array1, Array2, Array3, Axis};
+use ndarray_rand::rand::rngs::StdRng;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: one endogenous regressor, one control and a strong instrument
+fn summary_design(n: usize, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>, Array2<f64>) {
+    let data = IvDesign::new(n).w_x(0.5).w_y(0.8).sigma_ex(0.0).intercept(1.0).generate(rng);
+    (data.y, data.x, data.w, data.z)
+}
+
+#[test]
+fn test_ols_summary_matches_fit() {
+    let mut rng = seeded_rng(1);
+    let (y, x, w, _) = summary_design(200, &mut rng);
+    let regressors = concatenate(Axis(1), &[x.view(), w.view()]).unwrap();
+    let fit = run_regression(&regressors, &y, &CovarianceType::HC1, true).unwrap();
+    let summary = fit.summary("y", &["x", "w"]).unwrap();
+
+    assert_eq!(summary.names(), vec!["const", "x", "w"]);
+    assert_eq!(summary.params()[0], fit.intercept());
+    let ci = fit.conf_int(0.95).unwrap();
+    for i in 0..2 {
+        assert_eq!(summary.params()[i + 1], fit.params()[i]);
+        assert_eq!(summary.std_errors()[i + 1], Some(fit.std_errors()[i]));
+        assert!((summary.p_values()[i + 1].unwrap() - fit.p_values()[i]).abs() < 1e-12);
+        let (lower, upper) = summary.intervals()[i + 1].unwrap();
+        assert!((lower - ci[[i, 0]]).abs() < 1e-12 && (upper - ci[[i, 1]]).abs() < 1e-12);
+    }
+
+    let report = summary.to_string();
+    assert!(report.contains("OLS Regression Results"));
+    assert!(report.contains("P>|t|"));
+    assert!(report.contains("No. Observations:") && report.contains("200"));
+    assert!(report.contains("HC1"));
+    assert!(!report.contains("shape="));
+}
+
+#[test]
+fn test_iv_and_jive_summaries() {
+    let mut rng = seeded_rng(2);
+    let (y, x, w, z) = summary_design(200, &mut rng);
+    let tsls = run_2sls(&y, &x, &w, &z, &CovarianceType::Classical, false).unwrap();
+    let summary = tsls.summary("y", &["x", "w"]).unwrap();
+    assert_eq!(summary.names(), vec!["x", "w"]);
+    let report = summary.to_string();
+    assert!(report.contains("P>|z|") && report.contains("2SLS"));
+
+    let jive = run_jive(&y, &x, &w, &z, JiveVariant::Ujive, true).unwrap();
+    let summary = jive.summary("y", &["x", "w"]).unwrap();
+    assert_eq!(summary.names(), vec!["const", "x", "w"]);
+    assert!(summary.std_errors().iter().all(|se| se.is_none()));
+    assert!(summary.to_string().contains("Ujive"));
+}
+
+#[test]
+fn test_gmm_and_control_function_intercept_rows() {
+    let mut rng = seeded_rng(5);
+    let (y, x, w, z) = summary_design(200, &mut rng);
+
+    // the intercept row follows how the model was fit, not the fitted value
+    let gmm = run_gmm(&y, &x, &w, &z, GmmWeighting::TwoStep, &CovarianceType::HC0, true).unwrap();
+    let summary = gmm.summary("y", &["x", "w"]).unwrap();
+    assert_eq!(summary.names(), vec!["const", "x", "w"]);
+    assert_eq!(summary.std_errors()[0], gmm.intercept_std_error());
+    assert_eq!(summary.std_errors()[1], Some(gmm.std_errors()[0]));
+    assert!(summary.p_values().iter().all(|p| p.is_some()));
+    let gmm = run_gmm(&y, &x, &w, &z, GmmWeighting::TwoStep, &CovarianceType::HC0, false).unwrap();
+    assert_eq!(gmm.summary("y", &["x", "w"]).unwrap().names(), vec!["x", "w"]);
+
+    let control_function = run_control_function(&y, &x, &w, &z, &CovarianceType::HC0, true).unwrap();
+    let summary = control_function.summary("y", &["x", "w"]).unwrap();
+    assert_eq!(summary.names(), vec!["const", "x", "w", "x_resid"]);
+    assert_eq!(summary.params()[0], control_function.intercept());
+    assert_eq!(summary.std_errors()[0], control_function.intercept_std_error());
+    assert!(summary.std_errors()[0].unwrap() > 0.0);
+    let control_function = run_control_function(&y, &x, &w, &z, &CovarianceType::HC0, false).unwrap();
+    assert!(control_function.intercept_std_error().is_none());
+    assert_eq!(control_function.summary("y", &["x", "w"]).unwrap().names(), vec!["x", "w", "x_resid"]);
+}
+
+#[test]
+fn test_posterior_summary() {
+    let draws = RivGibbsDraws {
+        beta: Array1::linspace(-1.0, 0.0, 101),
+        gamma: Array2::from_shape_fn((101, 1), |(i, _)| i as f64),
+        delta: Array2::zeros((101, 1)),
+        sigma: Array3::zeros((101, 2, 2)),
+    };
+    let summary = draws.summary("y", &["x"]).unwrap();
+    assert_eq!(summary.names(), vec!["const", "x"]);
+    assert!((summary.params()[1] + 0.5).abs() < 1e-12);
+    let (lower, upper) = summary.intervals()[1].unwrap();
+    assert!(lower < -0.95 && upper > -0.05);
+    assert!(summary.p_values().iter().all(|p| p.is_none()));
+    assert!(draws.summary("y", &["x", "w", "u"]).is_err());
+}
+
+#[test]
+fn test_summary_rejects_wrong_names() {
+    let mut rng = seeded_rng(3);
+    let (y, x, w, _) = summary_design(50, &mut rng);
+    let regressors = concatenate(Axis(1), &[x.view(), w.view()]).unwrap();
+    let fit = run_regression(&regressors, &y, &CovarianceType::Classical, false).unwrap();
+    assert!(fit.summary("y", &["x"]).is_err());
+}
+
+#[test]
+fn test_manual_summary_and_covariance_labels() {
+    let mut summary = RegressionSummary::new("Custom Results", None);
+    summary.add_statistic("Covariance Type", CovarianceType::Cluster(array![0, 0, 1, 1]));
+    summary.add_coefficient("a_long_coefficient_name", 1.0, Some(0.5)).unwrap();
+    summary.add_coefficient("b", 2.0, None).unwrap();
+    assert!((summary.p_values()[0].unwrap() - 0.0455).abs() < 1e-3);
+    assert_eq!(summary.p_values()[1], None);
+    assert_eq!(summary.statistics()[0].1, "cluster");
+
+    let report = summary.to_string();
+    let widths: Vec<usize> = report.lines().filter(|line| line.starts_with('=')).map(|line| line.len()).collect();
+    assert!(widths.iter().all(|w| *w == widths[0] && *w > 78));
+
+    let hac = CovarianceType::Hac { kernel: HacKernel::Bartlett, bandwidth: None };
+    assert_eq!(hac.to_string(), "HAC (Bartlett, auto)");
+}
@@ -0,0 +1,136 @@
+use marketing_iv_methods::utils::covariance::CovarianceType;
+use marketing_iv_methods::utils::linear_regression::run_regression;
+use marketing_iv_methods::utils::random_vector_gen::{random_vector_generate, seeded_rng};
+use marketing_iv_methods::utils::weak_iv::{anderson_rubin_confidence_set, clr_confidence_set, run_anderson_rubin, run_clr, ConfidenceSet};
+use ndarray::{concatenate, Array1, Array2, Axis};
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand_distr::Normal;
+
+mod common;
+use common::IvDesign;
+
+// Fixture: x = pi * sum(z) + 2v + e_x and y = -0.5x + 3v + e_y, with `k` instruments of
+// strength `pi`
+fn design(n: usize, k: usize, pi: f64, rng: &mut StdRng) -> (Array2<f64>, Array2<f64>, Array2<f64>) {
+    let data = IvDesign::new(n).pi(vec![pi; k]).alpha_x(2.0).alpha_y(3.0).generate(rng);
+    (data.y, data.x, data.z)
+}
+
+#[test]
+fn test_confidence_set_merges_overlapping_intervals() {
+    let set = ConfidenceSet::new(vec![(2.0, 3.0), (0.0, 1.0), (0.5, 2.5), (5.0, 4.0)]);
+    assert_eq!(set.intervals(), &[(0.0, 3.0)]);
+    assert!(set.is_bounded() && !set.is_empty());
+    assert_eq!(ConfidenceSet::new(vec![]).to_string(), "empty set");
+}
+
+#[test]
+fn test_clr_equals_ar_with_one_instrument() {
+    let mut rng = seeded_rng(1);
+    let (y, x, z) = design(300, 1, 0.5, &mut rng);
+    let w = Array2::zeros((300, 0));
+    let ar = run_anderson_rubin(&y, &x, &w, &z, 0.0, true).unwrap();
+    let clr = run_clr(&y, &x, &w, &z, 0.0, true).unwrap();
+    assert!((ar.statistic() - clr.statistic()).abs() < 1e-8, "Expected equal statistics, got {} and {}", ar.statistic(), clr.statistic());
+}
+
+#[test]
+fn test_strong_instruments_give_bounded_sets_around_beta() {
+    let mut rng = seeded_rng(2);
+    // a single instrument, so the Anderson-Rubin set can never be empty
+    let (y, x, z) = design(2000, 1, 1.0, &mut rng);
+    let w = Array2::zeros((2000, 0));
+    let ar_set = anderson_rubin_confidence_set(&y, &x, &w, &z, 0.95, true).unwrap();
+    let clr_set = clr_confidence_set(&y, &x, &w, &z, &Array1::linspace(-2.0, 1.0, 61), 0.95, true).unwrap();
+    for set in [&ar_set, &clr_set] {
+        assert_eq!(set.intervals().len(), 1, "Expected a single interval, got {}", set);
+        assert!(set.is_bounded(), "Expected a bounded set, got {}", set);
+        assert!(set.intervals()[0].1 - set.intervals()[0].0 < 0.5, "Expected a short interval, got {}", set);
+    }
+}
+
+#[test]
+fn test_irrelevant_instruments_give_the_real_line() {
+    let mut rng = seeded_rng(3);
+    // instruments made exactly orthogonal to y and x carry no information about beta
+    let (y, x, noise) = design(500, 2, 0.0, &mut rng);
+    let yx = concatenate(Axis(1), &[y.view(), x.view()]).unwrap();
+    let mut z = Array2::zeros((500, 2));
+    for j in 0..2 {
+        let z_j = noise.column(j).to_owned().insert_axis(Axis(1));
+        let fit = run_regression(&yx, &z_j, &CovarianceType::Classical, true).unwrap();
+        z.column_mut(j).assign(&(&z_j.column(0) - &yx.dot(fit.params()) - fit.intercept()));
+    }
+    let w = Array2::zeros((500, 0));
+    let ar_set = anderson_rubin_confidence_set(&y, &x, &w, &z, 0.95, true).unwrap();
+    let clr_set = clr_confidence_set(&y, &x, &w, &z, &Array1::linspace(-3.0, 2.0, 51), 0.95, true).unwrap();
+    for set in [&ar_set, &clr_set] {
+        assert_eq!(set.intervals(), &[(f64::NEG_INFINITY, f64::INFINITY)], "Expected the real line, got {}", set);
+    }
+}
+
+#[test]
+fn test_clr_set_agrees_with_test() {
+    let mut rng = seeded_rng(4);
+    let (y, x, z) = design(200, 4, 0.1, &mut rng);
+    let w = Array2::zeros((200, 0));
+    let set = clr_confidence_set(&y, &x, &w, &z, &Array1::linspace(-3.0, 2.0, 101), 0.95, true).unwrap();
+    for beta_0 in Array1::linspace(-5.0, 5.0, 37).iter() {
+        let accepted = run_clr(&y, &x, &w, &z, *beta_0, true).unwrap().p_value() > 0.05;
+        assert_eq!(set.contains(*beta_0), accepted, "Set {} disagrees with the test at {}", set, beta_0);
+    }
+}
+
+#[test]
+fn test_clr_set_finds_rejection_gap_beyond_grid() {
+    let mut rng = seeded_rng(0);
+    let (y, x, z) = design(200, 3, 0.15, &mut rng);
+    let w = Array2::zeros((200, 0));
+    // the set is two disjoint rays, so a grid inside either ray near the gap must still find it
+    let wide = clr_confidence_set(&y, &x, &w, &z, &Array1::linspace(-10.0, 10.0, 401), 0.95, true).unwrap();
+    assert_eq!(wide.intervals().len(), 2, "Expected two rays, got {}", wide);
+    for grid in [Array1::linspace(-0.6, -0.2, 21), Array1::linspace(2.2, 2.6, 21)] {
+        let narrow = clr_confidence_set(&y, &x, &w, &z, &grid, 0.95, true).unwrap();
+        assert_eq!(narrow.intervals().len(), 2, "Expected two rays, got {}", narrow);
+        for (a, b) in narrow.intervals().iter().zip(wide.intervals().iter()) {
+            assert!((a.0 - b.0).abs() < 1e-6 || a.0 == b.0, "Expected {} to equal {}", narrow, wide);
+            assert!((a.1 - b.1).abs() < 1e-6 || a.1 == b.1, "Expected {} to equal {}", narrow, wide);
+        }
+    }
+}
+
+#[test]
+fn test_ar_set_agrees_with_test() {
+    let mut rng = seeded_rng(5);
+    let (y, x, z) = design(200, 4, 0.1, &mut rng);
+    let w = random_vector_generate(200, Normal::new(0.0, 1.0).unwrap(), &mut rng);
+    let set = anderson_rubin_confidence_set(&y, &x, &w, &z, 0.9, false).unwrap();
+    for beta_0 in Array1::linspace(-5.0, 5.0, 37).iter() {
+        let accepted = run_anderson_rubin(&y, &x, &w, &z, *beta_0, false).unwrap().p_value() > 0.1;
+        assert_eq!(set.contains(*beta_0), accepted, "Set {} disagrees with the test at {}", set, beta_0);
+    }
+}
+
+#[test]
+fn test_ar_size_under_weak_instruments() {
+    let mut rng = seeded_rng(6);
+    let replications = 400;
+    let mut rejections = 0;
+    for _ in 0..replications {
+        let (y, x, z) = design(100, 3, 0.05, &mut rng);
+        if run_anderson_rubin(&y, &x, &Array2::zeros((100, 0)), &z, -0.5, true).unwrap().p_value() < 0.05 {
+            rejections += 1;
+        }
+    }
+    let rate = rejections as f64 / replications as f64;
+    assert!(rate < 0.1, "Expected a rejection rate near 5%, got {}", rate);
+}
+
+#[test]
+fn test_errors_with_two_endogenous_regressors() {
+    let mut rng = seeded_rng(7);
+    let (y, x, z) = design(50, 3, 0.5, &mut rng);
+    let x2 = concatenate(Axis(1), &[x.view(), x.view()]).unwrap();
+    let result = run_clr(&y, &x2, &Array2::zeros((50, 0)), &z, 0.0, true);
+    assert_eq!(result.unwrap_err(), "Weak-instrument-robust inference requires exactly one endogenous regressor");
+}